@@ -0,0 +1,316 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{BlockQuoteKind, CowStr, Event, Tag, TagEnd};
+use regex::Regex;
+
+use crate::html::escape_html;
+use crate::pipeline::Transform;
+
+/// `::: kind [title]` opens a container, a bare `:::` closes it
+static CONTAINER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^:{3,}\s*([A-Za-z]*)\s*(.*)$").unwrap());
+
+/// Canonical kind, icon and default title for each supported container name
+fn admonition_kind(name: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    let kind = match name.to_ascii_lowercase().as_str() {
+        "note" => ("note", "📝", "说明"),
+        "info" => ("info", "ℹ️", "信息"),
+        "tip" | "hint" => ("tip", "💡", "提示"),
+        "important" => ("important", "❗", "重要"),
+        "warning" | "warn" => ("warning", "⚠️", "警告"),
+        "caution" => ("caution", "🔔", "注意"),
+        "danger" | "error" => ("danger", "🚫", "危险"),
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn blockquote_kind_name(kind: BlockQuoteKind) -> &'static str {
+    match kind {
+        BlockQuoteKind::Note => "note",
+        BlockQuoteKind::Tip => "tip",
+        BlockQuoteKind::Important => "important",
+        BlockQuoteKind::Warning => "warning",
+        BlockQuoteKind::Caution => "caution",
+    }
+}
+
+fn open_section(name: &str, title: &str) -> Option<String> {
+    let (kind, icon, default_title) = admonition_kind(name)?;
+    let title = if title.trim().is_empty() {
+        default_title
+    } else {
+        title.trim()
+    };
+    Some(format!(
+        "<section class=\"admonition admonition-{}\">\n<p class=\"admonition-title\"><span class=\"admonition-icon\">{}</span>{}</p>\n",
+        kind,
+        icon,
+        escape_html(title)
+    ))
+}
+
+const CLOSE_SECTION: &str = "</section>\n";
+
+enum Marker {
+    Open(String),
+    Close,
+}
+
+/// Classify one line of a paragraph as a container marker
+fn parse_marker(line: &[Event], open_containers: usize) -> Option<Marker> {
+    let mut text = String::new();
+    for event in line {
+        match event {
+            Event::Text(t) => text.push_str(t),
+            _ => return None,
+        }
+    }
+    let caps = CONTAINER_RE.captures(text.trim())?;
+    let name = &caps[1];
+    if name.is_empty() {
+        return (caps[2].trim().is_empty() && open_containers > 0).then_some(Marker::Close);
+    }
+    open_section(name, &caps[2]).map(Marker::Open)
+}
+
+fn is_container_tag(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::BlockQuote(_) | Tag::List(_) | Tag::Item | Tag::FootnoteDefinition(_)
+    )
+}
+
+fn is_container_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::BlockQuote(_) | TagEnd::List(_) | TagEnd::Item | TagEnd::FootnoteDefinition
+    )
+}
+
+/// Events that make up paragraph content. Tight list items hold these directly,
+/// without a surrounding paragraph.
+fn is_inline(event: &Event) -> bool {
+    match event {
+        Event::Start(tag) => matches!(
+            tag,
+            Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Superscript
+                | Tag::Subscript
+                | Tag::Link { .. }
+                | Tag::Image { .. }
+        ),
+        Event::End(tag) => matches!(
+            tag,
+            TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript
+                | TagEnd::Link
+                | TagEnd::Image
+        ),
+        Event::Rule | Event::Html(_) => false,
+        _ => true,
+    }
+}
+
+/// An open `:::` container: the block depth it was opened at, and whether its
+/// closing marker was already seen deeper in the tree (e.g. lazily inside a list)
+struct OpenContainer {
+    depth: usize,
+    closed: bool,
+}
+
+/// Renders `::: tip` style containers and GitHub `> [!NOTE]` alerts as themed
+/// sections with an icon and title
+pub struct AdmonitionTransform;
+
+impl AdmonitionTransform {
+    /// Split a paragraph on soft breaks and turn marker lines into section
+    /// boundaries; the remaining lines stay grouped in paragraphs. `wrap` is false
+    /// for tight list items, whose text is not wrapped in a paragraph.
+    fn rewrite_paragraph<'a>(
+        &self,
+        inline: Vec<Event<'a>>,
+        wrap: bool,
+        depth: usize,
+        stack: &mut Vec<OpenContainer>,
+        output: &mut Vec<Event<'a>>,
+    ) {
+        let mut lines: Vec<Vec<Event<'a>>> = vec![Vec::new()];
+        for event in inline {
+            if matches!(event, Event::SoftBreak) {
+                lines.push(Vec::new());
+            } else if let Some(line) = lines.last_mut() {
+                line.push(event);
+            }
+        }
+
+        let mut paragraph: Vec<Event<'a>> = Vec::new();
+        let flush = |paragraph: &mut Vec<Event<'a>>, output: &mut Vec<Event<'a>>| {
+            if paragraph.is_empty() {
+                return;
+            }
+            if wrap {
+                output.push(Event::Start(Tag::Paragraph));
+            }
+            output.append(paragraph);
+            if wrap {
+                output.push(Event::End(TagEnd::Paragraph));
+            }
+        };
+        for line in lines {
+            match parse_marker(&line, stack.len()) {
+                Some(Marker::Open(html)) => {
+                    flush(&mut paragraph, output);
+                    output.push(Event::Html(CowStr::from(html)));
+                    stack.push(OpenContainer {
+                        depth,
+                        closed: false,
+                    });
+                }
+                Some(Marker::Close) => {
+                    flush(&mut paragraph, output);
+                    if let Some(open) = stack.last_mut() {
+                        if open.depth == depth {
+                            stack.pop();
+                            output.push(Event::Html(CowStr::from(CLOSE_SECTION)));
+                        } else {
+                            open.closed = true;
+                        }
+                    }
+                }
+                None => {
+                    if !paragraph.is_empty() {
+                        paragraph.push(Event::SoftBreak);
+                    }
+                    paragraph.extend(line);
+                }
+            }
+        }
+        flush(&mut paragraph, output);
+    }
+}
+
+impl Transform for AdmonitionTransform {
+    fn name(&self) -> &'static str {
+        "admonitions"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        let mut output = Vec::with_capacity(events.len());
+        let mut stack: Vec<OpenContainer> = Vec::new();
+        let mut depth = 0usize;
+        let mut paragraph: Option<Vec<Event<'a>>> = None;
+        let mut tight: Vec<Event<'a>> = Vec::new();
+        // Headings, code blocks and tables hold their own inline content
+        let mut leaf_depth = 0usize;
+
+        for event in events {
+            if let Some(inline) = paragraph.as_mut() {
+                if matches!(event, Event::End(TagEnd::Paragraph)) {
+                    let inline = paragraph.take().unwrap_or_default();
+                    self.rewrite_paragraph(inline, true, depth, &mut stack, &mut output);
+                } else {
+                    inline.push(event);
+                }
+                continue;
+            }
+            if depth > 0 && leaf_depth == 0 && is_inline(&event) {
+                tight.push(event);
+                continue;
+            }
+            if !tight.is_empty() {
+                let inline = std::mem::take(&mut tight);
+                self.rewrite_paragraph(inline, false, depth, &mut stack, &mut output);
+            }
+
+            match event {
+                Event::Start(Tag::Paragraph) => paragraph = Some(Vec::new()),
+                Event::Start(Tag::BlockQuote(Some(kind))) => {
+                    let html = open_section(blockquote_kind_name(kind), "").unwrap_or_default();
+                    output.push(Event::Html(CowStr::from(html)));
+                    depth += 1;
+                }
+                Event::End(TagEnd::BlockQuote(Some(_))) => {
+                    output.push(Event::Html(CowStr::from(CLOSE_SECTION)));
+                    depth -= 1;
+                }
+                Event::Start(ref tag) if is_container_tag(tag) => {
+                    depth += 1;
+                    output.push(event);
+                }
+                Event::End(ref tag) if is_container_end(tag) => {
+                    depth -= 1;
+                    output.push(event);
+                    // A closing marker seen inside a nested block takes effect
+                    // once we are back at the level the container was opened
+                    while stack
+                        .last()
+                        .is_some_and(|open| open.closed && open.depth == depth)
+                    {
+                        stack.pop();
+                        output.push(Event::Html(CowStr::from(CLOSE_SECTION)));
+                    }
+                }
+                Event::Start(_) => {
+                    leaf_depth += 1;
+                    output.push(event);
+                }
+                Event::End(_) => {
+                    leaf_depth = leaf_depth.saturating_sub(1);
+                    output.push(event);
+                }
+                _ => output.push(event),
+            }
+        }
+
+        for _ in stack {
+            output.push(Event::Html(CowStr::from(CLOSE_SECTION)));
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Options, Parser};
+
+    fn render(markdown: &str) -> String {
+        let events =
+            AdmonitionTransform.apply(Parser::new_ext(markdown, Options::ENABLE_GFM).collect());
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        output
+    }
+
+    #[test]
+    fn test_colon_containers_with_and_without_blank_lines() {
+        let html = render(
+            "::: tip 小技巧\n先保存文件\n:::\n\n::: warning\n\n- 列表项\n:::\n\n::: unknown\n",
+        );
+
+        assert!(html.contains("<section class=\"admonition admonition-tip\">"));
+        assert!(html.contains("<span class=\"admonition-icon\">💡</span>小技巧</p>"));
+        assert!(html.contains("<p>先保存文件</p>\n</section>"));
+        assert!(html.contains("<span class=\"admonition-icon\">⚠️</span>警告</p>"));
+        assert!(html.contains("</ul>\n</section>"));
+        assert!(html.contains("<p>::: unknown</p>"));
+        assert_eq!(
+            html.matches("<section").count(),
+            html.matches("</section>").count()
+        );
+    }
+
+    #[test]
+    fn test_github_alerts_become_sections() {
+        let html = render("> [!NOTE]\n> 这是说明\n");
+        assert!(html.contains("<section class=\"admonition admonition-note\">"));
+        assert!(html.contains("说明</p>"));
+        assert!(html.contains("<p>这是说明</p>"));
+        assert!(!html.contains("<blockquote"));
+    }
+}
@@ -0,0 +1,481 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::image::prefix_image_url;
+use crate::models::{ConvertWarning, DocumentStats, ImageInfo, LinkInfo, OutlineItem};
+use crate::site::{PostContext, SiteProfile};
+use crate::workspace::Workspace;
+
+/// Reading speed for Chinese text, in characters per minute
+const CJK_CHARS_PER_MINUTE: f64 = 400.0;
+/// Reading speed for space-separated text, in words per minute
+const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// Maps byte offsets in the Markdown body back to 1-based source lines
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    base: usize,
+}
+
+impl LineIndex {
+    /// Index `source`; offsets passed to `line` are relative to `body`,
+    /// which must be a suffix of `source` (the text after front matter)
+    pub fn new(source: &str, body: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex {
+            line_starts,
+            base: source.len() - body.len(),
+        }
+    }
+
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= self.base + offset)
+    }
+}
+
+/// Settings the analysis needs to resolve image sources the same way the
+/// WeChat upload does
+pub struct AnalysisOptions<'a> {
+    pub image_prefix: String,
+    pub assets_dir: String,
+    pub workspace: &'a Workspace,
+    /// The saved post and its site's conventions; `None` for an unsaved file
+    pub site: Option<(SiteProfile, PostContext)>,
+    /// Emit a `<!--wxline:N-->` comment before each block so checks on the
+    /// final HTML can point back to the Markdown source
+    pub line_markers: bool,
+}
+
+/// HTML comment recording the source line of the block that follows it
+pub fn line_marker(line: usize) -> String {
+    format!("<!--wxline:{}-->", line)
+}
+
+fn is_block_start(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Start(
+            Tag::Paragraph
+                | Tag::Heading { .. }
+                | Tag::BlockQuote(_)
+                | Tag::CodeBlock(_)
+                | Tag::HtmlBlock
+                | Tag::List(_)
+                | Tag::Item
+                | Tag::Table(_)
+                | Tag::FootnoteDefinition(_)
+        )
+    )
+}
+
+/// Everything collected about a document besides its HTML
+#[derive(Default)]
+pub struct DocumentAnalysis {
+    pub outline: Vec<OutlineItem>,
+    pub stats: DocumentStats,
+    pub images: Vec<ImageInfo>,
+    pub links: Vec<LinkInfo>,
+    pub warnings: Vec<ConvertWarning>,
+}
+
+impl DocumentAnalysis {
+    fn warn(&mut self, kind: &str, message: String, line: usize) {
+        self.warnings.push(ConvertWarning {
+            kind: kind.to_string(),
+            message,
+            line,
+        });
+    }
+}
+
+/// Heading, image or link whose text is still being collected
+enum Open {
+    Heading {
+        index: usize,
+        level: u8,
+        line: usize,
+        id: Option<String>,
+        text: String,
+    },
+    Image(ImageInfo),
+    Link(LinkInfo),
+}
+
+/// Turn heading text into an anchor: lowercase letters and digits (any script)
+/// joined by hyphens
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}')
+}
+
+#[derive(Default)]
+struct TextCounter {
+    characters: usize,
+    cjk_chars: usize,
+    words: usize,
+    in_word: bool,
+}
+
+impl TextCounter {
+    fn add(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.in_word = false;
+                continue;
+            }
+            self.characters += 1;
+            if is_cjk(c) {
+                self.cjk_chars += 1;
+                self.in_word = false;
+            } else if c.is_alphanumeric() {
+                if !self.in_word {
+                    self.words += 1;
+                }
+                self.in_word = true;
+            } else {
+                self.in_word = false;
+            }
+        }
+    }
+
+    fn stats(&self) -> DocumentStats {
+        let minutes =
+            self.cjk_chars as f64 / CJK_CHARS_PER_MINUTE + self.words as f64 / WORDS_PER_MINUTE;
+        DocumentStats {
+            characters: self.characters,
+            words: self.cjk_chars + self.words,
+            reading_minutes: minutes.ceil() as u32,
+        }
+    }
+}
+
+fn resolve_image(
+    src: &str,
+    options: &AnalysisOptions,
+) -> Result<(&'static str, Option<String>), String> {
+    let lower = src.to_ascii_lowercase();
+    if lower.starts_with("data:") {
+        return Ok(("data", None));
+    }
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return Ok(("remote", Some(src.to_string())));
+    }
+    if src.starts_with("//") {
+        return Ok(("remote", Some(format!("https:{}", src))));
+    }
+
+    let Some((profile, post)) = &options.site else {
+        return match prefix_image_url(src, &options.image_prefix, &options.assets_dir) {
+            Some(url) => Ok(("remote", Some(url))),
+            None => Err(format!("当前文件尚未保存，无法解析本地图片: {}", src)),
+        };
+    };
+    let path = src.strip_prefix("file://").unwrap_or(src);
+    let resolved = profile.resolve_image(options.workspace, post, path);
+    match (resolved.local, resolved.url) {
+        (Some(local), _) => Ok(("local", Some(local.to_string_lossy().to_string()))),
+        (None, Some(url)) => Ok(("remote", Some(url))),
+        (None, None) => Err(format!("图片文件不存在: {}", src)),
+    }
+}
+
+/// Walk the parsed document once, collecting the outline, text statistics,
+/// images, links and warnings. Headings get an `id` so the outline anchors
+/// resolve in the HTML; all other events pass through unchanged.
+pub fn analyze_events<'a>(
+    events: impl Iterator<Item = (Event<'a>, Range<usize>)>,
+    lines: &LineIndex,
+    options: &AnalysisOptions,
+) -> (Vec<Event<'a>>, DocumentAnalysis) {
+    let mut output = Vec::new();
+    let mut analysis = DocumentAnalysis::default();
+    let mut counter = TextCounter::default();
+    let mut anchors: HashSet<String> = HashSet::new();
+    let mut open: Vec<Open> = Vec::new();
+    let mut code_block_depth = 0usize;
+
+    for (event, range) in events {
+        let line = lines.line(range.start);
+        if options.line_markers && is_block_start(&event) {
+            output.push(Event::Html(CowStr::from(line_marker(line))));
+        }
+        match &event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                open.push(Open::Heading {
+                    index: output.len(),
+                    level: *level as u8,
+                    line,
+                    id: id.as_ref().map(|id| id.to_string()),
+                    text: String::new(),
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(Open::Heading {
+                    index,
+                    level,
+                    line,
+                    id,
+                    text,
+                }) = open.pop()
+                {
+                    let text = text.trim().to_string();
+                    let base = id.unwrap_or_else(|| slugify(&text));
+                    let mut anchor = base.clone();
+                    let mut suffix = 1;
+                    while !anchors.insert(anchor.clone()) {
+                        anchor = format!("{}-{}", base, suffix);
+                        suffix += 1;
+                    }
+                    if let Event::Start(Tag::Heading { id, .. }) = &mut output[index] {
+                        *id = Some(CowStr::from(anchor.clone()));
+                    }
+                    if text.is_empty() {
+                        analysis.warn("heading-empty", "标题内容为空".to_string(), line);
+                    }
+                    analysis.outline.push(OutlineItem {
+                        level,
+                        text,
+                        anchor,
+                        line,
+                    });
+                }
+            }
+            Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) => {
+                open.push(Open::Image(ImageInfo {
+                    src: dest_url.trim().to_string(),
+                    alt: String::new(),
+                    title: title.to_string(),
+                    kind: String::new(),
+                    resolved: None,
+                    line,
+                }));
+            }
+            Event::End(TagEnd::Image) => {
+                if let Some(Open::Image(mut image)) = open.pop() {
+                    image.alt = image.alt.trim().to_string();
+                    match resolve_image(&image.src, options) {
+                        Ok((kind, resolved)) => {
+                            image.kind = kind.to_string();
+                            image.resolved = resolved;
+                        }
+                        Err(message) => {
+                            image.kind = "local".to_string();
+                            analysis.warn("image-unresolved", message, image.line);
+                        }
+                    }
+                    if image.alt.is_empty() {
+                        analysis.warn(
+                            "image-alt-empty",
+                            format!("图片缺少替代文字: {}", image.src),
+                            image.line,
+                        );
+                    }
+                    analysis.images.push(image);
+                }
+            }
+            Event::Start(Tag::Link {
+                dest_url, title, ..
+            }) => {
+                open.push(Open::Link(LinkInfo {
+                    url: dest_url.trim().to_string(),
+                    text: String::new(),
+                    title: title.to_string(),
+                    line,
+                }));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some(Open::Link(mut link)) = open.pop() {
+                    link.text = link.text.trim().to_string();
+                    if link.url.is_empty() {
+                        analysis.warn(
+                            "link-empty",
+                            format!("链接地址为空: {}", link.text),
+                            link.line,
+                        );
+                    }
+                    analysis.links.push(link);
+                }
+            }
+            Event::Start(Tag::CodeBlock(_)) => code_block_depth += 1,
+            Event::End(TagEnd::CodeBlock) => code_block_depth = code_block_depth.saturating_sub(1),
+            Event::Text(text) | Event::Code(text) => {
+                for item in open.iter_mut() {
+                    match item {
+                        Open::Heading { text: buffer, .. } => buffer.push_str(text),
+                        Open::Image(image) => image.alt.push_str(text),
+                        Open::Link(link) => link.text.push_str(text),
+                    }
+                }
+                // Image alt text is not read as prose
+                let in_image = open.iter().any(|item| matches!(item, Open::Image(_)));
+                if code_block_depth == 0 && !in_image {
+                    counter.add(text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => counter.in_word = false,
+            _ => {}
+        }
+        output.push(event);
+    }
+
+    for link in &analysis.links {
+        if let Some(anchor) = link.url.strip_prefix('#') {
+            if !anchor.is_empty() && !anchors.contains(anchor) {
+                analysis.warnings.push(ConvertWarning {
+                    kind: "anchor-not-found".to_string(),
+                    message: format!("页内链接指向不存在的标题: {}", link.url),
+                    line: link.line,
+                });
+            }
+        }
+    }
+
+    analysis.stats = counter.stats();
+    (output, analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::Parser;
+
+    fn analyze(source: &str, body: &str) -> (Vec<Event<'static>>, DocumentAnalysis) {
+        let workspace = Workspace::load(None);
+        let options = AnalysisOptions {
+            image_prefix: String::new(),
+            assets_dir: "assets".to_string(),
+            workspace: &workspace,
+            site: None,
+            line_markers: false,
+        };
+        let lines = LineIndex::new(source, body);
+        let (events, analysis) =
+            analyze_events(Parser::new(body).into_offset_iter(), &lines, &options);
+        let events = events.into_iter().map(|e| e.into_static()).collect();
+        (events, analysis)
+    }
+
+    #[test]
+    fn test_outline_anchors_and_source_lines() {
+        let source =
+            "---\ntitle: T\n---\n# 简介 Intro\n\n## Setup\n\n## Setup\n\n[跳转](#missing)\n";
+        let body = &source[16..];
+        let (events, analysis) = analyze(source, body);
+
+        let anchors: Vec<_> = analysis.outline.iter().map(|h| h.anchor.as_str()).collect();
+        assert_eq!(anchors, ["简介-intro", "setup", "setup-1"]);
+        assert_eq!(analysis.outline[0].line, 4);
+        assert_eq!(analysis.outline[2].level, 2);
+        assert!(events.iter().any(|e| matches!(
+            e,
+            Event::Start(Tag::Heading { id: Some(id), .. }) if id.as_ref() == "setup-1"
+        )));
+        assert_eq!(analysis.links.len(), 1);
+        assert_eq!(analysis.warnings[0].kind, "anchor-not-found");
+        assert_eq!(analysis.warnings[0].line, 10);
+    }
+
+    #[test]
+    fn test_stats_and_image_warnings() {
+        let body = "中文内容 hello world\n\n```\nnot counted\n```\n\n![](https://a.com/x.png) ![图](pic.png)\n";
+        let (_, analysis) = analyze(body, body);
+
+        assert_eq!(analysis.stats.words, 6);
+        assert_eq!(analysis.stats.characters, 14);
+        assert_eq!(analysis.stats.reading_minutes, 1);
+
+        assert_eq!(analysis.images.len(), 2);
+        assert_eq!(analysis.images[0].kind, "remote");
+        assert_eq!(analysis.images[1].alt, "图");
+        assert!(analysis.images[1].resolved.is_none());
+        let kinds: Vec<_> = analysis.warnings.iter().map(|w| w.kind.as_str()).collect();
+        assert_eq!(kinds, ["image-alt-empty", "image-unresolved"]);
+    }
+
+    #[test]
+    fn test_images_resolve_through_site_profile() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(temp.path()).unwrap();
+        std::fs::write(
+            root.join("hugo.toml"),
+            "baseURL = 'https://blog.example.com/'\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("static/images")).unwrap();
+        std::fs::write(root.join("static/images/a.png"), "png").unwrap();
+        let posts = root.join("content/posts");
+        std::fs::create_dir_all(&posts).unwrap();
+        let workspace = Workspace::load(None);
+        workspace.approve(&root).unwrap();
+
+        let outside = tempfile::tempdir().unwrap();
+        let secret = std::fs::canonicalize(outside.path())
+            .unwrap()
+            .join("secret.png");
+        std::fs::write(&secret, "png").unwrap();
+
+        let body = format!(
+            "![a](/images/a.png) ![b](/images/missing.png) ![c]({})\n",
+            secret.display()
+        );
+        let body = body.as_str();
+        let options = AnalysisOptions {
+            image_prefix: String::new(),
+            assets_dir: "assets".to_string(),
+            workspace: &workspace,
+            site: Some((
+                SiteProfile::detect(&posts, None),
+                PostContext::new(posts.clone(), Some(posts.join("p.md")), body),
+            )),
+            line_markers: false,
+        };
+        let lines = LineIndex::new(body, body);
+        let (_, analysis) = analyze_events(Parser::new(body).into_offset_iter(), &lines, &options);
+
+        assert_eq!(analysis.images[0].kind, "local");
+        let local = root.join("static/images/a.png");
+        assert_eq!(
+            analysis.images[0].resolved.as_deref(),
+            Some(local.to_str().unwrap())
+        );
+        // Missing files are looked up where the site publishes them
+        assert_eq!(analysis.images[1].kind, "remote");
+        assert_eq!(
+            analysis.images[1].resolved.as_deref(),
+            Some("https://blog.example.com/images/missing.png")
+        );
+        // Files outside the workspace are never reported, even if they exist
+        assert_ne!(analysis.images[2].kind, "local");
+        assert!(analysis.images[2]
+            .resolved
+            .as_deref()
+            .is_none_or(|url| url.starts_with("https://blog.example.com/")));
+    }
+}
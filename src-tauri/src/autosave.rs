@@ -0,0 +1,278 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::encoding::{read_text, FileFormats};
+use crate::file::{save_text, write_atomic};
+use crate::front_matter::strip_front_matter;
+use crate::models::DraftInfo;
+use crate::watch::FileWatch;
+use crate::workspace::Workspace;
+
+/// Folder in the app data directory holding one journal file per draft
+const DRAFTS_DIR: &str = "drafts";
+/// Oldest drafts beyond this many are dropped when a new one is written
+const MAX_DRAFTS: usize = 50;
+/// Characters of the body shown when listing drafts
+const PREVIEW_CHARS: usize = 120;
+
+/// One journalled editor buffer
+#[derive(Serialize, Deserialize)]
+struct DraftRecord {
+    id: String,
+    /// File the buffer belongs to; `None` for a post never saved
+    path: Option<String>,
+    content: String,
+    /// Milliseconds since the Unix epoch
+    updated: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn drafts_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(DRAFTS_DIR))
+        .map_err(|e| format!("无法定位草稿目录: {}", e))
+}
+
+/// Ids are generated here, so anything else could point outside the folder
+fn draft_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("无效的草稿 ID: {}", id));
+    }
+    Ok(dir.join(format!("{}.json", id)))
+}
+
+fn read_draft(dir: &Path, id: &str) -> Result<DraftRecord, String> {
+    let text =
+        fs::read_to_string(draft_path(dir, id)?).map_err(|_| format!("未找到草稿: {}", id))?;
+    serde_json::from_str(&text).map_err(|e| format!("草稿已损坏: {}", e))
+}
+
+fn draft_title(content: &str) -> String {
+    let (meta, body) = strip_front_matter(content);
+    meta.and_then(|m| m.title)
+        .or_else(|| {
+            body.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string())
+        })
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "未命名草稿".to_string())
+}
+
+/// Journal `content` under `id`, or under a new id when `id` is `None`
+fn write_draft(
+    dir: &Path,
+    id: Option<String>,
+    path: Option<String>,
+    content: String,
+) -> Result<String, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("无法创建草稿目录: {}", e))?;
+    let updated = now_millis();
+    let id = match id {
+        Some(id) => id,
+        None => {
+            let mut candidate = updated;
+            while dir.join(format!("{}.json", candidate)).exists() {
+                candidate += 1;
+            }
+            candidate.to_string()
+        }
+    };
+    let record = DraftRecord {
+        id: id.clone(),
+        path,
+        content,
+        updated,
+    };
+    let text = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+    write_atomic(&draft_path(dir, &id)?, text.as_bytes())?;
+    prune_drafts(dir);
+    Ok(id)
+}
+
+/// Drop the least recently written drafts beyond `MAX_DRAFTS`, going by file
+/// times alone since this runs on every autosave
+fn prune_drafts(dir: &Path) {
+    let mut files: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "json"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    if files.len() <= MAX_DRAFTS {
+        return;
+    }
+    files.sort_by_key(|(modified, _)| Reverse(*modified));
+    for (_, path) in files.into_iter().skip(MAX_DRAFTS) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Drafts worth recovering, newest first. Drafts whose file on disk already
+/// holds the same content are removed instead of listed.
+fn list_drafts_in(dir: &Path) -> Vec<DraftInfo> {
+    let mut drafts: Vec<DraftInfo> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "json"))
+        .filter_map(|entry| {
+            let text = fs::read_to_string(entry.path()).ok()?;
+            let record: DraftRecord = serde_json::from_str(&text).ok()?;
+            let saved = record
+                .path
+                .as_ref()
+                .and_then(|path| read_text(Path::new(path)).ok())
+                .is_some_and(|(on_disk, _)| on_disk == record.content);
+            if saved {
+                let _ = fs::remove_file(entry.path());
+                return None;
+            }
+            let (_, body) = strip_front_matter(&record.content);
+            Some(DraftInfo {
+                title: draft_title(&record.content),
+                preview: body.trim().chars().take(PREVIEW_CHARS).collect(),
+                size: record.content.len() as u64,
+                id: record.id,
+                path: record.path,
+                updated: record.updated,
+            })
+        })
+        .collect();
+    drafts.sort_by_key(|d| Reverse(d.updated));
+    drafts
+}
+
+/// Journal the editor buffer to the app data directory so it survives a
+/// crash. Returns the draft id to pass on the next call for the same buffer.
+#[tauri::command]
+pub fn autosave_draft(
+    app: AppHandle,
+    id: Option<String>,
+    path: Option<String>,
+    content: String,
+) -> Result<String, String> {
+    write_draft(&drafts_dir(&app)?, id, path, content)
+}
+
+/// Drafts left behind by a crash or by closing without saving, newest first
+#[tauri::command]
+pub fn list_drafts(app: AppHandle) -> Result<Vec<DraftInfo>, String> {
+    Ok(list_drafts_in(&drafts_dir(&app)?))
+}
+
+/// Content of a draft
+#[tauri::command]
+pub fn load_draft(app: AppHandle, id: String) -> Result<String, String> {
+    Ok(read_draft(&drafts_dir(&app)?, &id)?.content)
+}
+
+/// Forget a draft, e.g. once its buffer was saved to a file
+#[tauri::command]
+pub fn discard_draft(app: AppHandle, id: String) -> Result<(), String> {
+    let path = draft_path(&drafts_dir(&app)?, &id)?;
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("删除草稿失败: {}", e)),
+        _ => Ok(()),
+    }
+}
+
+/// Write a draft to a markdown file in the workspace and remove it from the
+/// store. Saving works as in `save_markdown_file`: the file is backed up,
+/// external edits are not overwritten unless `force` is set, and it keeps its
+/// encoding and line endings. Returns the content written.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn promote_draft(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    watch: State<'_, FileWatch>,
+    formats: State<'_, FileFormats>,
+    id: String,
+    path: String,
+    backup_count: Option<usize>,
+    force: Option<bool>,
+    normalize: Option<bool>,
+) -> Result<String, String> {
+    let dir = drafts_dir(&app)?;
+    let draft = read_draft(&dir, &id)?;
+    save_text(
+        &watch,
+        &formats,
+        &workspace.resolve(&path)?,
+        &draft.content,
+        backup_count,
+        force.unwrap_or(false),
+        normalize.unwrap_or(false),
+    )?;
+    let _ = fs::remove_file(draft_path(&dir, &id)?);
+    Ok(draft.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_list_and_skip_saved_drafts() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().to_path_buf();
+        let dir = root.join(DRAFTS_DIR);
+        let post = root.join("post.md");
+
+        let unsaved = write_draft(&dir, None, None, "# 新文章\n正文".to_string()).unwrap();
+        assert_eq!(
+            write_draft(
+                &dir,
+                Some(unsaved.clone()),
+                None,
+                "# 新文章\n更多正文".into()
+            )
+            .unwrap(),
+            unsaved
+        );
+        let on_disk = write_draft(
+            &dir,
+            None,
+            Some(post.to_string_lossy().to_string()),
+            "---\ntitle: 已保存\n---\nbody".to_string(),
+        )
+        .unwrap();
+
+        let drafts = list_drafts_in(&dir);
+        assert_eq!(drafts.len(), 2);
+        let titles: Vec<&str> = drafts.iter().map(|d| d.title.as_str()).collect();
+        assert!(titles.contains(&"新文章") && titles.contains(&"已保存"));
+        assert_eq!(
+            read_draft(&dir, &unsaved).unwrap().content,
+            "# 新文章\n更多正文"
+        );
+
+        // Once the file holds the draft's content there is nothing to recover
+        fs::write(&post, "---\ntitle: 已保存\n---\nbody").unwrap();
+        let drafts = list_drafts_in(&dir);
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].id, unsaved);
+        assert!(read_draft(&dir, &on_disk).is_err());
+        assert!(draft_path(&dir, "../secrets").is_err());
+
+        // Old drafts are pruned without reading them
+        for n in 0..MAX_DRAFTS + 2 {
+            write_draft(&dir, None, None, format!("draft {}", n)).unwrap();
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), MAX_DRAFTS);
+    }
+}
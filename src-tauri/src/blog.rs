@@ -0,0 +1,417 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::encoding::read_text;
+use crate::front_matter::split_front_matter;
+use crate::models::{BlogPost, BlogPostList, BlogPostQuery, WechatUploadResultEntry};
+use crate::workspace::{approve_and_expose, Workspace};
+
+/// Build output, dependencies and theme sources of Hexo, Hugo and Jekyll
+/// sites, skipped only at the top of the root so posts may use these names
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "public",
+    "themes",
+    "resources",
+    "_site",
+    "vendor",
+    "target",
+];
+/// Folders whose posts are drafts whatever their front matter says
+const DRAFT_DIRS: &[&str] = &["_drafts"];
+/// Written next to a post by `wechat_upload_and_replace_images`
+const MEDIA_LOG: &str = "wechat_media_log.jsonl";
+/// Host of images uploaded to WeChat, for posts saved after the upload
+const WECHAT_IMAGE_HOST: &str = "mmbiz.qpic.cn";
+
+/// Modification time and size, to tell whether a file needs re-reading
+pub(crate) type Stamp = (u64, u64);
+
+fn stamp(metadata: &fs::Metadata) -> Stamp {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    (modified, metadata.len())
+}
+
+struct IndexedPost {
+    stamp: Stamp,
+    post: BlogPost,
+    image_urls: Vec<String>,
+}
+
+/// Image URLs recorded in one folder's media log
+#[derive(Default)]
+struct MediaLog {
+    stamp: Option<Stamp>,
+    urls: HashSet<String>,
+}
+
+impl MediaLog {
+    fn read(dir: &Path, stamp: Option<Stamp>) -> Self {
+        let urls = fs::read_to_string(dir.join(MEDIA_LOG))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str::<WechatUploadResultEntry>(line).ok())
+            .flat_map(|entry| [entry.original_url, entry.wechat_url])
+            .collect();
+        MediaLog { stamp, urls }
+    }
+
+    fn has(&self, url: &str) -> bool {
+        self.urls.contains(url) || url.contains(WECHAT_IMAGE_HOST)
+    }
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+}
+
+/// Markdown files under `root` with their stamps, skipping hidden folders and
+/// the root's build output and dependencies
+pub(crate) fn markdown_files(root: &Path) -> Vec<(PathBuf, Stamp)> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                let skipped = dir == root && SKIPPED_DIRS.contains(&name.as_str());
+                if !name.starts_with('.') && !skipped {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !file_type.is_file() || !is_markdown(&path) {
+                continue;
+            }
+            if let Ok(metadata) = entry.metadata() {
+                files.push((path, stamp(&metadata)));
+            }
+        }
+    }
+    files
+}
+
+/// `path` relative to `root`, with `/` separators
+pub(crate) fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn read_post(root: &Path, path: &Path, stamp: Stamp) -> IndexedPost {
+    let content = read_text(path).map(|(text, _)| text).unwrap_or_default();
+    let (meta, body, error) = match split_front_matter(&content) {
+        Ok((meta, body)) => (meta.unwrap_or_default(), body, None),
+        Err(e) => (Default::default(), content.as_str(), Some(e)),
+    };
+
+    let relative_path = relative_path(root, path);
+    let in_draft_dir = relative_path
+        .split('/')
+        .any(|part| DRAFT_DIRS.contains(&part));
+    let draft = in_draft_dir
+        || match meta.extra.get("draft") {
+            Some(serde_json::Value::Bool(draft)) => *draft,
+            Some(serde_json::Value::String(draft)) => draft.eq_ignore_ascii_case("true"),
+            _ => false,
+        };
+
+    let mut image_urls: Vec<String> = Parser::new(body)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Image { dest_url, .. }) => Some(dest_url.to_string()),
+            _ => None,
+        })
+        .collect();
+    image_urls.sort();
+    image_urls.dedup();
+
+    IndexedPost {
+        stamp,
+        post: BlogPost {
+            path: path.to_string_lossy().to_string(),
+            title: meta.title.clone().unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            }),
+            relative_path,
+            date: meta.date,
+            tags: meta.tags,
+            draft,
+            published_to_wechat: false,
+            images: image_urls.len(),
+            wechat_images: 0,
+            modified: stamp.0,
+            error,
+        },
+        image_urls,
+    }
+}
+
+/// Posts under one blog root, re-read only when their files change
+struct PostIndex {
+    root: PathBuf,
+    posts: HashMap<PathBuf, IndexedPost>,
+    logs: HashMap<PathBuf, MediaLog>,
+}
+
+impl PostIndex {
+    fn new(root: PathBuf) -> Self {
+        PostIndex {
+            root,
+            posts: HashMap::new(),
+            logs: HashMap::new(),
+        }
+    }
+
+    /// Walk the root, parse new and changed posts and forget deleted ones
+    fn refresh(&mut self) {
+        let mut seen = HashSet::new();
+        for (path, current) in markdown_files(&self.root) {
+            seen.insert(path.clone());
+            if self.posts.get(&path).is_some_and(|p| p.stamp == current) {
+                continue;
+            }
+            let post = read_post(&self.root, &path, current);
+            self.posts.insert(path, post);
+        }
+        self.posts.retain(|path, _| seen.contains(path));
+
+        let dirs: HashSet<PathBuf> = self
+            .posts
+            .keys()
+            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .collect();
+        self.logs.retain(|dir, _| dirs.contains(dir));
+        for dir in dirs {
+            let current = fs::metadata(dir.join(MEDIA_LOG)).ok().map(|m| stamp(&m));
+            if self.logs.get(&dir).is_none_or(|log| log.stamp != current) {
+                let log = MediaLog::read(&dir, current);
+                self.logs.insert(dir, log);
+            }
+        }
+    }
+
+    /// Every post with its WeChat status filled in from the media logs
+    fn posts(&self) -> Vec<BlogPost> {
+        let empty = MediaLog::default();
+        self.posts
+            .iter()
+            .map(|(path, indexed)| {
+                let log = path
+                    .parent()
+                    .and_then(|dir| self.logs.get(dir))
+                    .unwrap_or(&empty);
+                let uploaded = indexed.image_urls.iter().filter(|u| log.has(u)).count();
+                BlogPost {
+                    published_to_wechat: uploaded > 0 && uploaded == indexed.image_urls.len(),
+                    wechat_images: uploaded,
+                    ..indexed.post.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Year, month, day, hour, minute and second of a front matter date, so
+/// `2024-3-1` and `2024-03-01 08:00` compare by value rather than as text.
+/// Anything after the seconds, such as a time zone, is ignored.
+fn date_key(date: &str) -> Vec<u32> {
+    date.split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .take(6)
+        .map(|part| part.parse().unwrap_or(u32::MAX))
+        .collect()
+}
+
+/// Apply a query's filters and ordering
+fn query_posts(posts: Vec<BlogPost>, query: &BlogPostQuery) -> Vec<BlogPost> {
+    let search = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase);
+    let mut posts: Vec<BlogPost> = posts
+        .into_iter()
+        .filter(|post| {
+            search.as_ref().is_none_or(|search| {
+                post.title.to_lowercase().contains(search)
+                    || post.relative_path.to_lowercase().contains(search)
+                    || post.tags.iter().any(|t| t.to_lowercase().contains(search))
+            })
+        })
+        .filter(|post| query.tag.as_ref().is_none_or(|tag| post.tags.contains(tag)))
+        .filter(|post| query.draft.is_none_or(|draft| post.draft == draft))
+        .filter(|post| {
+            query
+                .published
+                .is_none_or(|published| post.published_to_wechat == published)
+        })
+        .collect();
+
+    match query.sort_by.as_deref() {
+        Some("title") => posts.sort_by(|a, b| a.title.cmp(&b.title)),
+        Some("modified") => posts.sort_by_key(|p| p.modified),
+        // Undated posts sort as oldest
+        _ => posts
+            .sort_by_cached_key(|p| (date_key(p.date.as_deref().unwrap_or_default()), p.modified)),
+    }
+    if !query.ascending {
+        posts.reverse();
+    }
+    posts
+}
+
+/// The blog opened in the app, if any
+#[derive(Default)]
+pub struct BlogIndex {
+    index: Mutex<Option<PostIndex>>,
+}
+
+fn list_posts(
+    workspace: &Workspace,
+    index: &BlogIndex,
+    query: &BlogPostQuery,
+) -> Result<BlogPostList, String> {
+    let mut guard = index.index.lock().map_err(|e| e.to_string())?;
+    let index = guard
+        .as_mut()
+        .ok_or_else(|| "尚未打开博客目录".to_string())?;
+    // The root may have been removed from the workspace since it was opened
+    workspace.resolve(&index.root.to_string_lossy())?;
+    index.refresh();
+
+    let posts = index.posts();
+    let tags: BTreeSet<String> = posts.iter().flat_map(|p| p.tags.clone()).collect();
+    Ok(BlogPostList {
+        root: index.root.to_string_lossy().to_string(),
+        total: posts.len(),
+        posts: query_posts(posts, query),
+        tags: tags.into_iter().collect(),
+    })
+}
+
+/// Let the user pick a blog checkout, add it to the workspace and index its posts
+#[tauri::command]
+pub async fn open_blog_workspace(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    index: State<'_, BlogIndex>,
+    query: Option<BlogPostQuery>,
+) -> Result<Option<BlogPostList>, String> {
+    let Some(folder) = app.dialog().file().blocking_pick_folder() else {
+        return Ok(None);
+    };
+    let dir = folder.into_path().map_err(|e| e.to_string())?;
+    approve_and_expose(&app, &workspace, &dir)?;
+    let root = workspace.resolve(&dir.to_string_lossy())?;
+    *index.index.lock().map_err(|e| e.to_string())? = Some(PostIndex::new(root));
+    list_posts(&workspace, &index, &query.unwrap_or_default()).map(Some)
+}
+
+/// Posts of the open blog, re-reading only the files that changed since the
+/// last call
+#[tauri::command]
+pub fn list_blog_posts(
+    workspace: State<'_, Workspace>,
+    index: State<'_, BlogIndex>,
+    query: Option<BlogPostQuery>,
+) -> Result<BlogPostList, String> {
+    list_posts(&workspace, &index, &query.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_updates_incrementally_and_filters() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().to_path_buf();
+        let posts_dir = root.join("source/_posts");
+        fs::create_dir_all(&posts_dir).unwrap();
+        fs::create_dir_all(root.join("source/_drafts")).unwrap();
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("node_modules/pkg/README.md"), "# x").unwrap();
+        // Skipped names only apply at the root
+        fs::create_dir_all(posts_dir.join("themes")).unwrap();
+        fs::write(
+            posts_dir.join("themes/dark.md"),
+            "---\ntitle: Dark\ndate: 2024-3-15\n---\n",
+        )
+        .unwrap();
+
+        fs::write(
+            posts_dir.join("hello.md"),
+            "---\ntitle: Hello\ndate: 2024-03-01\ntags: [rust, wechat]\n---\n![a](assets/a.png)\n",
+        )
+        .unwrap();
+        fs::write(
+            posts_dir.join("later.md"),
+            "---\ntitle: Later\ndate: 2024-05-01\ndraft: true\ntags: rust\n---\nbody\n",
+        )
+        .unwrap();
+        fs::write(root.join("source/_drafts/idea.md"), "no front matter").unwrap();
+        fs::write(
+            posts_dir.join(MEDIA_LOG),
+            "{\"original_url\":\"assets/a.png\",\"wechat_url\":\"http://mmbiz.qpic.cn/a\",\"media_id\":\"1\"}\n",
+        )
+        .unwrap();
+
+        let mut index = PostIndex::new(root.clone());
+        index.refresh();
+        let all = query_posts(index.posts(), &BlogPostQuery::default());
+        let titles: Vec<&str> = all.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, ["Later", "Dark", "Hello", "idea"]);
+        let hello = &all[2];
+        assert!(hello.published_to_wechat && !hello.draft);
+        assert!(all[0].draft && all[3].draft);
+
+        let query = BlogPostQuery {
+            tag: Some("rust".to_string()),
+            draft: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(query_posts(index.posts(), &query).len(), 1);
+        assert_eq!(date_key("2024-3-1"), date_key("2024-03-01"));
+        assert!(date_key("2024-03-01T10:00:00+08:00") > date_key("2024-3-1 9:30"));
+
+        // Only the edited post is re-read; deleted posts disappear
+        let stamp_of = |index: &PostIndex, name: &str| index.posts[&posts_dir.join(name)].stamp;
+        let later_stamp = stamp_of(&index, "later.md");
+        fs::write(posts_dir.join("hello.md"), "---\ntitle: Hello again\n---\n").unwrap();
+        fs::remove_file(root.join("source/_drafts/idea.md")).unwrap();
+        index.refresh();
+        assert_eq!(stamp_of(&index, "later.md"), later_stamp);
+        let search = BlogPostQuery {
+            search: Some("AGAIN".to_string()),
+            ..Default::default()
+        };
+        let found = query_posts(index.posts(), &search);
+        assert_eq!(found.len(), 1);
+        assert!(!found[0].published_to_wechat);
+        assert_eq!(index.posts.len(), 3);
+    }
+}
@@ -0,0 +1,262 @@
+/// An sRGB colour with alpha in `0.0..=1.0`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f64,
+}
+
+impl Rgba {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Rgba { r, g, b, a: 1.0 }
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Paint this colour over an opaque `background`
+    pub fn over(self, background: Rgba) -> Rgba {
+        let mix = |top: u8, bottom: u8| {
+            (top as f64 * self.a + bottom as f64 * (1.0 - self.a)).round() as u8
+        };
+        Rgba::rgb(
+            mix(self.r, background.r),
+            mix(self.g, background.g),
+            mix(self.b, background.b),
+        )
+    }
+
+    /// WCAG relative luminance
+    pub fn luminance(self) -> f64 {
+        let channel = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)
+    }
+
+    /// `(hue in degrees, saturation, lightness)`
+    pub fn to_hsl(self) -> (f64, f64, f64) {
+        let r = self.r as f64 / 255.0;
+        let g = self.g as f64 / 255.0;
+        let b = self.b as f64 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        if max == min {
+            return (0.0, 0.0, l);
+        }
+        let d = max - min;
+        let s = if l > 0.5 {
+            d / (2.0 - max - min)
+        } else {
+            d / (max + min)
+        };
+        let h = if max == r {
+            (g - b) / d + if g < b { 6.0 } else { 0.0 }
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+        (h * 60.0, s, l)
+    }
+
+    pub fn from_hsl(h: f64, s: f64, l: f64) -> Self {
+        let s = s.clamp(0.0, 1.0);
+        let l = l.clamp(0.0, 1.0);
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = l - c / 2.0;
+        let channel = |v: f64| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Rgba::rgb(channel(r), channel(g), channel(b))
+    }
+}
+
+/// WCAG contrast ratio between two opaque colours, from 1 to 21
+pub fn contrast_ratio(a: Rgba, b: Rgba) -> f64 {
+    let (la, lb) = (a.luminance(), b.luminance());
+    let (light, dark) = if la > lb { (la, lb) } else { (lb, la) };
+    (light + 0.05) / (dark + 0.05)
+}
+
+/// Move `color`'s lightness away from `background` until the pair reaches
+/// `target` contrast, keeping hue and saturation. Returns `None` if even
+/// black or white is not enough.
+pub fn adjust_for_contrast(color: Rgba, background: Rgba, target: f64) -> Option<Rgba> {
+    if contrast_ratio(color, background) >= target {
+        return Some(color);
+    }
+    let (h, s, l) = color.to_hsl();
+    let step = if background.luminance() > 0.18 {
+        -0.01
+    } else {
+        0.01
+    };
+    let mut lightness = l;
+    while (0.0..=1.0).contains(&lightness) {
+        let candidate = Rgba::from_hsl(h, s, lightness);
+        if contrast_ratio(candidate, background) >= target {
+            return Some(candidate);
+        }
+        lightness += step;
+    }
+    None
+}
+
+fn named_color(name: &str) -> Option<Rgba> {
+    let color = match name {
+        "black" => Rgba::rgb(0, 0, 0),
+        "white" => Rgba::rgb(255, 255, 255),
+        "red" => Rgba::rgb(255, 0, 0),
+        "green" => Rgba::rgb(0, 128, 0),
+        "blue" => Rgba::rgb(0, 0, 255),
+        "yellow" => Rgba::rgb(255, 255, 0),
+        "orange" => Rgba::rgb(255, 165, 0),
+        "purple" => Rgba::rgb(128, 0, 128),
+        "gray" | "grey" => Rgba::rgb(128, 128, 128),
+        "silver" => Rgba::rgb(192, 192, 192),
+        "navy" => Rgba::rgb(0, 0, 128),
+        "teal" => Rgba::rgb(0, 128, 128),
+        "maroon" => Rgba::rgb(128, 0, 0),
+        "transparent" => Rgba {
+            a: 0.0,
+            ..Rgba::rgb(0, 0, 0)
+        },
+        _ => return None,
+    };
+    Some(color)
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    Some(Rgba {
+        r: digits[0],
+        g: digits[1],
+        b: digits[2],
+        a: digits.get(3).map_or(1.0, |a| *a as f64 / 255.0),
+    })
+}
+
+fn parse_function(name: &str, args: &str) -> Option<Rgba> {
+    let parts: Vec<&str> = args
+        .split([',', ' ', '/'])
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    let number = |part: &str, scale: f64| -> Option<f64> {
+        match part.strip_suffix('%') {
+            Some(percent) => percent.parse::<f64>().ok().map(|p| p / 100.0 * scale),
+            None => part.trim_end_matches("deg").parse::<f64>().ok(),
+        }
+    };
+    let alpha = match parts.get(3) {
+        Some(a) => number(a, 1.0)?.clamp(0.0, 1.0),
+        None => 1.0,
+    };
+    let color = match name {
+        "rgb" | "rgba" => Rgba::rgb(
+            number(parts[0], 255.0)?.round().clamp(0.0, 255.0) as u8,
+            number(parts[1], 255.0)?.round().clamp(0.0, 255.0) as u8,
+            number(parts[2], 255.0)?.round().clamp(0.0, 255.0) as u8,
+        ),
+        "hsl" | "hsla" => Rgba::from_hsl(
+            number(parts[0], 1.0)?,
+            number(parts[1], 1.0)?,
+            number(parts[2], 1.0)?,
+        ),
+        _ => return None,
+    };
+    Some(Rgba { a: alpha, ..color })
+}
+
+/// Parse a single CSS colour value: hex, `rgb()`/`rgba()`, `hsl()`/`hsla()` or
+/// a common colour keyword
+pub fn parse_color(value: &str) -> Option<Rgba> {
+    let value = value.trim().trim_end_matches("!important").trim();
+    let lower = value.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if let Some((name, rest)) = lower.split_once('(') {
+        return parse_function(name.trim(), rest.strip_suffix(')')?);
+    }
+    named_color(&lower)
+}
+
+/// The first colour in a shorthand such as `background` or `border`
+pub fn find_color(value: &str) -> Option<Rgba> {
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in value
+        .char_indices()
+        .chain(std::iter::once((value.len(), ' ')))
+    {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ' ' | ',' if depth == 0 => {
+                if let Some(color) = parse_color(&value[start..i]) {
+                    return Some(color);
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_contrast() {
+        assert_eq!(parse_color("#07C160"), Some(Rgba::rgb(7, 193, 96)));
+        assert_eq!(parse_color("#fff"), Some(Rgba::rgb(255, 255, 255)));
+        assert_eq!(parse_color("rgb(51, 51, 51)"), Some(Rgba::rgb(51, 51, 51)));
+        assert_eq!(parse_color("rgba(0,0,0,0.5)").map(|c| c.a), Some(0.5));
+        assert_eq!(find_color("4px solid #d0d0d0"), parse_color("#d0d0d0"));
+        assert_eq!(parse_color("inherit"), None);
+
+        let white = Rgba::rgb(255, 255, 255);
+        let black = Rgba::rgb(0, 0, 0);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 1e-9);
+        let grey = parse_color("#999").unwrap();
+        let fixed = adjust_for_contrast(grey, white, 4.5).unwrap();
+        assert!(contrast_ratio(fixed, white) >= 4.5);
+        assert!(fixed.luminance() < grey.luminance());
+    }
+}
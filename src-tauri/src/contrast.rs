@@ -0,0 +1,195 @@
+use crate::color::{adjust_for_contrast, contrast_ratio, find_color, parse_color, Rgba};
+use crate::css::{get_fallback_css, parse_rules};
+use crate::css_vars::resolve_css_variables;
+use crate::models::{ContrastCheck, ContrastReport};
+
+/// Selector of the article container every fallback rule is scoped to
+const CONTENT_SCOPE: &str = ".wechat-content";
+/// WCAG AA minimum for body text
+const AA_NORMAL: f64 = 4.5;
+/// WCAG AA minimum for large text (at least 24px, or 18.66px bold)
+const AA_LARGE: f64 = 3.0;
+/// WCAG AAA minimum for body text
+const AAA_NORMAL: f64 = 7.0;
+/// WCAG AAA minimum for large text
+const AAA_LARGE: f64 = 4.5;
+/// Elements the fallback styles render as large, bold text
+const LARGE_TEXT: &[&str] = &["h1", "h2", "h3"];
+
+/// `.wechat-content h2` and `h2` name the same element inside the article
+fn normalize_selector(selector: &str) -> String {
+    let rest = selector
+        .strip_prefix(CONTENT_SCOPE)
+        .map(str::trim)
+        .unwrap_or(selector);
+    if rest.is_empty() {
+        CONTENT_SCOPE.to_string()
+    } else {
+        rest.split_whitespace()
+            .filter(|part| !matches!(*part, ">" | "+" | "~"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Declarations per normalized selector for one stylesheet; rules inside
+/// at-rules are skipped since they only apply on some screens
+fn layer(css: &str) -> Vec<(String, Vec<(String, String)>)> {
+    parse_rules(css)
+        .into_iter()
+        .filter(|rule| rule.at_rule.is_none())
+        .flat_map(|rule| {
+            rule.selectors
+                .iter()
+                .map(|selector| (normalize_selector(selector), rule.declarations.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+struct Cascade {
+    fallback: Vec<(String, Vec<(String, String)>)>,
+    theme: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl Cascade {
+    /// Last colour declared for `selector`, with the theme layered over the fallback
+    fn declared(&self, selector: &str, background: bool) -> Option<Rgba> {
+        let find = |rules: &[(String, Vec<(String, String)>)]| {
+            rules
+                .iter()
+                .rev()
+                .filter(|(s, _)| s == selector)
+                .flat_map(|(_, declarations)| declarations.iter().rev())
+                .filter_map(|(property, value)| match property.as_str() {
+                    "color" if !background => parse_color(value),
+                    "background-color" if background => parse_color(value),
+                    "background" if background => find_color(value),
+                    _ => None,
+                })
+                .next()
+        };
+        find(&self.theme).or_else(|| find(&self.fallback))
+    }
+
+    /// `(text, background)` the selector ends up with, inheriting from its
+    /// ancestors in the selector and finally from the article container
+    fn resolve(&self, selector: &str) -> (Rgba, Rgba) {
+        let (parent_text, parent_background) = if selector == CONTENT_SCOPE {
+            (Rgba::rgb(0, 0, 0), Rgba::rgb(255, 255, 255))
+        } else {
+            match selector.rsplit_once(' ') {
+                Some((ancestors, _)) => self.resolve(ancestors),
+                None => self.resolve(CONTENT_SCOPE),
+            }
+        };
+        let background = self
+            .declared(selector, true)
+            .map_or(parent_background, |c| c.over(parent_background));
+        let text = self
+            .declared(selector, false)
+            .map_or(parent_text, |c| c.over(background));
+        (text, background)
+    }
+}
+
+/// Compute the WCAG contrast of text against background for every selector the
+/// fallback stylesheet styles, with the theme layered on top, and suggest the
+/// nearest colours that pass AA for the pairs that do not
+pub fn check_contrast(theme_css: &str) -> ContrastReport {
+    let cascade = Cascade {
+        fallback: layer(get_fallback_css()),
+        theme: layer(&resolve_css_variables(theme_css).css),
+    };
+
+    let mut targets: Vec<String> = Vec::new();
+    for (selector, _) in &cascade.fallback {
+        let last = selector.rsplit(' ').next().unwrap_or_default();
+        if last == "img" || selector.contains(":hover") || targets.contains(selector) {
+            continue;
+        }
+        targets.push(selector.clone());
+    }
+
+    let checks: Vec<ContrastCheck> = targets
+        .into_iter()
+        .map(|selector| {
+            let (text, background) = cascade.resolve(&selector);
+            let large_text = LARGE_TEXT.contains(&selector.as_str());
+            let (required, enhanced) = if large_text {
+                (AA_LARGE, AAA_LARGE)
+            } else {
+                (AA_NORMAL, AAA_NORMAL)
+            };
+            let ratio = contrast_ratio(text, background);
+            let passes_aa = ratio >= required;
+            ContrastCheck {
+                selector,
+                color: text.to_hex(),
+                background: background.to_hex(),
+                ratio: (ratio * 100.0).round() / 100.0,
+                large_text,
+                required,
+                passes_aa,
+                passes_aaa: ratio >= enhanced,
+                suggested_color: (!passes_aa)
+                    .then(|| adjust_for_contrast(text, background, required))
+                    .flatten()
+                    .map(Rgba::to_hex),
+                suggested_background: (!passes_aa)
+                    .then(|| adjust_for_contrast(background, text, required))
+                    .flatten()
+                    .map(Rgba::to_hex),
+            }
+        })
+        .collect();
+
+    ContrastReport {
+        failures: checks.iter().filter(|c| !c.passes_aa).count(),
+        checks,
+    }
+}
+
+/// Check a theme's text and background colours against WCAG contrast guidelines
+#[tauri::command]
+pub fn check_theme_contrast(css: String) -> Result<ContrastReport, String> {
+    Ok(check_contrast(&css))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grey_on_grey_blockquote_fails_with_suggestions() {
+        let theme = ".wechat-content { --quote: #aaa; }
+.wechat-content blockquote { color: var(--quote); background: #eeeeee; }
+h2 { color: #07c160; }
+@media (prefers-color-scheme: dark) { p { color: #fff; } }";
+        let report = check_contrast(theme);
+        let find = |selector: &str| {
+            report
+                .checks
+                .iter()
+                .find(|c| c.selector == selector)
+                .unwrap()
+        };
+
+        let quote = find("blockquote");
+        assert_eq!(quote.color, "#aaaaaa");
+        assert_eq!(quote.background, "#eeeeee");
+        assert!(!quote.passes_aa);
+        let suggested = parse_color(quote.suggested_color.as_deref().unwrap()).unwrap();
+        assert!(contrast_ratio(suggested, Rgba::rgb(0xee, 0xee, 0xee)) >= AA_NORMAL);
+        assert!(quote.suggested_background.is_some());
+
+        // Large text only needs 3:1; the media query does not apply
+        let heading = find("h2");
+        assert!(heading.large_text);
+        assert!(!heading.passes_aa);
+        assert_eq!(find("p").color, "#333333");
+        assert_eq!(find("pre code").background, "#f6f8fa");
+        assert!(report.checks.iter().all(|c| c.selector != "img"));
+        assert!(report.failures >= 2);
+    }
+}
@@ -1,4 +1,20 @@
-/// Fallback CSS styles for custom CSS themes
+use std::borrow::Cow;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tauri::State;
+
+use crate::css_vars::{resolve_css_variables, split_declaration, tokenize};
+use crate::lint::{parse_attrs, TOKEN_RE};
+use crate::models::{ConvertOptions, ElementStyleTrace, StyleLayer, StyleSource};
+use crate::workspace::Workspace;
+
+/// The `style` attribute of a start tag, as css_inline writes it
+static STYLE_ATTR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\s+style="([^"]*)""#).unwrap());
+
+/// Fallback CSS styles for custom CSS themes. Every rule is scoped to
+/// `.wechat-content` and applied as a lower layer than the theme, see
+/// `inline_theme_css`.
 pub fn get_fallback_css() -> &'static str {
     r#"
 /* Fallback styles for custom CSS themes */
@@ -11,7 +27,7 @@ pub fn get_fallback_css() -> &'static str {
   padding: 16px;
 }
 
-h1 {
+.wechat-content h1 {
   font-size: 24px;
   font-weight: bold;
   margin-top: 20px;
@@ -20,7 +36,7 @@ h1 {
   padding-bottom: 5px;
 }
 
-h2 {
+.wechat-content h2 {
   font-size: 20px;
   font-weight: bold;
   margin-top: 18px;
@@ -29,19 +45,19 @@ h2 {
   padding-left: 10px;
 }
 
-h3 {
+.wechat-content h3 {
   font-size: 18px;
   font-weight: bold;
   margin-top: 16px;
   margin-bottom: 10px;
 }
 
-p {
+.wechat-content p {
   margin-bottom: 16px;
   text-align: justify;
 }
 
-blockquote {
+.wechat-content blockquote {
   margin: 16px 0;
   padding: 10px 16px;
   background-color: #f7f7f7;
@@ -50,16 +66,16 @@ blockquote {
   font-size: 15px;
 }
 
-ul, ol {
+.wechat-content ul, .wechat-content ol {
   margin-bottom: 16px;
   padding-left: 24px;
 }
 
-li {
+.wechat-content li {
   margin-bottom: 4px;
 }
 
-img {
+.wechat-content img {
   max-width: 100%;
   height: auto;
   display: block;
@@ -67,7 +83,7 @@ img {
   border-radius: 4px;
 }
 
-code {
+.wechat-content code {
   font-family: Consolas, Monaco, "Courier New", monospace;
   background-color: #f0f0f0;
   padding: 2px 4px;
@@ -76,7 +92,7 @@ code {
   color: #d63384;
 }
 
-pre {
+.wechat-content pre {
   background-color: #f6f8fa;
   padding: 16px;
   overflow: auto;
@@ -84,45 +100,45 @@ pre {
   margin-bottom: 16px;
 }
 
-pre code {
+.wechat-content pre code {
   background-color: transparent;
   padding: 0;
   color: #333;
   font-size: 13px;
 }
 
-a {
+.wechat-content a {
   color: #576b95;
   text-decoration: none;
 }
 
-a:hover {
+.wechat-content a:hover {
   text-decoration: underline;
 }
 
-table {
+.wechat-content table {
   border-collapse: collapse;
   width: 100%;
   margin-bottom: 16px;
   font-size: 14px;
 }
 
-th, td {
+.wechat-content th, .wechat-content td {
   border: 1px solid #dfe2e5;
   padding: 6px 13px;
 }
 
-th {
+.wechat-content th {
   background-color: #f2f2f2;
   font-weight: bold;
 }
 
-tr:nth-child(2n) {
+.wechat-content tr:nth-child(2n) {
   background-color: #f8f8f8;
 }
 
 /* Footnote styles - IMPORTANT: Always include these */
-.footnote-ref {
+.wechat-content .footnote-ref {
   color: #07c160;
   font-size: 0.8em;
   vertical-align: super;
@@ -130,26 +146,521 @@ tr:nth-child(2n) {
   font-weight: bold;
 }
 
-.footnotes {
+.wechat-content .footnotes {
   margin-top: 24px;
   padding-top: 16px;
   border-top: 1px solid #eaeaea;
   font-size: 14px;
 }
 
-.footnotes ol {
+.wechat-content .footnotes-title {
+  margin: 0 0 8px 0;
+  font-weight: bold;
+  color: #333;
+}
+
+.wechat-content .footnotes ol {
   padding-left: 20px;
   margin: 0;
 }
 
-.footnotes li {
+.wechat-content .footnotes li {
   margin-bottom: 8px;
   color: #666;
 }
 
-.footnote-url {
+.wechat-content .footnote-text {
+  color: #333;
+}
+
+.wechat-content .footnote-url {
   color: #576b95;
   word-break: break-all;
 }
+
+/* Admonitions - themes override the colours per kind, e.g. `.admonition-tip` */
+.wechat-content .admonition {
+  margin: 16px 0;
+  padding: 12px 16px;
+  border-left: 4px solid #8c8c8c;
+  border-radius: 4px;
+  background-color: #f7f7f7;
+  font-size: 15px;
+}
+
+.wechat-content .admonition-title {
+  margin: 0 0 6px 0;
+  font-weight: bold;
+  color: #333;
+}
+
+.wechat-content .admonition-icon {
+  margin-right: 6px;
+}
+
+.wechat-content .admonition-note {
+  border-left-color: #576b95;
+  background-color: #f2f5fa;
+}
+
+.wechat-content .admonition-info {
+  border-left-color: #1677ff;
+  background-color: #f0f7ff;
+}
+
+.wechat-content .admonition-tip {
+  border-left-color: #07c160;
+  background-color: #f0fbf4;
+}
+
+.wechat-content .admonition-important {
+  border-left-color: #8250df;
+  background-color: #f6f2fd;
+}
+
+.wechat-content .admonition-warning {
+  border-left-color: #fa9d3b;
+  background-color: #fff8ef;
+}
+
+.wechat-content .admonition-caution {
+  border-left-color: #e5484d;
+  background-color: #fff5f5;
+}
+
+.wechat-content .admonition-danger {
+  border-left-color: #c62828;
+  background-color: #fdecec;
+}
+
+/* Table of contents - plain text, WeChat drops in-page anchors */
+.wechat-content .toc {
+  margin: 16px 0 24px 0;
+  padding: 12px 16px;
+  background-color: #f7f8fa;
+  border-radius: 4px;
+  font-size: 14px;
+}
+
+.wechat-content .toc-title {
+  margin: 0 0 8px 0;
+  font-weight: bold;
+  color: #333;
+}
+
+.wechat-content .toc-list {
+  list-style: none;
+  margin: 0;
+  padding-left: 0;
+}
+
+.wechat-content .toc-list .toc-list {
+  padding-left: 1.5em;
+}
+
+.wechat-content .toc-item {
+  margin: 4px 0;
+  color: #555;
+  line-height: 1.6;
+}
+
+.wechat-content .toc-level-1 > .toc-item {
+  color: #333;
+}
+
+.wechat-content .toc-number {
+  color: #07c160;
+  font-weight: bold;
+  margin-right: 4px;
+}
 "#
 }
+
+/// One style rule of a stylesheet
+pub(crate) struct CssRule {
+    pub selectors: Vec<String>,
+    pub declarations: Vec<(String, String)>,
+    /// Innermost enclosing at-rule, e.g. `@media (max-width: 600px)`
+    pub at_rule: Option<String>,
+}
+
+/// Parse a stylesheet into its style rules, in source order. At-rule blocks
+/// are entered so rules inside `@media` are kept with their condition.
+pub(crate) fn parse_rules(css: &str) -> Vec<CssRule> {
+    let mut rules: Vec<CssRule> = Vec::new();
+    // Headers of the open blocks, and the index of each one's rule if it is a style rule
+    let mut open: Vec<(String, Option<usize>)> = Vec::new();
+    for (piece, terminator) in tokenize(css) {
+        match terminator {
+            '{' => {
+                let header = piece.trim().to_string();
+                let rule = if header.starts_with('@') {
+                    None
+                } else {
+                    rules.push(CssRule {
+                        selectors: header
+                            .split(',')
+                            .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+                            .filter(|s| !s.is_empty())
+                            .collect(),
+                        declarations: Vec::new(),
+                        at_rule: open
+                            .iter()
+                            .rev()
+                            .find(|(h, _)| h.starts_with('@'))
+                            .map(|(h, _)| h.clone()),
+                    });
+                    Some(rules.len() - 1)
+                };
+                open.push((header, rule));
+            }
+            ';' | '}' => {
+                if let (Some((_, Some(index))), Some((name, value))) =
+                    (open.last(), split_declaration(&piece))
+                {
+                    rules[*index]
+                        .declarations
+                        .push((name.to_ascii_lowercase(), value.to_string()));
+                }
+                if terminator == '}' {
+                    open.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+    rules
+}
+
+fn wrap_document(body_html: &str, css: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+{}
+</style>
+</head>
+<body>
+<div class="wechat-content">
+{}
+</div>
+</body>
+</html>"#,
+        css, body_html
+    )
+}
+
+/// The inlined document after each layer: theme only, then theme and fallback
+struct InlinedLayers {
+    themed: String,
+    merged: String,
+    unresolved: Vec<String>,
+}
+
+/// Properties that `shorthand` sets, besides those sharing its name as a prefix
+fn shorthand_covers(shorthand: &str, property: &str) -> bool {
+    const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+    const PARTS: [&str; 3] = ["width", "style", "color"];
+    let rest = match property.strip_prefix(shorthand) {
+        Some(rest) if !rest.is_empty() => rest.strip_prefix('-'),
+        _ => None,
+    };
+    match shorthand {
+        "margin" | "padding" | "background" | "list-style" | "text-decoration" | "overflow" => {
+            rest.is_some()
+        }
+        "font" => rest.is_some() || property == "line-height",
+        // `border` resets the sides and their parts but not `border-radius`
+        "border" => rest.is_some_and(|rest| {
+            let (first, second) = rest.split_once('-').unwrap_or((rest, ""));
+            (SIDES.contains(&first) && (second.is_empty() || PARTS.contains(&second)))
+                || (PARTS.contains(&first) && second.is_empty())
+        }),
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            rest.is_some_and(|rest| PARTS.contains(&rest))
+        }
+        "border-width" | "border-style" | "border-color" | "border-radius" => {
+            let part = &shorthand["border-".len()..];
+            property
+                .strip_prefix("border-")
+                .and_then(|p| p.strip_suffix(part))
+                .and_then(|p| p.strip_suffix('-'))
+                .is_some_and(|side| {
+                    if part == "radius" {
+                        ["top-left", "top-right", "bottom-right", "bottom-left"].contains(&side)
+                    } else {
+                        SIDES.contains(&side)
+                    }
+                })
+        }
+        _ => false,
+    }
+}
+
+/// css_inline merges fallback declarations by exact property name, so a
+/// fallback `margin-top` would still override a theme `margin`. Drop fallback
+/// declarations the theme covers with a shorthand, and put the rest before
+/// the theme's own so fallback shorthands cannot override theme longhands.
+fn drop_shadowed_fallback(themed: &str, merged: &str) -> String {
+    let mut themed = styled_elements(themed).into_iter();
+    TOKEN_RE
+        .replace_all(merged, |caps: &regex::Captures| {
+            let raw = caps[0].to_string();
+            if caps.get(3).is_none() || !caps[2].is_empty() {
+                return raw;
+            }
+            let theme = themed.next().map(|e| e.style).unwrap_or_default();
+            let Some(style) = STYLE_ATTR_RE.captures(&raw) else {
+                return raw;
+            };
+            let (own, fallback): (Vec<_>, Vec<_>) = parse_style(&style[1])
+                .into_iter()
+                .partition(|(property, _)| theme.iter().any(|(p, _)| p == property));
+            let declarations: Vec<String> = fallback
+                .into_iter()
+                .filter(|(property, _)| !theme.iter().any(|(p, _)| shorthand_covers(p, property)))
+                .chain(own)
+                .map(|(property, value)| format!("{}: {}", property, value))
+                .collect();
+            let range = style.get(0).map(|m| m.range()).unwrap_or_default();
+            format!(
+                "{} style=\"{}\"{}",
+                &raw[..range.start],
+                declarations.join(";"),
+                &raw[range.end..]
+            )
+        })
+        .into_owned()
+}
+
+/// Inline the theme first and the fallback second. css_inline keeps
+/// declarations an element already has, so fallback rules only fill in the
+/// properties the theme left unset, whatever their selector specificity.
+fn inline_layers(body_html: &str, theme_css: &str) -> Result<InlinedLayers, String> {
+    let resolved = resolve_css_variables(theme_css);
+    let themed =
+        css_inline::inline(&wrap_document(body_html, &resolved.css)).map_err(|e| e.to_string())?;
+    let merged = css_inline::CSSInliner::options()
+        .extra_css(Some(Cow::Borrowed(get_fallback_css())))
+        .build()
+        .inline(&themed)
+        .map_err(|e| e.to_string())?;
+    let merged = drop_shadowed_fallback(&themed, &merged);
+    Ok(InlinedLayers {
+        themed,
+        merged,
+        unresolved: resolved.unresolved,
+    })
+}
+
+/// Wrap converted HTML in the `.wechat-content` container and inline the
+/// theme CSS, layered over the fallback CSS, into `style` attributes. Custom
+/// properties are resolved first; the names of any that could not be are returned.
+pub fn inline_theme_css(body_html: &str, theme_css: &str) -> Result<(String, Vec<String>), String> {
+    let layers = inline_layers(body_html, theme_css)?;
+    Ok((layers.merged, layers.unresolved))
+}
+
+/// Split a `style` attribute into `(property, value)` pairs
+pub(crate) fn parse_style(style: &str) -> Vec<(String, String)> {
+    let mut declarations = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for c in style.chars().chain(std::iter::once(';')) {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ';') if depth == 0 => {
+                if let Some((name, value)) = current.split_once(':') {
+                    declarations.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    declarations
+}
+
+/// A start tag and its parsed `style` attribute
+struct StyledElement {
+    line: usize,
+    /// Tag name followed by its classes, e.g. `p.admonition-title`
+    element: String,
+    style: Vec<(String, String)>,
+}
+
+impl StyledElement {
+    fn has(&self, property: &str) -> bool {
+        self.style.iter().any(|(p, _)| p == property)
+    }
+}
+
+/// Every start tag in document order
+fn styled_elements(html: &str) -> Vec<StyledElement> {
+    let mut line = 0;
+    let mut elements = Vec::new();
+    for caps in TOKEN_RE.captures_iter(html) {
+        if let Some(marker) = caps.get(1) {
+            line = marker.as_str().parse().unwrap_or(line);
+            continue;
+        }
+        let Some(name) = caps.get(3) else {
+            continue;
+        };
+        if !caps[2].is_empty() {
+            continue;
+        }
+        let attrs = parse_attrs(caps.get(4).map(|m| m.as_str()).unwrap_or_default());
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .unwrap_or_default()
+        };
+        let mut element = name.as_str().to_ascii_lowercase();
+        for class in attr("class").split_whitespace() {
+            element.push('.');
+            element.push_str(class);
+        }
+        elements.push(StyledElement {
+            line,
+            element,
+            style: parse_style(attr("style")),
+        });
+    }
+    elements
+}
+
+/// Report which layer supplied each inlined property: the element's own
+/// `style` attribute, the theme, or the fallback CSS
+pub fn trace_style_layers(
+    body_html: &str,
+    theme_css: &str,
+) -> Result<Vec<ElementStyleTrace>, String> {
+    let original = css_inline::inline(&wrap_document(body_html, "")).map_err(|e| e.to_string())?;
+    let layers = inline_layers(body_html, theme_css)?;
+
+    let original = styled_elements(&original);
+    let themed = styled_elements(&layers.themed);
+    let merged = styled_elements(&layers.merged);
+    if original.len() != merged.len() || themed.len() != merged.len() {
+        return Err("样式追踪失败：各层的 HTML 结构不一致".to_string());
+    }
+
+    let traces = merged
+        .into_iter()
+        .zip(original.iter().zip(themed.iter()))
+        .filter(|(merged, _)| !merged.style.is_empty())
+        .map(|(merged, (inline, theme))| ElementStyleTrace {
+            element: merged.element,
+            line: merged.line,
+            properties: merged
+                .style
+                .into_iter()
+                .map(|(property, value)| {
+                    let layer = if inline.has(&property) {
+                        StyleLayer::Inline
+                    } else if theme.has(&property) {
+                        StyleLayer::Theme
+                    } else {
+                        StyleLayer::Fallback
+                    };
+                    StyleSource {
+                        property,
+                        value,
+                        layer,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(traces)
+}
+
+/// Convert the Markdown and list, for every element, which CSS layer supplied
+/// each inlined property
+#[tauri::command]
+pub fn trace_theme_styles(
+    workspace: State<'_, Workspace>,
+    content: String,
+    css: String,
+    options: Option<ConvertOptions>,
+) -> Result<Vec<ElementStyleTrace>, String> {
+    let options = options.unwrap_or_default();
+    let body = crate::render_body(&workspace, &content, &options, true)?;
+    let css =
+        crate::theme_tokens::theme_css_for(&css, &options, body.meta.as_ref(), &mut Vec::new())?;
+    trace_style_layers(&body.html, &css)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_overrides_fallback_per_property() {
+        let body = "<!--wxline:1--><p>text</p>\n<!--wxline:3--><p style=\"color: blue\">own</p>";
+        // A bare `p` selector is less specific than the fallback's
+        // `.wechat-content p`, but it still wins
+        let theme = "p { margin-bottom: 0; color: red; }";
+
+        let (html, _) = inline_theme_css(body, theme).unwrap();
+        assert!(html.contains("margin-bottom: 0"));
+        assert!(!html.contains("margin-bottom: 16px"));
+        assert!(html.contains("text-align: justify"));
+
+        let traces = trace_style_layers(body, theme).unwrap();
+        let layer = |line: usize, property: &str| {
+            traces
+                .iter()
+                .find(|t| t.line == line && t.element == "p")
+                .and_then(|t| t.properties.iter().find(|p| p.property == property))
+                .map(|p| p.layer)
+        };
+        assert_eq!(layer(1, "margin-bottom"), Some(StyleLayer::Theme));
+        assert_eq!(layer(1, "text-align"), Some(StyleLayer::Fallback));
+        assert_eq!(layer(3, "color"), Some(StyleLayer::Inline));
+        assert_eq!(traces[0].element, "div.wechat-content");
+    }
+
+    #[test]
+    fn test_theme_shorthands_shadow_fallback_longhands() {
+        let body =
+            "<!--wxline:1--><h2>title</h2>\n<!--wxline:3--><blockquote><p>q</p></blockquote>";
+        let theme = "h2 { margin: 0; border: none; } blockquote { background: #fff; margin-left: 8px; border-left-color: red; }";
+
+        let (html, _) = inline_theme_css(body, theme).unwrap();
+        let start_tag = |name: &str| {
+            let rest = &html[html.find(&format!("<{}", name)).unwrap()..];
+            rest[..rest.find('>').unwrap()].to_string()
+        };
+        let h2 = start_tag("h2");
+        assert!(h2.contains("margin: 0") && h2.contains("border: none"));
+        assert!(!h2.contains("margin-top") && !h2.contains("border-left"));
+        assert!(h2.contains("font-size: 20px"));
+
+        // The fallback `margin` and `border-left` shorthands come before the
+        // theme longhands so they cannot override them
+        let quote = start_tag("blockquote");
+        assert!(!quote.contains("background-color"));
+        let position = |needle: &str| quote.find(needle).unwrap();
+        assert!(position("margin: 16px 0") < position("margin-left: 8px"));
+        assert!(position("border-left: 4px") < position("border-left-color: red"));
+
+        assert!(shorthand_covers("border", "border-top-color"));
+        assert!(!shorthand_covers("border", "border-radius"));
+        assert!(shorthand_covers("border-radius", "border-top-left-radius"));
+        assert!(shorthand_covers("font", "line-height"));
+        assert!(!shorthand_covers("margin", "margin"));
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+/// Nested `var()` references deeper than this are treated as a cycle
+const MAX_VAR_DEPTH: usize = 32;
+/// Rules whose custom properties every other rule inherits
+const ROOT_SELECTORS: &[&str] = &[":root", "html", "body", ".wechat-content"];
+
+/// CSS with every `var()` replaced by a concrete value
+pub struct ResolvedCss {
+    pub css: String,
+    /// Variables that were referenced without a definition or fallback,
+    /// or that refer to themselves
+    pub unresolved: Vec<String>,
+}
+
+/// Split a stylesheet into `(text, terminator)` pieces at `{`, `}` and `;`,
+/// skipping over comments, strings and parentheses
+pub(crate) fn tokenize(css: &str) -> Vec<(String, char)> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut chars = css.chars().peekable();
+    let mut paren_depth = 0usize;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '"' | '\'' => {
+                current.push(c);
+                let mut escaped = false;
+                for s in chars.by_ref() {
+                    current.push(s);
+                    if escaped {
+                        escaped = false;
+                    } else if s == '\\' {
+                        escaped = true;
+                    } else if s == c {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                paren_depth += 1;
+                current.push(c);
+            }
+            ')' => {
+                paren_depth = paren_depth.saturating_sub(1);
+                current.push(c);
+            }
+            ';' if paren_depth > 0 => current.push(c),
+            '{' | '}' | ';' => {
+                pieces.push((std::mem::take(&mut current), c));
+                paren_depth = 0;
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        pieces.push((current, '\0'));
+    }
+    pieces
+}
+
+/// `(name, value)` when the piece is a declaration inside a rule block
+pub(crate) fn split_declaration(piece: &str) -> Option<(&str, &str)> {
+    let (name, value) = piece.split_once(':')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((name, value.trim()))
+}
+
+/// Find the `)` that closes the parenthesis opened just before `start`
+fn closing_paren(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 1usize;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `var()` arguments into the name and the optional fallback at the
+/// first top-level comma
+fn split_var_args(args: &str) -> (&str, Option<&str>) {
+    let mut depth = 0usize;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => return (args[..i].trim(), Some(args[i + 1..].trim())),
+            _ => {}
+        }
+    }
+    (args.trim(), None)
+}
+
+fn is_root_selector(selector: &str) -> bool {
+    selector
+        .split(',')
+        .all(|s| ROOT_SELECTORS.contains(&s.trim()))
+}
+
+struct Resolver<'a> {
+    /// Definitions in top-level root rules
+    global: &'a HashMap<String, String>,
+    /// Definitions in the rule being resolved, which win over the global ones
+    local: &'a HashMap<String, String>,
+    unresolved: Vec<String>,
+}
+
+impl Resolver<'_> {
+    /// Substitute every `var()` in `value`. Returns `None` if any reference
+    /// cannot be resolved, in which case the declaration should be dropped.
+    fn resolve(&mut self, value: &str, stack: &mut Vec<String>) -> Option<String> {
+        let mut output = String::new();
+        let mut rest = value;
+        while let Some(start) = rest.find("var(") {
+            output.push_str(&rest[..start]);
+            let args_start = start + "var(".len();
+            let end = closing_paren(rest, args_start)?;
+            let (name, fallback) = split_var_args(&rest[args_start..end]);
+            output.push_str(&self.lookup(name, fallback, stack)?);
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+        Some(output)
+    }
+
+    fn lookup(
+        &mut self,
+        name: &str,
+        fallback: Option<&str>,
+        stack: &mut Vec<String>,
+    ) -> Option<String> {
+        let cyclic = stack.iter().any(|n| n == name) || stack.len() >= MAX_VAR_DEPTH;
+        if !cyclic {
+            if let Some(value) = self.local.get(name).or_else(|| self.global.get(name)) {
+                stack.push(name.to_string());
+                let resolved = self.resolve(value, stack);
+                stack.pop();
+                if resolved.is_some() {
+                    return resolved;
+                }
+            }
+        }
+        match fallback {
+            Some(fallback) => self.resolve(fallback, stack),
+            None => {
+                if !self.unresolved.iter().any(|n| n == name) {
+                    self.unresolved.push(name.to_string());
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Resolve CSS custom properties into concrete values, since WeChat drops
+/// `var()` and the properties themselves. Properties defined on a top-level
+/// `:root` or `.wechat-content` rule apply everywhere; those defined in any
+/// other rule, or inside `@media` and other at-rules, only apply within that
+/// rule. Declarations that still cannot be resolved are removed so the
+/// fallback styles apply instead.
+pub fn resolve_css_variables(css: &str) -> ResolvedCss {
+    let pieces = tokenize(css);
+
+    let mut global = HashMap::new();
+    // Definitions of each block, numbered in the order the blocks open
+    let mut locals: Vec<HashMap<String, String>> = Vec::new();
+    let mut open: Vec<(usize, &str)> = Vec::new();
+    for (piece, terminator) in &pieces {
+        if let (Some(&(block, _)), ';' | '}') = (open.last(), terminator) {
+            if let Some((name, value)) = split_declaration(piece) {
+                if name.starts_with("--") {
+                    locals[block].insert(name.to_string(), value.to_string());
+                    if let [(_, selector)] = open.as_slice() {
+                        if is_root_selector(selector) {
+                            global.insert(name.to_string(), value.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        match terminator {
+            '{' => {
+                open.push((locals.len(), piece.trim()));
+                locals.push(HashMap::new());
+            }
+            '}' => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let outside = HashMap::new();
+    let mut resolver = Resolver {
+        global: &global,
+        local: &outside,
+        unresolved: Vec::new(),
+    };
+    let mut blocks: Vec<usize> = Vec::new();
+    let mut opened = 0usize;
+    let mut output = String::with_capacity(css.len());
+    for (piece, terminator) in &pieces {
+        let declaration = match terminator {
+            ';' | '}' if !blocks.is_empty() => split_declaration(piece),
+            _ => None,
+        };
+        match declaration {
+            Some((name, _)) if name.starts_with("--") => {}
+            Some((name, value)) if value.contains("var(") => {
+                if let Some(value) = resolver.resolve(value, &mut Vec::new()) {
+                    output.push_str(&format!("{}: {};", name, value));
+                }
+            }
+            Some((name, value)) => output.push_str(&format!("{}: {};", name, value)),
+            None => {
+                output.push_str(piece.trim());
+                if *terminator == ';' {
+                    output.push(';');
+                }
+            }
+        }
+
+        resolver.local = match terminator {
+            '{' => &locals[opened],
+            '}' => blocks.iter().rev().nth(1).map_or(&outside, |&b| &locals[b]),
+            _ => resolver.local,
+        };
+        match terminator {
+            '{' => {
+                blocks.push(opened);
+                opened += 1;
+                output.push_str(" {\n");
+            }
+            '}' => {
+                blocks.pop();
+                output.push_str("\n}\n");
+            }
+            ';' if !blocks.is_empty() => output.push('\n'),
+            _ => {}
+        }
+    }
+
+    ResolvedCss {
+        css: output,
+        unresolved: resolver.unresolved,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_nested_references_and_fallbacks() {
+        let css = r#":root { --primary: #07c160; --accent: var(--primary); }
+/* comment; with { braces } */
+h1 { color: var(--accent); border-left: 4px solid var(--missing, var(--primary)); }
+p { margin: var(--gap, 0 0 16px 0); font-family: "a;b", serif; }"#;
+        let resolved = resolve_css_variables(css);
+
+        assert!(resolved.css.contains("color: #07c160;"));
+        assert!(resolved.css.contains("border-left: 4px solid #07c160;"));
+        assert!(resolved.css.contains("margin: 0 0 16px 0;"));
+        assert!(resolved.css.contains("font-family: \"a;b\", serif;"));
+        assert!(!resolved.css.contains("--primary"));
+        assert!(!resolved.css.contains("comment"));
+        assert!(resolved.unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_reports_missing_and_cyclic_variables() {
+        let css = ".wechat-content { --a: var(--b); --b: var(--a); color: var(--a); }
+h2 { color: var(--nope); font-size: 20px; }
+@media (max-width: 600px) { h2 { color: var(--a, red); } }";
+        let resolved = resolve_css_variables(css);
+
+        assert!(!resolved.css.contains("var("));
+        assert!(resolved.css.contains("font-size: 20px;"));
+        assert!(resolved.css.contains("color: red;"));
+        assert!(resolved.css.contains("@media (max-width: 600px) {"));
+        assert_eq!(resolved.unresolved, ["--a", "--b", "--nope"]);
+    }
+
+    #[test]
+    fn test_scoped_definitions_do_not_leak() {
+        let css = ":root { --x: #333; }
+@media (prefers-color-scheme: dark) { :root { --x: #eee; } }
+.wechat-content blockquote { --x: #999; color: var(--x); }
+p { color: var(--x); }";
+        let resolved = resolve_css_variables(css);
+
+        let (blockquote, p) = resolved.css.split_once("\np {").unwrap();
+        assert!(blockquote.contains("color: #999;"));
+        assert!(p.contains("color: #333;"));
+        assert!(resolved.unresolved.is_empty());
+    }
+}
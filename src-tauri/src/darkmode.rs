@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use crate::color::{adjust_for_contrast, contrast_ratio, find_color, parse_color, Rgba};
+use crate::css::parse_style;
+use crate::lint::{parse_attrs, TOKEN_RE};
+use crate::models::ConvertWarning;
+
+/// Page background of the WeChat reader in dark mode
+const DARK_BACKGROUND: Rgba = Rgba::rgb(0x19, 0x19, 0x19);
+/// Backgrounds at or below this luminance are already dark and kept as is
+const DARK_LUMINANCE: f64 = 0.05;
+/// WCAG AA contrast for body text
+const MIN_CONTRAST: f64 = 4.5;
+/// A text colour whose lightness moves further than this in dark mode no
+/// longer looks like the theme's colour
+const MAX_LIGHTNESS_SHIFT: f64 = 0.2;
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "wbr",
+];
+
+/// The background WeChat's reader paints in place of `background` in dark mode
+fn dark_background(background: Rgba) -> Rgba {
+    let background = background.over(Rgba::rgb(255, 255, 255));
+    if background.luminance() <= DARK_LUMINANCE {
+        return background;
+    }
+    // White maps onto the reader's own page background, lighter greys stay
+    // slightly lighter than it
+    let (_, _, page) = DARK_BACKGROUND.to_hsl();
+    let (h, s, l) = background.to_hsl();
+    Rgba::from_hsl(h, s * 0.6, page + (1.0 - l) * 0.3)
+}
+
+/// The text colour to use on `dark_bg`: the original if it is readable,
+/// otherwise the same hue lightened until it is
+fn dark_text(color: Rgba, light_bg: Rgba, dark_bg: Rgba) -> Rgba {
+    let color = color.over(light_bg);
+    adjust_for_contrast(color, dark_bg, MIN_CONTRAST).unwrap_or(Rgba::rgb(0xe6, 0xe6, 0xe6))
+}
+
+fn css_rgb(color: Rgba) -> String {
+    format!("rgb({}, {}, {})", color.r, color.g, color.b)
+}
+
+/// Effective colours of an open element, in light and dark mode
+#[derive(Clone, Copy)]
+struct Colors {
+    text: Rgba,
+    background: Rgba,
+    dark_text: Rgba,
+    dark_background: Rgba,
+}
+
+struct OpenElement {
+    name: String,
+    colors: Colors,
+}
+
+/// Result of checking the inlined HTML for WeChat's dark mode
+pub struct DarkModeOutput {
+    pub html: String,
+    pub warnings: Vec<ConvertWarning>,
+}
+
+/// Check the inlined colours against WeChat's dark mode and, with
+/// `emit_hints`, annotate each coloured element with the `data-darkmode-*`
+/// attributes the official editor writes, so the reader uses our colours
+/// instead of inverting them on its own.
+pub fn apply_dark_mode(html: &str, emit_hints: bool) -> DarkModeOutput {
+    let root = Colors {
+        text: Rgba::rgb(0, 0, 0),
+        background: Rgba::rgb(255, 255, 255),
+        dark_text: Rgba::rgb(0xa3, 0xa3, 0xa3),
+        dark_background: DARK_BACKGROUND,
+    };
+    let mut stack: Vec<OpenElement> = Vec::new();
+    let mut output = String::with_capacity(html.len());
+    let mut last = 0;
+    let mut warnings = Vec::new();
+    let mut reported = HashSet::new();
+
+    for caps in TOKEN_RE.captures_iter(html) {
+        let Some(name) = caps.get(3) else {
+            continue;
+        };
+        let name = name.as_str().to_ascii_lowercase();
+        if !caps[2].is_empty() {
+            if let Some(index) = stack.iter().rposition(|open| open.name == name) {
+                stack.truncate(index);
+            }
+            continue;
+        }
+
+        let attrs_source = caps.get(4).map(|m| m.as_str()).unwrap_or_default();
+        let attrs = parse_attrs(attrs_source);
+        let style = attrs
+            .iter()
+            .find(|(key, _)| key == "style")
+            .map(|(_, value)| parse_style(value))
+            .unwrap_or_default();
+        let own_text = style
+            .iter()
+            .rev()
+            .find(|(p, _)| p == "color")
+            .and_then(|(_, v)| parse_color(v));
+        let own_background = style
+            .iter()
+            .rev()
+            .find(|(p, _)| p == "background-color" || p == "background")
+            .and_then(|(_, v)| find_color(v))
+            .filter(|c| c.a > 0.0);
+
+        let parent = stack.last().map_or(root, |open| open.colors);
+        let mut colors = parent;
+        if let Some(background) = own_background {
+            colors.background = background.over(parent.background);
+            colors.dark_background = dark_background(colors.background);
+        }
+        if let Some(text) = own_text {
+            colors.text = text.over(colors.background);
+        }
+        if own_text.is_some() || own_background.is_some() {
+            colors.dark_text = dark_text(colors.text, colors.background, colors.dark_background);
+            let element = match attrs.iter().find(|(key, _)| key == "class") {
+                Some((_, class)) if !class.trim().is_empty() => {
+                    format!(
+                        "{}.{}",
+                        name,
+                        class.split_whitespace().collect::<Vec<_>>().join(".")
+                    )
+                }
+                _ => name.clone(),
+            };
+            check_colors(&element, &colors, &mut reported, &mut warnings);
+
+            if emit_hints {
+                let tag_end = caps.get(0).map_or(0, |m| m.end());
+                let insert_at = tag_end
+                    - if attrs_source.trim_end().ends_with('/') {
+                        2
+                    } else {
+                        1
+                    };
+                output.push_str(&html[last..insert_at]);
+                if let Some(text) = own_text {
+                    output.push_str(&format!(
+                        " data-darkmode-color=\"{}\" data-darkmode-original-color=\"{}\"",
+                        css_rgb(colors.dark_text),
+                        css_rgb(text.over(colors.background))
+                    ));
+                }
+                if own_background.is_some() {
+                    output.push_str(&format!(
+                        " data-darkmode-bgcolor=\"{}\" data-darkmode-original-bgcolor=\"{}\"",
+                        css_rgb(colors.dark_background),
+                        css_rgb(colors.background)
+                    ));
+                }
+                last = insert_at;
+            }
+        }
+
+        let self_closing = attrs_source.trim_end().ends_with('/');
+        if !self_closing && !VOID_ELEMENTS.contains(&name.as_str()) {
+            stack.push(OpenElement { name, colors });
+        }
+    }
+    output.push_str(&html[last..]);
+
+    DarkModeOutput {
+        html: output,
+        warnings,
+    }
+}
+
+/// Warn about colour pairs that stay unreadable or change noticeably in dark mode
+fn check_colors(
+    element: &str,
+    colors: &Colors,
+    reported: &mut HashSet<(String, String, String)>,
+    warnings: &mut Vec<ConvertWarning>,
+) {
+    let text = colors.text.to_hex();
+    let background = colors.background.to_hex();
+    let contrast = contrast_ratio(colors.dark_text, colors.dark_background);
+    let (_, saturation, lightness) = colors.text.to_hsl();
+    let (_, _, dark_lightness) = colors.dark_text.to_hsl();
+
+    let (kind, message) = if contrast < MIN_CONTRAST {
+        (
+            "darkmode-contrast",
+            format!(
+                "<{}> 的文字 {} 与背景 {} 在微信深色模式下对比度只有 {:.1}，可能看不清",
+                element, text, background, contrast
+            ),
+        )
+    } else if saturation > 0.3 && dark_lightness - lightness > MAX_LIGHTNESS_SHIFT {
+        (
+            "darkmode-color-shift",
+            format!(
+                "<{}> 的文字颜色 {} 在微信深色模式下需提亮为 {} 才能看清，建议在主题中选用更亮的颜色",
+                element,
+                text,
+                colors.dark_text.to_hex()
+            ),
+        )
+    } else {
+        return;
+    };
+    if reported.insert((
+        kind.to_string(),
+        element.to_string(),
+        text.clone() + &background,
+    )) {
+        warnings.push(ConvertWarning {
+            kind: kind.to_string(),
+            message,
+            line: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hints_and_warnings() {
+        let html = r#"<div class="wechat-content" style="color: #333; background-color: #fff">
+<h2 style="color: #07c160">标题</h2><img src="a.png" style="background: #fff"/>
+<pre style="background-color: #f6f8fa"><code style="color: #333">x</code></pre>
+<p style="color: #333">正文</p></div>"#;
+
+        let output = apply_dark_mode(html, true);
+        assert!(output.html.contains(
+            "<div class=\"wechat-content\" style=\"color: #333; background-color: #fff\" data-darkmode-color="
+        ));
+        assert!(output
+            .html
+            .contains("data-darkmode-bgcolor=\"rgb(25, 25, 25)\" data-darkmode-original-bgcolor=\"rgb(255, 255, 255)\""));
+        assert!(output
+            .html
+            .contains("data-darkmode-original-bgcolor=\"rgb(255, 255, 255)\"/>"));
+        assert_eq!(output.html.matches("data-darkmode-color=").count(), 4);
+        assert!(output
+            .warnings
+            .iter()
+            .all(|w| w.kind != "darkmode-contrast"));
+
+        let plain = apply_dark_mode(html, false);
+        assert_eq!(plain.html, html);
+        assert_eq!(plain.warnings.len(), output.warnings.len());
+    }
+
+    #[test]
+    fn test_dark_text_on_mid_background_is_reported() {
+        let html = r#"<p style="background-color: #07c160; color: #ffffff">a</p><p style="color: #123524">b</p>"#;
+        let warnings = apply_dark_mode(html, false).warnings;
+        let kinds: Vec<&str> = warnings.iter().map(|w| w.kind.as_str()).collect();
+        assert!(kinds.contains(&"darkmode-color-shift"));
+        assert!(warnings.iter().any(|w| w.message.contains("#123524")));
+    }
+}
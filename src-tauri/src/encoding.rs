@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use encoding_rs::{GB18030, UTF_16BE, UTF_16LE};
+use tauri::State;
+
+use crate::models::{LineEnding, TextEncoding, TextFormat};
+use crate::workspace::Workspace;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// CRLF when most line breaks are CRLF, so one stray LF does not flip a file
+fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count();
+    if crlf > 0 && crlf * 2 >= lf {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Decode a markdown file, detecting its encoding from a BOM or, without one,
+/// trying UTF-8 and then GB18030. Returns the text with the BOM removed and
+/// line breaks normalised to `\n`, plus what is needed to write it back.
+pub(crate) fn decode_text(bytes: &[u8]) -> Result<(String, TextFormat), String> {
+    let unreadable = || "无法识别文件编码，仅支持 UTF-8、UTF-16 和 GBK/GB18030".to_string();
+    let (text, encoding, bom) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        let text = String::from_utf8(rest.to_vec()).map_err(|_| unreadable())?;
+        (text, TextEncoding::Utf8, true)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+        let text = UTF_16LE
+            .decode_without_bom_handling_and_without_replacement(rest)
+            .ok_or_else(unreadable)?;
+        (text.into_owned(), TextEncoding::Utf16le, true)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16BE_BOM) {
+        let text = UTF_16BE
+            .decode_without_bom_handling_and_without_replacement(rest)
+            .ok_or_else(unreadable)?;
+        (text.into_owned(), TextEncoding::Utf16be, true)
+    } else if let Ok(text) = std::str::from_utf8(bytes) {
+        (text.to_string(), TextEncoding::Utf8, false)
+    } else {
+        let text = GB18030
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .ok_or_else(unreadable)?;
+        (text.into_owned(), TextEncoding::Gb18030, false)
+    };
+
+    let line_ending = detect_line_ending(&text);
+    let text = if text.contains("\r\n") {
+        text.replace("\r\n", "\n")
+    } else {
+        text
+    };
+    Ok((
+        text,
+        TextFormat {
+            encoding,
+            bom,
+            line_ending,
+        },
+    ))
+}
+
+/// Encode editor text back into the bytes of a file stored as `format`
+pub(crate) fn encode_text(text: &str, format: &TextFormat) -> Vec<u8> {
+    let text = match format.line_ending {
+        LineEnding::Lf => text.replace("\r\n", "\n"),
+        LineEnding::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+    };
+    match format.encoding {
+        TextEncoding::Utf8 => {
+            let bom: &[u8] = if format.bom { UTF8_BOM } else { b"" };
+            [bom, text.as_bytes()].concat()
+        }
+        // GB18030 maps every Unicode character, so nothing is lost
+        TextEncoding::Gb18030 => GB18030.encode(&text).0.into_owned(),
+        TextEncoding::Utf16le => {
+            let bom: &[u8] = if format.bom { UTF16LE_BOM } else { b"" };
+            let units = text.encode_utf16().flat_map(u16::to_le_bytes);
+            bom.iter().copied().chain(units).collect()
+        }
+        TextEncoding::Utf16be => {
+            let bom: &[u8] = if format.bom { UTF16BE_BOM } else { b"" };
+            let units = text.encode_utf16().flat_map(u16::to_be_bytes);
+            bom.iter().copied().chain(units).collect()
+        }
+    }
+}
+
+/// Read and decode a text file in any encoding `decode_text` understands
+pub(crate) fn read_text(path: &Path) -> Result<(String, TextFormat), String> {
+    let bytes = fs::read(path).map_err(|e| format!("无法读取文件: {}", e))?;
+    decode_text(&bytes)
+}
+
+/// The format each opened file was read in, so saving keeps it
+#[derive(Default)]
+pub struct FileFormats(Mutex<HashMap<PathBuf, TextFormat>>);
+
+impl FileFormats {
+    pub fn remember(&self, path: &Path, format: TextFormat) {
+        if let Ok(mut formats) = self.0.lock() {
+            formats.insert(path.to_path_buf(), format);
+        }
+    }
+
+    /// The remembered format of `path`, else the format of the file on disk,
+    /// else UTF-8 with LF line breaks
+    pub fn format_of(&self, path: &Path) -> TextFormat {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|formats| formats.get(path).copied())
+            .or_else(|| read_text(path).ok().map(|(_, format)| format))
+            .unwrap_or_default()
+    }
+}
+
+/// Encoding, BOM and line-ending style a file will be saved with
+#[tauri::command]
+pub fn get_file_format(
+    workspace: State<'_, Workspace>,
+    formats: State<'_, FileFormats>,
+    path: String,
+) -> Result<TextFormat, String> {
+    Ok(formats.format_of(&workspace.resolve(&path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_and_encode_round_trip() {
+        let utf8_bom_crlf = b"\xEF\xBB\xBF# Title\r\n\r\nbody\r\n".to_vec();
+        let (text, format) = decode_text(&utf8_bom_crlf).unwrap();
+        assert_eq!(text, "# Title\n\nbody\n");
+        assert_eq!(format.encoding, TextEncoding::Utf8);
+        assert!(format.bom);
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+        assert_eq!(encode_text(&text, &format), utf8_bom_crlf);
+
+        // "中文" in GBK, as exported by older Windows blog tools
+        let gbk = b"\xD6\xD0\xCE\xC4\r\n".to_vec();
+        let (text, format) = decode_text(&gbk).unwrap();
+        assert_eq!(text, "中文\n");
+        assert_eq!(format.encoding, TextEncoding::Gb18030);
+        assert_eq!(encode_text(&text, &format), gbk);
+        assert_eq!(
+            encode_text(&text, &TextFormat::default()),
+            "中文\n".as_bytes()
+        );
+
+        let utf16 = [UTF16LE_BOM, &[b'a', 0, b'\n', 0]].concat();
+        let (text, format) = decode_text(&utf16).unwrap();
+        assert_eq!(text, "a\n");
+        assert_eq!(format.encoding, TextEncoding::Utf16le);
+        assert_eq!(encode_text(&text, &format), utf16);
+
+        // Mostly LF with one CRLF stays LF
+        let (_, format) = decode_text(b"a\nb\nc\r\nd\n").unwrap();
+        assert_eq!(format.line_ending, LineEnding::Lf);
+        assert!(decode_text(b"\xFF\xFF\xFF").is_err());
+    }
+}
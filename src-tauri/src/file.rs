@@ -1,23 +1,350 @@
-/// Read markdown file content
+use std::cmp::Reverse;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::State;
+
+use crate::encoding::{decode_text, encode_text, FileFormats};
+use crate::models::{BackupInfo, TextFormat};
+use crate::watch::FileWatch;
+use crate::workspace::Workspace;
+
+/// Hidden folder next to a saved file that holds its backups
+const BACKUP_DIR: &str = ".wxtyper-backups";
+/// Backups kept per file when the frontend does not say otherwise
+const DEFAULT_BACKUP_COUNT: usize = 10;
+
+/// Numbers the temporary files of `write_atomic`, since commands run on a
+/// thread pool and may write the same file at once
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+fn file_name(path: &Path) -> Result<String, String> {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("无效的文件路径: {}", path.display()))
+}
+
+fn write_and_rename(temp: &Path, path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(temp)?;
+    file.write_all(bytes)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.sync_all()?;
+    drop(file);
+    fs::rename(temp, path)?;
+    // Make the rename itself durable; not every platform can open a directory
+    if let Ok(dir) = fs::File::open(parent_dir(path)) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Write `bytes` to a temporary file beside `path`, flush it to disk and rename
+/// it over `path`, so a crash or full disk leaves either the old or the new
+/// contents and never a truncated file
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let temp = parent_dir(path).join(format!(
+        ".{}.{}.{}.tmp",
+        file_name(path)?,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    write_and_rename(&temp, path, bytes).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("保存文件失败: {}", e)
+    })
+}
+
+fn backup_dir(path: &Path) -> PathBuf {
+    parent_dir(path).join(BACKUP_DIR)
+}
+
+/// Timestamp of a backup of `name`, from its `<name>.<millis>.bak` file name
+fn backup_timestamp(name: &str, backup_name: &str) -> Option<u64> {
+    backup_name
+        .strip_prefix(name)?
+        .strip_prefix('.')?
+        .strip_suffix(".bak")?
+        .parse()
+        .ok()
+}
+
+/// Backups of `path`, newest first
+fn list_backups_of(path: &Path) -> Result<Vec<BackupInfo>, String> {
+    let name = file_name(path)?;
+    let mut backups: Vec<BackupInfo> = fs::read_dir(backup_dir(path))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let timestamp = backup_timestamp(&name, &entry.file_name().to_string_lossy())?;
+            Some(BackupInfo {
+                path: entry.path().to_string_lossy().to_string(),
+                timestamp,
+                size: entry.metadata().ok()?.len(),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|b| Reverse(b.timestamp));
+    Ok(backups)
+}
+
+/// Copy the current contents of `path` into its backup folder and drop the
+/// oldest backups beyond `keep`
+fn backup_file(path: &Path, keep: usize) -> Result<(), String> {
+    if keep == 0 || !path.is_file() {
+        return Ok(());
+    }
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建备份目录: {}", e))?;
+
+    let name = file_name(path)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    // Saves within the same millisecond, or after the clock went back, still
+    // sort after every existing backup
+    let newest = list_backups_of(path)?.first().map(|b| b.timestamp + 1);
+    let timestamp = newest.map_or(now, |newest| now.max(newest));
+    let target = dir.join(format!("{}.{}.bak", name, timestamp));
+    let current = fs::read(path).map_err(|e| format!("无法读取原文件: {}", e))?;
+    write_atomic(&target, &current)?;
+
+    for old in list_backups_of(path)?.into_iter().skip(keep) {
+        let _ = fs::remove_file(old.path);
+    }
+    Ok(())
+}
+
+/// Save `content` to `path` atomically, backing up what was there first
+fn save_with_backup(path: &Path, content: &[u8], keep: usize) -> Result<(), String> {
+    let unchanged = fs::read(path).is_ok_and(|current| current == content);
+    if !unchanged {
+        backup_file(path, keep)?;
+    }
+    write_atomic(path, content)
+}
+
+/// Restore `backup` over `path` and return the restored content with its
+/// format. The version being replaced is backed up too, so a restore can be
+/// undone.
+fn restore_backup(
+    watch: &FileWatch,
+    path: &Path,
+    backup: &Path,
+    keep: usize,
+) -> Result<(String, TextFormat), String> {
+    let belongs_to_file = backup.parent() == Some(backup_dir(path).as_path())
+        && backup
+            .file_name()
+            .and_then(|n| backup_timestamp(&file_name(path).ok()?, &n.to_string_lossy()))
+            .is_some();
+    if !belongs_to_file {
+        return Err("该备份不属于当前文件".to_string());
+    }
+
+    let bytes = fs::read(backup).map_err(|e| format!("无法读取备份: {}", e))?;
+    let (content, format) = decode_text(&bytes)?;
+    watch.write_recorded(path, &bytes, || save_with_backup(path, &bytes, keep.max(1)))?;
+    Ok((content, format))
+}
+
+/// Read markdown file content, transcoded to UTF-8 with `\n` line breaks.
+/// The file's original encoding and line endings are remembered for saving.
+#[tauri::command]
+pub fn open_markdown_file(
+    workspace: State<'_, Workspace>,
+    watch: State<'_, FileWatch>,
+    formats: State<'_, FileFormats>,
+    path: String,
+) -> Result<String, String> {
+    let path = workspace.resolve(&path)?;
+    let bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let (content, format) = decode_text(&bytes)?;
+    watch.record(&path, &bytes);
+    formats.remember(&path, format);
+    Ok(content)
+}
+
+/// Save `content` to `path`, keeping the last `backup_count` versions.
+/// Refuses to overwrite changes made by another program since the file was
+/// opened or last saved, unless `force` is set. The file keeps the encoding
+/// and line endings it was opened with unless `normalize` asks for UTF-8
+/// without BOM and LF.
+pub(crate) fn save_text(
+    watch: &FileWatch,
+    formats: &FileFormats,
+    path: &Path,
+    content: &str,
+    backup_count: Option<usize>,
+    force: bool,
+    normalize: bool,
+) -> Result<(), String> {
+    if !force {
+        watch.check_unchanged(path)?;
+    }
+    let format = if normalize {
+        TextFormat::default()
+    } else {
+        formats.format_of(path)
+    };
+    let bytes = encode_text(content, &format);
+    watch.write_recorded(path, &bytes, || {
+        save_with_backup(path, &bytes, backup_count.unwrap_or(DEFAULT_BACKUP_COUNT))
+    })?;
+    formats.remember(path, format);
+    Ok(())
+}
+
+/// Save markdown file content, see `save_text`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn save_markdown_file(
+    workspace: State<'_, Workspace>,
+    watch: State<'_, FileWatch>,
+    formats: State<'_, FileFormats>,
+    path: String,
+    content: String,
+    backup_count: Option<usize>,
+    force: Option<bool>,
+    normalize: Option<bool>,
+) -> Result<(), String> {
+    save_text(
+        &watch,
+        &formats,
+        &workspace.resolve(&path)?,
+        &content,
+        backup_count,
+        force.unwrap_or(false),
+        normalize.unwrap_or(false),
+    )
+}
+
+/// List the backups of a markdown file, newest first
 #[tauri::command]
-pub fn open_markdown_file(path: String) -> Result<String, String> {
-    std::fs::read_to_string(&path).map_err(|e| e.to_string())
+pub fn list_file_backups(
+    workspace: State<'_, Workspace>,
+    path: String,
+) -> Result<Vec<BackupInfo>, String> {
+    list_backups_of(&workspace.resolve(&path)?)
 }
 
-/// Save markdown file content
+/// Restore a backup of a markdown file and return the restored content
 #[tauri::command]
-pub fn save_markdown_file(path: String, content: String) -> Result<(), String> {
-    std::fs::write(&path, content).map_err(|e| e.to_string())
+pub fn restore_file_backup(
+    workspace: State<'_, Workspace>,
+    watch: State<'_, FileWatch>,
+    formats: State<'_, FileFormats>,
+    path: String,
+    backup_path: String,
+    backup_count: Option<usize>,
+) -> Result<String, String> {
+    let path = workspace.resolve(&path)?;
+    let backup = workspace.resolve(&backup_path)?;
+    let keep = backup_count.unwrap_or(DEFAULT_BACKUP_COUNT);
+    let (content, format) = restore_backup(&watch, &path, &backup, keep)?;
+    formats.remember(&path, format);
+    Ok(content)
 }
 
 /// Save binary file (e.g., images)
 #[tauri::command]
-pub fn save_binary_file(path: String, bytes: Vec<u8>) -> Result<(), String> {
-    std::fs::write(&path, bytes).map_err(|e| e.to_string())
+pub fn save_binary_file(
+    workspace: State<'_, Workspace>,
+    path: String,
+    bytes: Vec<u8>,
+) -> Result<(), String> {
+    write_atomic(&workspace.resolve(&path)?, &bytes)
 }
 
 /// Create directory recursively
 #[tauri::command]
-pub fn create_directory(path: String) -> Result<(), String> {
-    std::fs::create_dir_all(&path).map_err(|e| e.to_string())
+pub fn create_directory(workspace: State<'_, Workspace>, path: String) -> Result<(), String> {
+    std::fs::create_dir_all(workspace.resolve(&path)?).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_keeps_rolling_backups_and_restores() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        let post = dir.join("post.md");
+
+        for version in ["v1", "v2", "v3", "v4"] {
+            save_with_backup(&post, version.as_bytes(), 2).unwrap();
+        }
+        // Saving identical content does not add a backup
+        save_with_backup(&post, b"v4", 2).unwrap();
+
+        assert_eq!(fs::read_to_string(&post).unwrap(), "v4");
+        let backups = list_backups_of(&post).unwrap();
+        let contents: Vec<String> = backups
+            .iter()
+            .map(|b| fs::read_to_string(&b.path).unwrap())
+            .collect();
+        assert_eq!(contents, ["v3", "v2"]);
+        assert!(fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .all(|e| !e.file_name().to_string_lossy().ends_with(".tmp")));
+
+        let watch = FileWatch::default();
+        let (restored, _) = restore_backup(&watch, &post, Path::new(&backups[1].path), 2).unwrap();
+        assert_eq!(restored, "v2");
+        assert_eq!(fs::read_to_string(&post).unwrap(), "v2");
+        let newest = &list_backups_of(&post).unwrap()[0];
+        assert_eq!(fs::read_to_string(&newest.path).unwrap(), "v4");
+
+        // New backups sort after existing ones even when the clock is behind them
+        let future = list_backups_of(&post).unwrap()[0].timestamp + 60_000;
+        fs::write(
+            backup_dir(&post).join(format!("post.md.{}.bak", future)),
+            "v5",
+        )
+        .unwrap();
+        save_with_backup(&post, b"v6", 2).unwrap();
+        let newest = &list_backups_of(&post).unwrap()[0];
+        assert_eq!(newest.timestamp, future + 1);
+        assert_eq!(fs::read_to_string(&newest.path).unwrap(), "v2");
+
+        let other = dir.join("other.md.1.bak");
+        fs::write(&other, [0xff, 0xfe, 0xff]).unwrap();
+        assert_eq!(
+            restore_backup(&watch, &post, &other, 2).unwrap_err(),
+            "该备份不属于当前文件"
+        );
+    }
+
+    #[test]
+    fn test_concurrent_atomic_writes_use_separate_temp_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let post = temp.path().join("post.md");
+        std::thread::scope(|scope| {
+            let writers: Vec<_> = (0..8)
+                .map(|i| {
+                    let post = &post;
+                    scope.spawn(move || write_atomic(post, format!("v{}", i).as_bytes()))
+                })
+                .collect();
+            for writer in writers {
+                writer.join().unwrap().unwrap();
+            }
+        });
+        assert!(fs::read_to_string(&post).unwrap().starts_with('v'));
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+    }
 }
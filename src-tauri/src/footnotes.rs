@@ -0,0 +1,327 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+use crate::html::escape_html;
+use crate::pipeline::Transform;
+
+/// Default heading shown above the link footnote list
+pub const DEFAULT_FOOTNOTE_HEADING: &str = "参考资料";
+
+/// Domains WeChat keeps clickable, so links to them never become footnotes
+const WECHAT_DOMAINS: &[&str] = &["mp.weixin.qq.com"];
+
+/// A whole `<a href>` element inside raw HTML
+static HTML_ANCHOR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\s[^>]*?\bhref\s*=\s*["']([^"']*)["'][^>]*>(.*?)</a\s*>"#).unwrap()
+});
+/// An opening `<a>` tag on its own, as inline HTML keeps it apart from the link text
+static HTML_ANCHOR_START_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)^<a(?:\s[^>]*)?>$").unwrap());
+static HTML_ANCHOR_END_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^</a\s*>$").unwrap());
+static HREF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\bhref\s*=\s*["']([^"']*)["']"#).unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// Options for turning external links into numbered footnotes
+pub struct FootnoteOptions {
+    /// Extra domains whose links stay clickable (subdomains match too)
+    pub allowed_domains: Vec<String>,
+    /// Heading above the footnote list; `None` or empty omits it
+    pub heading: Option<String>,
+}
+
+impl Default for FootnoteOptions {
+    fn default() -> Self {
+        FootnoteOptions {
+            allowed_domains: Vec::new(),
+            heading: Some(DEFAULT_FOOTNOTE_HEADING.to_string()),
+        }
+    }
+}
+
+impl FootnoteOptions {
+    fn is_allowed(&self, url: &str) -> bool {
+        let Some(host) = url_host(url) else {
+            return false;
+        };
+        WECHAT_DOMAINS
+            .iter()
+            .copied()
+            .chain(self.allowed_domains.iter().map(|d| d.as_str()))
+            .map(|d| d.trim().trim_start_matches("*.").to_ascii_lowercase())
+            .filter(|d| !d.is_empty())
+            .any(|d| host == d || host.ends_with(&format!(".{}", d)))
+    }
+}
+
+/// Extract the lowercased host of an absolute `http(s)://` URL
+fn url_host(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_ascii_lowercase())
+    }
+}
+
+enum LinkAction {
+    Keep,
+    Unwrap,
+    Footnote { number: usize, first: bool },
+}
+
+/// Where a link opens or closes, in markdown or in inline HTML
+enum LinkEdge {
+    /// `None` for an `<a>` without `href`, which is left alone
+    Open(Option<String>),
+    Close,
+}
+
+impl LinkEdge {
+    fn of(event: &Event) -> Option<Self> {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                Some(LinkEdge::Open(Some(dest_url.to_string())))
+            }
+            Event::End(TagEnd::Link) => Some(LinkEdge::Close),
+            Event::InlineHtml(html) if HTML_ANCHOR_END_RE.is_match(html.trim()) => {
+                Some(LinkEdge::Close)
+            }
+            Event::InlineHtml(html) if HTML_ANCHOR_START_RE.is_match(html.trim()) => Some(
+                LinkEdge::Open(HREF_RE.captures(html).map(|c| c[1].to_string())),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Footnotes collected so far, numbered by first appearance
+#[derive(Default)]
+struct FootnoteList {
+    numbers: HashMap<String, usize>,
+    /// `(url, label)` in number order
+    entries: Vec<(String, String)>,
+}
+
+impl FootnoteList {
+    fn action(&mut self, url: &str, options: &FootnoteOptions) -> LinkAction {
+        let url = url.trim();
+        if url.is_empty() || url.starts_with('#') {
+            LinkAction::Unwrap
+        } else if options.is_allowed(url) {
+            LinkAction::Keep
+        } else if let Some(&number) = self.numbers.get(url) {
+            LinkAction::Footnote {
+                number,
+                first: false,
+            }
+        } else {
+            self.entries.push((url.to_string(), String::new()));
+            let number = self.entries.len();
+            self.numbers.insert(url.to_string(), number);
+            LinkAction::Footnote {
+                number,
+                first: true,
+            }
+        }
+    }
+
+    /// Footnote the complete anchors inside a raw HTML event
+    fn rewrite_html(&mut self, html: &str, options: &FootnoteOptions) -> String {
+        HTML_ANCHOR_RE
+            .replace_all(html, |caps: &Captures| {
+                let text = &caps[2];
+                match self.action(&caps[1], options) {
+                    LinkAction::Keep => caps[0].to_string(),
+                    LinkAction::Unwrap => text.to_string(),
+                    LinkAction::Footnote { number, first } => {
+                        if first {
+                            self.entries[number - 1].1 = TAG_RE.replace_all(text, "").to_string();
+                        }
+                        format!("{}{}", text, footnote_ref(number))
+                    }
+                }
+            })
+            .into_owned()
+    }
+}
+
+fn footnote_ref(number: usize) -> String {
+    format!(" <span class=\"footnote-ref\">{}</span>", number)
+}
+
+/// Replace external links in the event stream with footnote references.
+/// Each unique URL gets one number; in-page anchors lose their link but keep the
+/// text, and allowlisted domains stay clickable. `<a href>` tags written as raw
+/// HTML are treated the same way.
+pub fn links_to_footnotes<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    options: &FootnoteOptions,
+) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut footnotes = FootnoteList::default();
+    let mut stack: Vec<LinkAction> = Vec::new();
+
+    for event in events {
+        match LinkEdge::of(&event) {
+            Some(LinkEdge::Open(url)) => {
+                let action = match url {
+                    Some(url) => footnotes.action(&url, options),
+                    None => LinkAction::Keep,
+                };
+                if matches!(action, LinkAction::Keep) {
+                    output.push(event);
+                }
+                stack.push(action);
+            }
+            Some(LinkEdge::Close) => match stack.pop() {
+                Some(LinkAction::Keep) | None => output.push(event),
+                Some(LinkAction::Unwrap) => {}
+                Some(LinkAction::Footnote { number, .. }) => {
+                    output.push(Event::InlineHtml(CowStr::from(footnote_ref(number))));
+                }
+            },
+            None => match event {
+                Event::Text(ref text) | Event::Code(ref text) => {
+                    // Remember the first link text for each URL to label its footnote
+                    if let Some(LinkAction::Footnote {
+                        number,
+                        first: true,
+                    }) = stack.last()
+                    {
+                        footnotes.entries[number - 1].1.push_str(text);
+                    }
+                    output.push(event);
+                }
+                Event::Html(html) => {
+                    output.push(Event::Html(footnotes.rewrite_html(&html, options).into()));
+                }
+                Event::InlineHtml(html) => {
+                    let html = footnotes.rewrite_html(&html, options);
+                    output.push(Event::InlineHtml(html.into()));
+                }
+                _ => output.push(event),
+            },
+        }
+    }
+
+    if !footnotes.entries.is_empty() {
+        output.push(Event::Html(CowStr::from(render_footnote_section(
+            &footnotes.entries,
+            options.heading.as_deref(),
+        ))));
+    }
+
+    output
+}
+
+/// Turns external links into numbered footnotes
+pub struct FootnoteTransform {
+    pub options: FootnoteOptions,
+}
+
+impl Transform for FootnoteTransform {
+    fn name(&self) -> &'static str {
+        "footnotes"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        links_to_footnotes(events.into_iter(), &self.options)
+    }
+}
+
+fn render_footnote_section(footnotes: &[(String, String)], heading: Option<&str>) -> String {
+    let mut section = String::from("<div class=\"footnotes\">\n");
+    if let Some(heading) = heading.map(str::trim).filter(|h| !h.is_empty()) {
+        section.push_str(&format!(
+            "<p class=\"footnotes-title\">{}</p>\n",
+            escape_html(heading)
+        ));
+    }
+    section.push_str("<ol>\n");
+    for (url, label) in footnotes {
+        let label = label.trim();
+        if label.is_empty() || label == url {
+            section.push_str(&format!(
+                "<li><span class=\"footnote-url\">{}</span></li>\n",
+                escape_html(url)
+            ));
+        } else {
+            section.push_str(&format!(
+                "<li><span class=\"footnote-text\">{}: </span><span class=\"footnote-url\">{}</span></li>\n",
+                escape_html(label),
+                escape_html(url)
+            ));
+        }
+    }
+    section.push_str("</ol>\n</div>\n");
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Parser};
+
+    fn render(markdown: &str, options: &FootnoteOptions) -> String {
+        let events = links_to_footnotes(Parser::new(markdown), options);
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        output
+    }
+
+    #[test]
+    fn test_duplicate_urls_share_one_footnote() {
+        let html = render(
+            "[A](https://a.com) and [again](https://a.com) and [B](https://b.com)",
+            &FootnoteOptions::default(),
+        );
+        assert!(html.contains("A <span class=\"footnote-ref\">1</span>"));
+        assert!(html.contains("again <span class=\"footnote-ref\">1</span>"));
+        assert!(html.contains("B <span class=\"footnote-ref\">2</span>"));
+        assert_eq!(html.matches("<li>").count(), 2);
+        assert!(html.contains("<p class=\"footnotes-title\">参考资料</p>"));
+    }
+
+    #[test]
+    fn test_anchors_code_and_allowlist_are_not_footnoted() {
+        let options = FootnoteOptions {
+            allowed_domains: vec!["example.com".to_string()],
+            heading: None,
+        };
+        let html = render(
+            "[top](#intro) `[x](https://code.com)` [wx](https://mp.weixin.qq.com/s/abc) [docs](https://docs.example.com/a)",
+            &options,
+        );
+        assert!(!html.contains("href=\"#intro\""));
+        assert!(html.contains("top"));
+        assert!(html.contains("<code>[x](https://code.com)</code>"));
+        assert!(html.contains("<a href=\"https://mp.weixin.qq.com/s/abc\">wx</a>"));
+        assert!(html.contains("<a href=\"https://docs.example.com/a\">docs</a>"));
+        assert!(!html.contains("class=\"footnotes\""));
+    }
+
+    #[test]
+    fn test_raw_html_anchors_are_footnoted() {
+        let html = render(
+            "Inline <a href=\"https://a.com\">A</a> and [md](https://a.com) and <a name=\"x\">x</a>\n\n<p>Block <a class=\"l\" href='https://b.com'><b>B</b></a> <a href=\"#top\">top</a></p>\n",
+            &FootnoteOptions::default(),
+        );
+        assert!(!html.contains("href=\"https://"));
+        assert!(html.contains("A <span class=\"footnote-ref\">1</span>"));
+        assert!(html.contains("md <span class=\"footnote-ref\">1</span>"));
+        assert!(html.contains("<a name=\"x\">x</a>"));
+        assert!(html.contains("<b>B</b> <span class=\"footnote-ref\">2</span>"));
+        assert!(html.contains("top</p>"));
+        assert!(html.contains("<span class=\"footnote-text\">B: </span>"));
+        assert_eq!(html.matches("<li>").count(), 2);
+    }
+}
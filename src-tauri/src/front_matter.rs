@@ -0,0 +1,240 @@
+use serde_json::{Map, Value};
+
+use crate::models::ArticleMeta;
+
+enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+/// Split leading YAML (`---`) or TOML (`+++`) front matter from a markdown document.
+/// Returns the parsed metadata (if any) and the remaining markdown body.
+pub fn split_front_matter(content: &str) -> Result<(Option<ArticleMeta>, &str), String> {
+    let text = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let (format, fence) = match text.lines().next().map(str::trim_end) {
+        Some("---") => (FrontMatterFormat::Yaml, "---"),
+        Some("+++") => (FrontMatterFormat::Toml, "+++"),
+        _ => return Ok((None, content)),
+    };
+
+    let after_open = match text.find('\n') {
+        Some(idx) => &text[idx + 1..],
+        None => return Ok((None, content)),
+    };
+
+    let mut offset = 0;
+    let mut closing = None;
+    for line in after_open.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == fence || (matches!(format, FrontMatterFormat::Yaml) && trimmed == "...") {
+            closing = Some((offset, offset + line.len()));
+            break;
+        }
+        offset += line.len();
+    }
+
+    let (raw_end, body_start) = match closing {
+        Some(range) => range,
+        None => return Ok((None, content)),
+    };
+    let raw = &after_open[..raw_end];
+    let body = &after_open[body_start..];
+
+    let map = match format {
+        // `---` also opens a thematic break, so a block that does not start
+        // like a YAML mapping is ordinary markdown rather than broken front matter
+        FrontMatterFormat::Yaml => {
+            let value = serde_yaml::from_str::<serde_yaml::Value>(raw)
+                .map_err(|e| format!("YAML front matter 解析失败: {}", e))
+                .and_then(|yaml| serde_json::to_value(yaml).map_err(|e| e.to_string()));
+            match value {
+                Ok(Value::Object(map)) => map,
+                Ok(Value::Null) => Map::new(),
+                _ if !looks_like_mapping(raw) => return Ok((None, content)),
+                Ok(_) => return Err("front matter 必须是键值对".to_string()),
+                Err(e) => return Err(e),
+            }
+        }
+        FrontMatterFormat::Toml => {
+            let toml: toml::Value =
+                toml::from_str(raw).map_err(|e| format!("TOML front matter 解析失败: {}", e))?;
+            match toml_to_json(toml) {
+                Value::Object(map) => map,
+                _ => return Err("front matter 必须是键值对".to_string()),
+            }
+        }
+    };
+
+    Ok((Some(meta_from_map(map)), body))
+}
+
+/// Whether a `---` block opens with a `key: value` line, as front matter does.
+/// A thematic break is followed by a blank line, prose or a list instead.
+fn looks_like_mapping(raw: &str) -> bool {
+    let Some(line) = raw.lines().find(|l| !l.trim_start().starts_with('#')) else {
+        return false;
+    };
+    if line.starts_with(char::is_whitespace) || line.starts_with('-') {
+        return false;
+    }
+    line.split_once(':').is_some_and(|(key, rest)| {
+        !key.trim().is_empty() && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+    })
+}
+
+/// Strip front matter for commands that only need the article body, ignoring parse errors
+pub fn strip_front_matter(content: &str) -> (Option<ArticleMeta>, &str) {
+    split_front_matter(content).unwrap_or((None, content))
+}
+
+pub(crate) fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn take_first(map: &mut Map<String, Value>, keys: &[&str]) -> Option<Value> {
+    keys.iter().find_map(|key| map.remove(*key))
+}
+
+fn value_to_string(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if trimmed.is_empty() {
+                None
+            } else {
+                Some(trimmed.to_string())
+            }
+        }
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Array(items) => {
+            let joined: Vec<String> = items.into_iter().filter_map(value_to_string).collect();
+            if joined.is_empty() {
+                None
+            } else {
+                Some(joined.join(", "))
+            }
+        }
+        _ => None,
+    }
+}
+
+fn value_to_list(value: Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.into_iter().filter_map(value_to_string).collect(),
+        Value::String(s) => s
+            .split([',', '，'])
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect(),
+        other => value_to_string(other).into_iter().collect(),
+    }
+}
+
+fn meta_from_map(mut map: Map<String, Value>) -> ArticleMeta {
+    let mut field = |keys: &[&str]| take_first(&mut map, keys).and_then(value_to_string);
+
+    let title = field(&["title"]);
+    let author = field(&["author", "authors"]);
+    let digest = field(&["digest", "description", "summary", "excerpt"]);
+    let cover = field(&[
+        "cover",
+        "cover_image",
+        "coverImage",
+        "image",
+        "thumbnail",
+        "banner",
+    ]);
+    let original_url = field(&[
+        "original_url",
+        "originalUrl",
+        "source_url",
+        "canonical_url",
+        "canonicalURL",
+    ]);
+    let date = field(&["date", "publishDate", "published"]);
+    let tags = take_first(&mut map, &["tags", "tag", "keywords"])
+        .map(value_to_list)
+        .unwrap_or_default();
+
+    ArticleMeta {
+        title,
+        author,
+        digest,
+        cover,
+        tags,
+        original_url,
+        date,
+        extra: map,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_yaml_front_matter() {
+        let markdown = "---\ntitle: 人工智能的未来\ntags: [AI, 深度学习]\ndescription: 摘要\ndraft: true\n---\n# 正文\n";
+        let (meta, body) = split_front_matter(markdown).unwrap();
+        let meta = meta.unwrap();
+
+        assert_eq!(meta.title.as_deref(), Some("人工智能的未来"));
+        assert_eq!(meta.digest.as_deref(), Some("摘要"));
+        assert_eq!(meta.tags, vec!["AI", "深度学习"]);
+        assert_eq!(meta.extra.get("draft"), Some(&Value::Bool(true)));
+        assert_eq!(body, "# 正文\n");
+    }
+
+    #[test]
+    fn test_split_toml_front_matter() {
+        let markdown =
+            "+++\ntitle = \"Hugo\"\ndate = 2024-01-02T10:00:00Z\ntags = \"a, b\"\n+++\nBody";
+        let (meta, body) = split_front_matter(markdown).unwrap();
+        let meta = meta.unwrap();
+
+        assert_eq!(meta.title.as_deref(), Some("Hugo"));
+        assert_eq!(meta.date.as_deref(), Some("2024-01-02T10:00:00Z"));
+        assert_eq!(meta.tags, vec!["a", "b"]);
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn test_thematic_break_is_not_front_matter() {
+        let markdown = "---\n\nJust a paragraph\n";
+        let (meta, body) = split_front_matter(markdown).unwrap();
+        assert!(meta.is_none());
+        assert_eq!(body, markdown);
+
+        // A document opening with an hr and using another one later
+        let markdown = "---\n\nFirst section: with a colon\n\n- item\n\n---\n\nSecond section\n";
+        let (meta, body) = split_front_matter(markdown).unwrap();
+        assert!(meta.is_none());
+        assert_eq!(body, markdown);
+
+        let markdown = "---\nJust a paragraph\n---\nMore text\n";
+        let (meta, body) = split_front_matter(markdown).unwrap();
+        assert!(meta.is_none());
+        assert_eq!(body, markdown);
+
+        // Broken front matter is reported rather than rendered as text
+        let error = split_front_matter("---\ntitle: \"Unclosed\ndate: 2024-01-01\n---\nBody\n")
+            .unwrap_err();
+        assert!(error.starts_with("YAML front matter 解析失败"));
+    }
+}
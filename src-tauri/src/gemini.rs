@@ -1,6 +1,10 @@
 use base64::{engine::general_purpose, Engine as _};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::front_matter::strip_front_matter;
+use crate::workspace::Workspace;
 
 // ==================== 文本生成 API 结构体 ====================
 
@@ -61,6 +65,7 @@ struct GeminiCandidate {
 
 #[tauri::command]
 pub async fn generate_cover_image(
+    workspace: State<'_, Workspace>,
     markdown: String,
     gemini_api_key: Option<String>,
     gemini_api_url: Option<String>,
@@ -161,6 +166,10 @@ pub async fn generate_cover_image(
         .map_err(|e| format!("解码图片失败: {}", e))?;
 
     let base_path = base_dir.ok_or_else(|| "请先保存Markdown文件".to_string())?;
+    let base_path = workspace
+        .resolve_base_dir(&base_path, &assets_dir)?
+        .to_string_lossy()
+        .to_string();
     let sep = if base_path.contains("\\") { "\\" } else { "/" };
     let target_dir = format!("{}{}{}", base_path, sep, assets_dir);
 
@@ -261,8 +270,9 @@ pub async fn test_gemini_config(
 }
 
 fn generate_image_prompt_from_markdown(markdown: &str) -> String {
-    let lines: Vec<&str> = markdown.lines().collect();
-    let mut title = String::new();
+    let (meta, body) = strip_front_matter(markdown);
+    let lines: Vec<&str> = body.lines().collect();
+    let mut title = meta.and_then(|m| m.title).unwrap_or_default();
     let mut content_preview = String::new();
 
     for line in lines.iter() {
@@ -0,0 +1,183 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Style, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::html::escape_html;
+use crate::pipeline::Transform;
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+/// Highlight palettes that can be selected per theme, mapped to bundled syntect themes.
+/// The first entry is used when a theme does not choose one.
+pub const PALETTES: &[(&str, &str)] = &[
+    ("github", "InspiredGitHub"),
+    ("solarized-light", "Solarized (light)"),
+    ("ocean-light", "base16-ocean.light"),
+    ("solarized-dark", "Solarized (dark)"),
+    ("ocean-dark", "base16-ocean.dark"),
+    ("eighties-dark", "base16-eighties.dark"),
+    ("mocha-dark", "base16-mocha.dark"),
+];
+
+/// Resolve a palette name to a syntect theme, falling back to the default palette
+fn resolve_palette(name: Option<&str>) -> &'static Theme {
+    let key = name
+        .map(|n| n.trim())
+        .filter(|n| !n.is_empty())
+        .and_then(|n| {
+            PALETTES
+                .iter()
+                .find(|(palette, theme)| palette.eq_ignore_ascii_case(n) || *theme == n)
+        })
+        .unwrap_or(&PALETTES[0])
+        .1;
+    &THEME_SET.themes[key]
+}
+
+/// Take the language token from a fenced code block info string, e.g. `rust,ignore` -> `rust`
+fn language_token(info: &str) -> &str {
+    info.split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .unwrap_or("")
+}
+
+fn span_style(style: &Style) -> String {
+    let fg = style.foreground;
+    let mut css = format!("color:#{:02x}{:02x}{:02x}", fg.r, fg.g, fg.b);
+    if style.font_style.contains(FontStyle::BOLD) {
+        css.push_str(";font-weight:bold");
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        css.push_str(";font-style:italic");
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        css.push_str(";text-decoration:underline");
+    }
+    css
+}
+
+/// Highlight code into a `<pre><code>` block whose tokens carry inline `style` attributes.
+/// Returns `None` when the language is unknown so the caller can keep the plain block.
+pub fn highlight_code(code: &str, lang: &str, palette: Option<&str>) -> Option<String> {
+    if lang.is_empty() {
+        return None;
+    }
+    let syntax = SYNTAX_SET.find_syntax_by_token(lang)?;
+    let mut highlighter = HighlightLines::new(syntax, resolve_palette(palette));
+
+    let mut html = format!("<pre><code class=\"language-{}\">", escape_html(lang));
+    let mut open_style: Option<String> = None;
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(line, &SYNTAX_SET).ok()?;
+        for (style, text) in regions {
+            if text.is_empty() {
+                continue;
+            }
+            // Whitespace keeps the current span so runs of one token colour stay merged
+            if !text.trim().is_empty() {
+                let css = span_style(&style);
+                if open_style.as_deref() != Some(css.as_str()) {
+                    if open_style.is_some() {
+                        html.push_str("</span>");
+                    }
+                    html.push_str(&format!("<span style=\"{}\">", css));
+                    open_style = Some(css);
+                }
+            }
+            html.push_str(&escape_html(text));
+        }
+    }
+    if open_style.is_some() {
+        html.push_str("</span>");
+    }
+    html.push_str("</code></pre>\n");
+    Some(html)
+}
+
+/// Replace fenced code blocks in the event stream with inline-styled highlighted HTML
+pub fn highlight_code_blocks<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    palette: Option<&str>,
+) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut pending: Option<(String, Vec<Event<'a>>, String)> = None;
+
+    for event in events {
+        if let Some((_, raw, code)) = pending.as_mut() {
+            if let Event::Text(text) = &event {
+                code.push_str(text);
+            }
+            let is_end = matches!(event, Event::End(TagEnd::CodeBlock));
+            raw.push(event);
+            if is_end {
+                if let Some((lang, raw, code)) = pending.take() {
+                    match highlight_code(&code, &lang, palette) {
+                        Some(html) => output.push(Event::Html(CowStr::from(html))),
+                        None => output.extend(raw),
+                    }
+                }
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+                let lang = language_token(info).to_string();
+                pending = Some((lang, vec![event], String::new()));
+            }
+            _ => output.push(event),
+        }
+    }
+
+    if let Some((_, raw, _)) = pending {
+        output.extend(raw);
+    }
+
+    output
+}
+
+/// Highlights fenced code blocks with the selected palette
+pub struct HighlightTransform {
+    pub palette: Option<String>,
+}
+
+impl Transform for HighlightTransform {
+    fn name(&self) -> &'static str {
+        "highlight"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        highlight_code_blocks(events.into_iter(), self.palette.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::Parser;
+
+    #[test]
+    fn test_highlight_inlines_token_styles() {
+        let html = highlight_code("let x = \"<a>\";\n", "rust", Some("github")).unwrap();
+        assert!(html.starts_with("<pre><code class=\"language-rust\"><span style=\"color:#"));
+        assert!(html.contains("&lt;a&gt;"));
+        assert!(!html.contains("class=\"s"));
+        assert_ne!(
+            html,
+            highlight_code("let x = \"<a>\";\n", "rust", Some("ocean-dark")).unwrap()
+        );
+        assert!(highlight_code("x", "no-such-language", None).is_none());
+
+        // Unknown languages keep the plain block
+        let markdown = "```rust,ignore\nfn main() {}\n```\n\n```nope\nplain\n```\n";
+        let events = highlight_code_blocks(Parser::new(markdown), None);
+        assert!(matches!(&events[0], Event::Html(html) if html.contains("language-rust")));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::Start(Tag::CodeBlock(_)))));
+    }
+}
@@ -1,26 +1,149 @@
-use regex::Regex;
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CowStr, Event, Tag};
+use regex::{Captures, Regex};
 use reqwest::Client;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use tauri::State;
+
+use crate::pipeline::Transform;
+use crate::site::{PostContext, SiteProfile};
+use crate::workspace::Workspace;
+
+/// Prefix a relative image path with the site URL. Returns `None` when the path
+/// is already absolute, points into the local assets directory, or no prefix is set.
+pub fn prefix_image_url(url: &str, prefix: &str, assets_dir: &str) -> Option<String> {
+    let prefix = prefix.trim().trim_end_matches('/');
+    let url = url.trim();
+    if prefix.is_empty() || url.is_empty() {
+        return None;
+    }
+
+    let lower = url.to_ascii_lowercase();
+    if ["http:", "https:", "data:", "//", "tauri:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+    {
+        return None;
+    }
+
+    let assets_dir = assets_dir.trim_end_matches(['/', '\\']);
+    let assets_prefix = format!("{}/", assets_dir);
+    if url.trim_start_matches("./").starts_with(&assets_prefix) {
+        return None;
+    }
+
+    if url.starts_with('/') {
+        Some(format!("{}{}", prefix, url))
+    } else {
+        Some(format!("{}/{}", prefix, url))
+    }
+}
+
+/// The `src` of an `<img>` tag written as raw HTML
+static IMG_SRC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)(<img\b[^>]*\bsrc=)(["'])([^"']+?)["']"#).unwrap());
+
+/// Prefixes relative image paths with the site URL so they resolve outside the blog.
+/// Absolute URLs and files under the local assets directory are left alone.
+pub struct ImagePrefixTransform {
+    pub prefix: String,
+    pub assets_dir: String,
+}
+
+impl ImagePrefixTransform {
+    /// Prefix the `src` of every `<img>` in a raw HTML event
+    fn prefix_html<'a>(&self, html: CowStr<'a>) -> CowStr<'a> {
+        if !IMG_SRC_RE.is_match(&html) {
+            return html;
+        }
+        let html = IMG_SRC_RE.replace_all(&html, |caps: &Captures| {
+            match prefix_image_url(&caps[3], &self.prefix, &self.assets_dir) {
+                Some(url) => format!("{}{}{}{}", &caps[1], &caps[2], url, &caps[2]),
+                None => caps[0].to_string(),
+            }
+        });
+        CowStr::from(html.into_owned())
+    }
+}
+
+impl Transform for ImagePrefixTransform {
+    fn name(&self) -> &'static str {
+        "images"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Start(Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }) => {
+                    let dest_url =
+                        match prefix_image_url(&dest_url, &self.prefix, &self.assets_dir) {
+                            Some(url) => CowStr::from(url),
+                            None => dest_url,
+                        };
+                    Event::Start(Tag::Image {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    })
+                }
+                Event::Html(html) => Event::Html(self.prefix_html(html)),
+                Event::InlineHtml(html) => Event::InlineHtml(self.prefix_html(html)),
+                other => other,
+            })
+            .collect()
+    }
+}
+
+/// Download `url` and pick a file name for it from its last path segment
+pub(crate) async fn download_image(
+    client: &Client,
+    url: &str,
+) -> Result<(Vec<u8>, String), String> {
+    let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("下载远程图片失败 {}: {}", url, resp.status()));
+    }
+    let body = resp.bytes().await.map_err(|e| e.to_string())?;
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.split('/').next_back())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("image.png")
+        .to_string();
+    Ok((body.to_vec(), name))
+}
 
 #[allow(non_snake_case)]
 #[tauri::command]
 pub async fn localize_images_to_assets(
+    workspace: State<'_, Workspace>,
     markdown: String,
     baseDir: Option<String>,
+    filePath: Option<String>,
     sitePrefix: Option<String>,
     assetsDir: Option<String>,
 ) -> Result<String, String> {
     let assets_dir_name = assetsDir.unwrap_or_else(|| "assets".to_string());
 
     let base_dir_path = if let Some(dir) = baseDir {
-        PathBuf::from(dir)
+        workspace.resolve_base_dir(&dir, &assets_dir_name)?
     } else {
         return Err(format!(
             "当前文件尚未保存，无法确定 {} 目录",
             assets_dir_name
         ));
     };
+    let file_path = filePath.map(|path| workspace.resolve(&path)).transpose()?;
+    let profile = SiteProfile::detect(&base_dir_path, sitePrefix.as_deref());
+    let post = PostContext::new(base_dir_path.clone(), file_path, &markdown);
 
     let re = Regex::new(r"!\[[^\]]*]\(([^)]+)\)").map_err(|e| e.to_string())?;
     let client = Client::new();
@@ -46,60 +169,32 @@ pub async fn localize_images_to_assets(
 
         let is_http = url.starts_with("http://") || url.starts_with("https://");
 
-        let (bytes_opt, filename_opt): (Option<Vec<u8>>, Option<String>) = if is_http {
-            let resp = client.get(&url).send().await.map_err(|e| e.to_string())?;
-            if !resp.status().is_success() {
-                return Err(format!("下载远程图片失败 {}: {}", url, resp.status()));
-            }
-            let body = resp.bytes().await.map_err(|e| e.to_string())?;
-            let name = url
-                .split('/')
-                .last()
-                .filter(|s| !s.is_empty())
-                .unwrap_or("image.png")
-                .to_string();
-            (Some(body.to_vec()), Some(name))
-        } else if let Some(prefix) = &sitePrefix {
-            let trimmed = prefix.trim();
-            if !trimmed.is_empty() {
-                let full_url = format!("{}{}", trimmed.trim_end_matches('/'), url);
-                let resp = client
-                    .get(&full_url)
-                    .send()
-                    .await
-                    .map_err(|e| e.to_string())?;
-                if !resp.status().is_success() {
-                    return Err(format!("下载远程图片失败 {}: {}", full_url, resp.status()));
+        let (bytes, filename) = if is_http {
+            download_image(&client, &url).await?
+        } else {
+            // Images the generator's conventions find on disk stay where the
+            // site expects them; missing ones are fetched from where it
+            // publishes them
+            let resolved = profile.resolve_image(&workspace, &post, &url);
+            match (resolved.local, resolved.url) {
+                (None, Some(published)) => download_image(&client, &published).await?,
+                _ => {
+                    url_map.insert(url.clone(), url.clone());
+                    continue;
                 }
-                let body = resp.bytes().await.map_err(|e| e.to_string())?;
-                let name = url
-                    .split('/')
-                    .last()
-                    .filter(|s| !s.is_empty())
-                    .unwrap_or("image.png")
-                    .to_string();
-                (Some(body.to_vec()), Some(name))
-            } else {
-                url_map.insert(url.clone(), url.clone());
-                continue;
             }
-        } else {
-            url_map.insert(url.clone(), url.clone());
-            continue;
         };
 
-        if let (Some(bytes), Some(filename)) = (bytes_opt, filename_opt) {
-            let mut assets_dir = base_dir_path.clone();
-            assets_dir.push(&assets_dir_name);
-            std::fs::create_dir_all(&assets_dir).map_err(|e| e.to_string())?;
+        let mut assets_dir = base_dir_path.clone();
+        assets_dir.push(&assets_dir_name);
+        std::fs::create_dir_all(&assets_dir).map_err(|e| e.to_string())?;
 
-            let mut local_path = assets_dir.clone();
-            local_path.push(&filename);
-            std::fs::write(&local_path, &bytes).map_err(|e| e.to_string())?;
+        let mut local_path = assets_dir.clone();
+        local_path.push(&filename);
+        std::fs::write(&local_path, &bytes).map_err(|e| e.to_string())?;
 
-            let new_url = format!("{}/{}", assets_dir_name, filename);
-            url_map.insert(url.clone(), new_url);
-        }
+        let new_url = format!("{}/{}", assets_dir_name, filename);
+        url_map.insert(url.clone(), new_url);
     }
 
     let mut updated = markdown.clone();
@@ -111,3 +206,26 @@ pub async fn localize_images_to_assets(
 
     Ok(updated)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Parser};
+
+    #[test]
+    fn test_prefixes_markdown_and_raw_html_images() {
+        let transform = ImagePrefixTransform {
+            prefix: "https://blog.example.com/".to_string(),
+            assets_dir: "assets".to_string(),
+        };
+        let markdown = "![a](/img/a.png) <img alt=\"b\" src='img/b.png'>\n\n<p><img src=\"assets/c.png\"><IMG SRC=\"https://cdn.com/d.png\"></p>\n";
+        let events = transform.apply(Parser::new(markdown).collect());
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+
+        assert!(output.contains("src=\"https://blog.example.com/img/a.png\""));
+        assert!(output.contains("<img alt=\"b\" src='https://blog.example.com/img/b.png'>"));
+        assert!(output.contains("<img src=\"assets/c.png\">"));
+        assert!(output.contains("<IMG SRC=\"https://cdn.com/d.png\">"));
+    }
+}
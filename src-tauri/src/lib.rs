@@ -1,67 +1,164 @@
+mod admonition;
+mod analysis;
+mod autosave;
+mod blog;
+mod color;
+mod contrast;
 mod css;
+mod css_vars;
+mod darkmode;
+mod encoding;
 mod file;
+mod footnotes;
+mod front_matter;
 mod gemini;
+mod highlight;
 mod html;
 mod image;
+mod lint;
+mod math;
+mod mermaid;
 mod models;
 mod openai;
+mod pipeline;
+mod search;
+mod site;
+mod svg;
+mod theme_import;
+mod theme_tokens;
+mod themes;
+mod toc;
+mod watch;
 mod wechat;
+mod workspace;
 
 use pulldown_cmark::{html as md_html, Options, Parser};
+use tauri::{Manager, State};
 
-use css::get_fallback_css;
-use html::{convert_links_to_footnotes, replace_mermaid_blocks};
+use analysis::{analyze_events, AnalysisOptions, DocumentAnalysis, LineIndex};
+use css::inline_theme_css;
+use front_matter::split_front_matter;
+use models::{ArticleMeta, ConvertMarkdownResult, ConvertOptions, ConvertWarning};
+use pipeline::Pipeline;
+use site::{PostContext, SiteProfile};
+use workspace::Workspace;
 
 #[tauri::command]
-fn convert_markdown(content: String, css: String) -> Result<String, String> {
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_FOOTNOTES);
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TASKLISTS);
+fn convert_markdown(
+    workspace: State<'_, Workspace>,
+    content: String,
+    css: String,
+    options: Option<ConvertOptions>,
+) -> Result<ConvertMarkdownResult, String> {
+    render_markdown(&workspace, &content, &css, &options.unwrap_or_default(), false)
+}
 
-    let parser = Parser::new_ext(&content, options);
-    let mut html_output = String::new();
-    md_html::push_html(&mut html_output, parser);
+/// Markdown rendered to HTML, before any CSS is inlined
+pub(crate) struct RenderedBody {
+    pub html: String,
+    pub meta: Option<ArticleMeta>,
+    pub analysis: DocumentAnalysis,
+}
 
-    let (html_output, has_mermaid) = replace_mermaid_blocks(&html_output);
-    let html_output = convert_links_to_footnotes(&html_output);
+/// Run the Markdown through the transform pipeline. With `line_markers`, each
+/// block is preceded by a source line comment for diagnostics on the final HTML.
+pub(crate) fn render_body(
+    workspace: &Workspace,
+    content: &str,
+    options: &ConvertOptions,
+    line_markers: bool,
+) -> Result<RenderedBody, String> {
+    let (meta, body) = split_front_matter(content)?;
 
-    let mermaid_scripts = if has_mermaid {
-        r#"<script src="https://cdn.jsdelivr.net/npm/mermaid@10/dist/mermaid.min.js"></script>
-<script>
-if (window.mermaid) {
-  window.mermaid.initialize({ startOnLoad: true, securityLevel: "loose" });
-}
-</script>"#
-    } else {
-        ""
+    let mut parser_options = Options::empty();
+    parser_options.insert(Options::ENABLE_TABLES);
+    parser_options.insert(Options::ENABLE_FOOTNOTES);
+    parser_options.insert(Options::ENABLE_STRIKETHROUGH);
+    parser_options.insert(Options::ENABLE_TASKLISTS);
+    parser_options.insert(Options::ENABLE_MATH);
+    parser_options.insert(Options::ENABLE_GFM);
+
+    let lines = LineIndex::new(content, body);
+    let image_prefix = options.image_prefix.clone().unwrap_or_default();
+    let assets_dir = options
+        .assets_dir
+        .clone()
+        .unwrap_or_else(|| "assets".to_string());
+    // Paths come from the webview, so only approved folders are looked at
+    let site = match &options.base_dir {
+        Some(dir) => {
+            let dir = workspace.resolve_base_dir(dir, &assets_dir)?;
+            let file = options
+                .file_path
+                .as_ref()
+                .map(|path| workspace.resolve(path))
+                .transpose()?;
+            Some((
+                SiteProfile::detect(&dir, Some(&image_prefix)),
+                PostContext::new(dir, file, content),
+            ))
+        }
+        None => None,
+    };
+    let analysis_options = AnalysisOptions {
+        image_prefix,
+        assets_dir,
+        workspace,
+        site,
+        line_markers,
     };
+    let (events, analysis) = analyze_events(
+        Parser::new_ext(body, parser_options).into_offset_iter(),
+        &lines,
+        &analysis_options,
+    );
 
-    let fallback_css = get_fallback_css();
-    let combined_css = format!("{}\n{}", fallback_css, css);
+    let pipeline = Pipeline::from_options(options, meta.as_ref())?;
+    let events = pipeline.run(events.into_iter());
 
-    let html_with_css = format!(
-        r#"<!DOCTYPE html>
-<html>
-<head>
-<meta charset="utf-8">
-<style>
-{}
-</style>
-{}
-</head>
-<body>
-<div class="wechat-content">
-{}
-</div>
-</body>
-</html>"#,
-        combined_css, mermaid_scripts, html_output
-    );
+    let mut html_output = String::new();
+    md_html::push_html(&mut html_output, events.into_iter());
+
+    Ok(RenderedBody {
+        html: html_output,
+        meta,
+        analysis,
+    })
+}
+
+/// Convert Markdown to WeChat-ready HTML with the theme CSS inlined
+pub(crate) fn render_markdown(
+    workspace: &Workspace,
+    content: &str,
+    css: &str,
+    options: &ConvertOptions,
+    line_markers: bool,
+) -> Result<ConvertMarkdownResult, String> {
+    let RenderedBody {
+        html,
+        meta,
+        analysis,
+    } = render_body(workspace, content, options, line_markers)?;
 
-    let inlined_html = css_inline::inline(&html_with_css).map_err(|e| e.to_string())?;
-    Ok(inlined_html)
+    let mut warnings = analysis.warnings;
+    let css = theme_tokens::theme_css_for(css, options, meta.as_ref(), &mut warnings)?;
+    let (inlined_html, unresolved_vars) = inline_theme_css(&html, &css)?;
+    warnings.extend(unresolved_vars.into_iter().map(|name| ConvertWarning {
+        kind: "css-var-unresolved".to_string(),
+        message: format!("CSS 变量 {} 未定义且没有默认值，相关样式已忽略", name),
+        line: 0,
+    }));
+    let dark_mode = darkmode::apply_dark_mode(&inlined_html, options.dark_mode_hints.unwrap_or(false));
+    warnings.extend(dark_mode.warnings);
+    Ok(ConvertMarkdownResult {
+        html: dark_mode.html,
+        meta,
+        outline: analysis.outline,
+        stats: analysis.stats,
+        images: analysis.images,
+        links: analysis.links,
+        warnings,
+    })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -69,19 +166,62 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            workspace::init_workspace(app.handle());
+            app.manage(blog::BlogIndex::default());
+            app.manage(watch::FileWatch::default());
+            app.manage(encoding::FileFormats::default());
+            app.manage(search::SearchIndex::default());
+            // Hot reload is a convenience; the app works without it
+            if let Err(e) = themes::watch_themes(app.handle()) {
+                eprintln!("{}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             convert_markdown,
+            lint::lint_wechat_html,
+            css::trace_theme_styles,
+            contrast::check_theme_contrast,
+            themes::list_themes,
+            themes::reload_themes,
+            themes::install_theme,
+            themes::remove_theme,
+            theme_import::import_community_theme,
+            theme_tokens::generate_theme,
+            theme_tokens::save_generated_theme,
+            workspace::pick_markdown_file,
+            workspace::pick_markdown_save_path,
+            workspace::add_workspace_root,
+            workspace::list_workspace_roots,
+            workspace::remove_workspace_root,
+            blog::open_blog_workspace,
+            blog::list_blog_posts,
+            search::search_markdown,
+            site::get_site_profile,
+            watch::watch_open_file,
+            watch::unwatch_open_file,
+            encoding::get_file_format,
             file::open_markdown_file,
             file::save_markdown_file,
+            file::list_file_backups,
+            file::restore_file_backup,
             file::save_binary_file,
             file::create_directory,
+            autosave::autosave_draft,
+            autosave::list_drafts,
+            autosave::load_draft,
+            autosave::discard_draft,
+            autosave::promote_draft,
             openai::generate_summary,
             openai::test_openai_config,
             gemini::generate_cover_image,
             gemini::test_gemini_config,
             wechat::wechat_upload_and_replace_images,
             wechat::test_wechat_access_token,
-            image::localize_images_to_assets
+            image::localize_images_to_assets,
+            math::render_math_to_assets,
+            mermaid::render_mermaid_to_assets
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,368 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tauri::State;
+
+use crate::models::{ConvertOptions, LintDiagnostic, LintSeverity};
+use crate::workspace::Workspace;
+
+/// Tags, comments and our `<!--wxline:N-->` source markers, in document order
+pub(crate) static TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)<!--wxline:(\d+)-->|<!--.*?-->|<(/?)([a-zA-Z][a-zA-Z0-9:-]*)((?:[^>"']|"[^"]*"|'[^']*')*)>"#,
+    )
+    .unwrap()
+});
+static ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([a-zA-Z_:][-a-zA-Z0-9_:.]*)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#)
+        .unwrap()
+});
+static POSITION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:^|;)\s*position\s*:\s*(absolute|fixed|sticky|relative)").unwrap()
+});
+static ID_REF_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"url\(\s*['\x22]?#").unwrap());
+
+/// Elements the WeChat editor removes together with their content
+const STRIPPED_ELEMENTS: &[&str] = &[
+    "script", "iframe", "frame", "embed", "object", "form", "input", "button", "select",
+    "textarea", "audio", "video", "canvas", "link", "meta",
+];
+/// Classes emitted by our own transforms; they are hooks for themes, not styling
+const GENERATED_CLASS_PREFIXES: &[&str] = &[
+    "language-",
+    "toc",
+    "footnote",
+    "admonition",
+    "math-",
+    "mermaid-",
+    "wechat-content",
+];
+/// Hosts whose links stay clickable in WeChat articles
+const WECHAT_HOSTS: &[&str] = &["mp.weixin.qq.com"];
+/// Data URIs larger than this (decoded) are likely rejected when pasting
+const MAX_DATA_URI_BYTES: usize = 1024 * 1024;
+/// Longest tag excerpt included in a diagnostic
+const SNIPPET_CHARS: usize = 120;
+
+struct Tag<'a> {
+    name: String,
+    raw: &'a str,
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag<'_> {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub(crate) fn parse_attrs(source: &str) -> Vec<(String, String)> {
+    ATTR_RE
+        .captures_iter(source)
+        .map(|caps| {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .or_else(|| caps.get(4))
+                .map(|m| m.as_str())
+                .unwrap_or_default();
+            (caps[1].to_ascii_lowercase(), value.to_string())
+        })
+        .collect()
+}
+
+fn snippet(raw: &str) -> String {
+    if raw.chars().count() <= SNIPPET_CHARS {
+        raw.to_string()
+    } else {
+        let cut: String = raw.chars().take(SNIPPET_CHARS).collect();
+        format!("{}…", cut)
+    }
+}
+
+fn is_wechat_host(url: &str) -> bool {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))
+        .unwrap_or(url);
+    let host = rest
+        .split(['/', '?', '#', ':'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    WECHAT_HOSTS
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+}
+
+struct Linter {
+    line: usize,
+    svg_depth: usize,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl Linter {
+    fn report(&mut self, rule: &str, severity: LintSeverity, message: String, tag: &Tag) {
+        self.diagnostics.push(LintDiagnostic {
+            rule: rule.to_string(),
+            severity,
+            message,
+            line: self.line,
+            snippet: snippet(tag.raw),
+        });
+    }
+
+    fn check(&mut self, tag: &Tag) {
+        let name = tag.name.as_str();
+        if STRIPPED_ELEMENTS.contains(&name) {
+            self.report(
+                "stripped-element",
+                LintSeverity::Error,
+                format!("微信会移除 <{}> 元素及其内容", name),
+                tag,
+            );
+        }
+
+        if let Some(style) = tag.attr("style") {
+            if let Some(caps) = POSITION_RE.captures(style) {
+                self.report(
+                    "position",
+                    LintSeverity::Warning,
+                    format!("微信会忽略 position: {}，元素会回到正常文档流", &caps[1]),
+                    tag,
+                );
+            }
+        }
+
+        if let Some(class) = tag.attr("class") {
+            let user_class = class.split_whitespace().find(|c| {
+                !GENERATED_CLASS_PREFIXES
+                    .iter()
+                    .any(|prefix| c.starts_with(prefix))
+            });
+            if let (Some(class), None) = (user_class, tag.attr("style")) {
+                self.report(
+                    "class-only-style",
+                    LintSeverity::Info,
+                    format!(
+                        "元素只依赖 class \"{}\"，粘贴到微信后 class 样式会丢失",
+                        class
+                    ),
+                    tag,
+                );
+            }
+        }
+
+        if name == "a" {
+            let href = tag.attr("href").unwrap_or_default().trim();
+            if href.starts_with('#') {
+                self.report(
+                    "anchor-link",
+                    LintSeverity::Info,
+                    "微信会移除页内锚点，该链接无法跳转".to_string(),
+                    tag,
+                );
+            } else if !href.is_empty() && !is_wechat_host(href) {
+                self.report(
+                    "external-link",
+                    LintSeverity::Warning,
+                    format!("微信文章中外链不可点击: {}", href),
+                    tag,
+                );
+            }
+        }
+
+        if name == "img" {
+            self.check_image(tag);
+        }
+
+        if self.svg_depth > 0 || name == "svg" {
+            self.check_svg(tag);
+        }
+    }
+
+    fn check_image(&mut self, tag: &Tag) {
+        let src = tag.attr("src").unwrap_or_default().trim();
+        let lower = src.to_ascii_lowercase();
+        if let Some(data) = lower.strip_prefix("data:") {
+            if data.starts_with("image/svg") {
+                self.report(
+                    "svg-image",
+                    LintSeverity::Warning,
+                    "微信不支持 SVG 图片，请将公式和图表导出为 PNG".to_string(),
+                    tag,
+                );
+            }
+            let decoded = src.len() / 4 * 3;
+            if decoded > MAX_DATA_URI_BYTES {
+                self.report(
+                    "data-uri-size",
+                    LintSeverity::Warning,
+                    format!(
+                        "内嵌图片约 {} KB，超过 {} KB，粘贴时可能被丢弃",
+                        decoded / 1024,
+                        MAX_DATA_URI_BYTES / 1024
+                    ),
+                    tag,
+                );
+            }
+        } else if !lower.starts_with("http://")
+            && !lower.starts_with("https://")
+            && !lower.starts_with("//")
+        {
+            self.report(
+                "local-image",
+                LintSeverity::Error,
+                format!("本地图片需要先上传到微信素材库: {}", src),
+                tag,
+            );
+        }
+    }
+
+    fn check_svg(&mut self, tag: &Tag) {
+        match tag.name.as_str() {
+            "foreignobject" => self.report(
+                "svg-foreign-object",
+                LintSeverity::Error,
+                "微信不支持 SVG 中的 <foreignObject>".to_string(),
+                tag,
+            ),
+            "style" => self.report(
+                "svg-style",
+                LintSeverity::Warning,
+                "SVG 内的 <style> 会被微信移除，请改用属性或内联样式".to_string(),
+                tag,
+            ),
+            _ => {}
+        }
+
+        let references_id = tag.attrs.iter().any(|(key, value)| {
+            ((key == "href" || key == "xlink:href") && value.starts_with('#'))
+                || ID_REF_RE.is_match(value)
+        });
+        if references_id {
+            self.report(
+                "svg-id-reference",
+                LintSeverity::Warning,
+                "微信会移除 id 属性，SVG 中通过 #id 引用的渐变、滤镜和 <use> 会失效".to_string(),
+                tag,
+            );
+        }
+    }
+}
+
+/// Check HTML produced by `convert_markdown` against the WeChat editor's limits.
+/// Diagnostics take their line from the nearest preceding source line marker.
+pub fn lint_html(html: &str) -> Vec<LintDiagnostic> {
+    let mut linter = Linter {
+        line: 1,
+        svg_depth: 0,
+        diagnostics: Vec::new(),
+    };
+
+    for caps in TOKEN_RE.captures_iter(html) {
+        if let Some(line) = caps.get(1) {
+            linter.line = line.as_str().parse().unwrap_or(linter.line);
+            continue;
+        }
+        let Some(name) = caps.get(3) else {
+            continue;
+        };
+        let name = name.as_str().to_ascii_lowercase();
+        let closing = !caps[2].is_empty();
+        let attrs_source = caps.get(4).map(|m| m.as_str()).unwrap_or_default();
+
+        if closing {
+            if name == "svg" {
+                linter.svg_depth = linter.svg_depth.saturating_sub(1);
+            }
+            continue;
+        }
+        let tag = Tag {
+            name,
+            raw: caps.get(0).map(|m| m.as_str()).unwrap_or_default(),
+            attrs: parse_attrs(attrs_source),
+        };
+        linter.check(&tag);
+        if tag.name == "svg" && !attrs_source.trim_end().ends_with('/') {
+            linter.svg_depth += 1;
+        }
+    }
+
+    linter.diagnostics
+}
+
+/// Convert the Markdown and report anything the WeChat editor would drop or break
+#[tauri::command]
+pub fn lint_wechat_html(
+    workspace: State<'_, Workspace>,
+    content: String,
+    css: String,
+    options: Option<ConvertOptions>,
+) -> Result<Vec<LintDiagnostic>, String> {
+    let result = crate::render_markdown(
+        &workspace,
+        &content,
+        &css,
+        &options.unwrap_or_default(),
+        true,
+    )?;
+    Ok(lint_html(&result.html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(html: &str) -> Vec<(String, usize)> {
+        lint_html(html)
+            .into_iter()
+            .map(|d| (d.rule, d.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_rules_report_marker_lines() {
+        let html = r##"<div class="wechat-content" style="color: #333;">
+<!--wxline:3--><p style="position: absolute; top: 0">x</p>
+<!--wxline:5--><iframe src="https://example.com"></iframe>
+<!--wxline:7--><p style="margin: 0"><a href="https://example.com">ext</a> <a href="https://mp.weixin.qq.com/s/x">wx</a></p>
+<!--wxline:9--><div class="callout">note</div><img src="assets/a.png" alt="a">
+<!--wxline:11--><svg><defs></defs><use href="#g"/><foreignObject></foreignObject></svg>
+</div>"##;
+        let found = rules(html);
+        let expected = [
+            ("position", 3),
+            ("stripped-element", 5),
+            ("external-link", 7),
+            ("class-only-style", 9),
+            ("local-image", 9),
+            ("svg-id-reference", 11),
+            ("svg-foreign-object", 11),
+        ];
+        assert_eq!(
+            found,
+            expected
+                .iter()
+                .map(|(rule, line)| (rule.to_string(), *line))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_svg_data_uri_and_size() {
+        let big = "A".repeat(MAX_DATA_URI_BYTES * 2);
+        let html = format!(
+            "<img src=\"data:image/svg+xml;base64,PHN2Zz4=\"><img src=\"data:image/png;base64,{}\">",
+            big
+        );
+        let found = lint_html(&html);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].rule, "svg-image");
+        assert_eq!(found[1].rule, "data-uri-size");
+        assert!(found[1].snippet.ends_with('…'));
+    }
+}
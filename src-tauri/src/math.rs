@@ -0,0 +1,313 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::{CowStr, Event, Options, Parser};
+use regex::Regex;
+use std::path::Path;
+use tauri::State;
+
+use crate::front_matter::split_front_matter;
+use crate::html::escape_html;
+use crate::models::AssetExportResult;
+use crate::pipeline::Transform;
+use crate::svg::{asset_file_name, data_uri, rasterize_svg, ImageFormat};
+use crate::workspace::Workspace;
+
+/// Pixels per `ex` for inline formulas, roughly matching a 16px body font
+const INLINE_EX_PX: f32 = 8.0;
+/// Display formulas are set slightly larger than the surrounding text
+const DISPLAY_EX_PX: f32 = 9.0;
+/// PNG output is rasterised at 2x so formulas stay sharp on high-DPI phones
+const PNG_SCALE: f32 = 2.0;
+
+static EX_ATTR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(width|height)="(-?[0-9.]+)ex""#).unwrap());
+static VALIGN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"vertical-align:\s*(-?[0-9.]+)ex").unwrap());
+
+/// A formula rendered to an image, sized for inline or display placement
+pub struct RenderedMath {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+    pub width_px: f32,
+    pub height_px: f32,
+    pub vertical_align_px: f32,
+}
+
+/// Render LaTeX to an SVG whose `ex` dimensions are converted to pixels,
+/// since neither WeChat nor resvg know MathJax's ex metrics.
+fn render_svg(latex: &str, display: bool) -> Result<(String, f32, f32, f32), String> {
+    let svg = if display {
+        mathjax_svg::convert_to_svg(latex)
+    } else {
+        mathjax_svg::convert_to_svg_inline(latex)
+    }
+    .map_err(|e| format!("公式渲染失败: {}", e))?;
+
+    let ex_px = if display { DISPLAY_EX_PX } else { INLINE_EX_PX };
+    let mut width = 0.0;
+    let mut height = 0.0;
+    let svg = EX_ATTR_RE.replace_all(&svg, |caps: &regex::Captures| {
+        let value: f32 = caps[2].parse().unwrap_or(0.0) * ex_px;
+        if &caps[1] == "width" {
+            width = value;
+        } else {
+            height = value;
+        }
+        format!("{}=\"{:.2}px\"", &caps[1], value)
+    });
+
+    let mut valign = 0.0;
+    let svg = VALIGN_RE.replace(&svg, |caps: &regex::Captures| {
+        valign = caps[1].parse::<f32>().unwrap_or(0.0) * ex_px;
+        format!("vertical-align: {:.2}px", valign)
+    });
+
+    Ok((svg.into_owned(), width, height, valign))
+}
+
+/// Render a LaTeX formula to SVG or PNG bytes
+pub fn render_math(
+    latex: &str,
+    display: bool,
+    format: ImageFormat,
+) -> Result<RenderedMath, String> {
+    let (svg, width_px, height_px, vertical_align_px) = render_svg(latex.trim(), display)?;
+    let bytes = match format {
+        ImageFormat::Svg => svg.into_bytes(),
+        ImageFormat::Png => rasterize_svg(&svg, PNG_SCALE)?,
+    };
+    Ok(RenderedMath {
+        bytes,
+        format,
+        width_px,
+        height_px,
+        vertical_align_px,
+    })
+}
+
+fn math_img_style(math: &RenderedMath, display: bool) -> String {
+    if display {
+        format!(
+            "display: block; margin: 0 auto; max-width: 100%; width: {:.2}px; height: auto;",
+            math.width_px
+        )
+    } else {
+        format!(
+            "display: inline-block; margin: 0 1px; width: {:.2}px; height: {:.2}px; vertical-align: {:.2}px;",
+            math.width_px, math.height_px, math.vertical_align_px
+        )
+    }
+}
+
+/// Build the HTML for a formula, embedding the image as a data URI
+pub fn math_to_html(latex: &str, display: bool, format: ImageFormat) -> String {
+    match render_math(latex, display, format) {
+        Ok(math) => {
+            let img = format!(
+                "<img class=\"{}\" src=\"{}\" alt=\"{}\" style=\"{}\">",
+                if display {
+                    "math-display"
+                } else {
+                    "math-inline"
+                },
+                data_uri(&math.bytes, math.format),
+                escape_html(latex.trim()),
+                math_img_style(&math, display)
+            );
+            if display {
+                format!(
+                    "<span class=\"math-block\" style=\"display: block; text-align: center; margin: 16px 0; overflow-x: auto;\">{}</span>",
+                    img
+                )
+            } else {
+                img
+            }
+        }
+        Err(e) => {
+            let delim = if display { "$$" } else { "$" };
+            format!(
+                "<code class=\"math-error\" title=\"{}\">{}{}{}</code>",
+                escape_html(&e),
+                delim,
+                escape_html(latex),
+                delim
+            )
+        }
+    }
+}
+
+/// Replace inline and display math events with rendered image HTML.
+/// Display math sits inside a paragraph, so both kinds are emitted as inline HTML.
+pub fn render_math_events<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    format: ImageFormat,
+) -> Vec<Event<'a>> {
+    events
+        .map(|event| match event {
+            Event::InlineMath(latex) => {
+                Event::InlineHtml(CowStr::from(math_to_html(&latex, false, format)))
+            }
+            Event::DisplayMath(latex) => {
+                Event::InlineHtml(CowStr::from(math_to_html(&latex, true, format)))
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// Renders `$...$` and `$$...$$` math to embedded images
+pub struct MathTransform {
+    pub format: ImageFormat,
+}
+
+impl Transform for MathTransform {
+    fn name(&self) -> &'static str {
+        "math"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        render_math_events(events.into_iter(), self.format)
+    }
+}
+
+/// `(latex, display, range)` of every formula in the article body, with
+/// ranges relative to the whole document so front matter is never touched
+fn math_spans(markdown: &str) -> Vec<(String, bool, std::ops::Range<usize>)> {
+    let body = split_front_matter(markdown)
+        .map(|(_, body)| body)
+        .unwrap_or(markdown);
+    let offset = markdown.len() - body.len();
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_MATH);
+    Parser::new_ext(body, options)
+        .into_offset_iter()
+        .filter_map(|(event, range)| {
+            let range = range.start + offset..range.end + offset;
+            match event {
+                Event::InlineMath(latex) => Some((latex.to_string(), false, range)),
+                Event::DisplayMath(latex) => Some((latex.to_string(), true, range)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Whether the article body has any `$...$` or `$$...$$` math
+pub fn has_math(markdown: &str) -> bool {
+    !math_spans(markdown).is_empty()
+}
+
+/// Render every formula to an image in the assets directory and replace it
+/// with an image reference. Formulas that fail to render are left in place.
+pub fn export_math(
+    markdown: &str,
+    base_dir: &Path,
+    assets_dir_name: &str,
+    format: ImageFormat,
+) -> Result<AssetExportResult, String> {
+    let mut replacements = Vec::new();
+    let mut errors = Vec::new();
+    for (latex, display, range) in math_spans(markdown) {
+        let variant = if display { "display" } else { "inline" };
+        let file_name = asset_file_name("math", &latex, variant, format);
+        let mut local_path = base_dir.join(assets_dir_name);
+        std::fs::create_dir_all(&local_path).map_err(|e| e.to_string())?;
+        local_path.push(&file_name);
+        if !local_path.exists() {
+            match render_math(&latex, display, format) {
+                Ok(math) => std::fs::write(&local_path, &math.bytes).map_err(|e| e.to_string())?,
+                Err(e) => {
+                    errors.push(format!("{} ({})", e, latex.trim()));
+                    continue;
+                }
+            }
+        }
+
+        let alt = latex.trim().replace(['[', ']', '\n'], " ");
+        let image = if display {
+            format!("\n\n![{}]({}/{})\n\n", alt, assets_dir_name, file_name)
+        } else {
+            format!("![{}]({}/{})", alt, assets_dir_name, file_name)
+        };
+        replacements.push((range, image));
+    }
+
+    let rendered = replacements.len();
+    let mut updated = markdown.to_string();
+    for (range, image) in replacements.into_iter().rev() {
+        updated.replace_range(range, &image);
+    }
+
+    Ok(AssetExportResult {
+        markdown: updated,
+        rendered,
+        errors,
+    })
+}
+
+/// Render every formula in the markdown to an image file in the assets directory
+/// and replace it with an image reference, so it can be uploaded like any other image.
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn render_math_to_assets(
+    workspace: State<'_, Workspace>,
+    markdown: String,
+    baseDir: Option<String>,
+    assetsDir: Option<String>,
+    format: Option<String>,
+) -> Result<AssetExportResult, String> {
+    let assets_dir_name = assetsDir.unwrap_or_else(|| "assets".to_string());
+    let base_dir_path = if let Some(dir) = baseDir {
+        workspace.resolve_base_dir(&dir, &assets_dir_name)?
+    } else {
+        return Err(format!(
+            "当前文件尚未保存，无法确定 {} 目录",
+            assets_dir_name
+        ));
+    };
+    // WeChat's material API does not accept SVG, so PNG is the default here
+    let format = ImageFormat::parse(format.as_deref(), ImageFormat::Png);
+
+    export_math(&markdown, &base_dir_path, &assets_dir_name, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_math_to_images() {
+        let inline = math_to_html("x^2", false, ImageFormat::Png);
+        assert!(inline.starts_with("<img class=\"math-inline\" src=\"data:image/png;base64,"));
+        assert!(inline.contains("alt=\"x^2\""));
+        // `ex` sizes become pixels, which WeChat understands
+        assert!(inline.contains("px;") && !inline.contains("ex;"));
+
+        let display = math_to_html("x", true, ImageFormat::Svg);
+        assert!(display.contains("class=\"math-block\""));
+        assert!(display.contains("data:image/svg+xml;base64,"));
+
+        let failed = math_to_html("\\bad{", false, ImageFormat::Png);
+        assert!(failed.starts_with("<code class=\"math-error\""));
+    }
+
+    #[test]
+    fn test_export_math_skips_front_matter_and_keeps_failures() {
+        let temp = tempfile::tempdir().unwrap();
+        let markdown = "---\ntitle: Cost $5 or $6\n---\nArea $r^2$ and $\\bad{x}$ end\n";
+        assert!(has_math(markdown));
+        assert!(!has_math("---\ntitle: $5 and $6\n---\nNo math\n"));
+
+        let exported = export_math(markdown, temp.path(), "assets", ImageFormat::Png).unwrap();
+        let file_name = asset_file_name("math", "r^2", "inline", ImageFormat::Png);
+        assert_eq!(exported.rendered, 1);
+        assert_eq!(exported.errors.len(), 1);
+        assert_eq!(
+            exported.markdown,
+            format!(
+                "---\ntitle: Cost $5 or $6\n---\nArea ![r^2](assets/{}) and $\\bad{{x}}$ end\n",
+                file_name
+            )
+        );
+        assert!(temp.path().join("assets").join(file_name).is_file());
+    }
+}
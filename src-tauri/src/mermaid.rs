@@ -0,0 +1,238 @@
+use mermaid_rs_renderer::{render_with_options, RenderOptions};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use tauri::State;
+
+use crate::html::escape_html;
+use crate::models::AssetExportResult;
+use crate::pipeline::Transform;
+use crate::svg::{asset_file_name, data_uri, rasterize_svg, ImageFormat};
+use crate::workspace::Workspace;
+
+/// PNG output is rasterised at 2x so diagram labels stay readable on phones
+const PNG_SCALE: f32 = 2.0;
+
+fn is_mermaid(info: &str) -> bool {
+    info.split_whitespace()
+        .next()
+        .is_some_and(|lang| lang.eq_ignore_ascii_case("mermaid"))
+}
+
+/// Whether the markdown has a fenced `mermaid` block, with any fence or case
+pub fn has_mermaid_blocks(markdown: &str) -> bool {
+    Parser::new_ext(markdown, Options::empty()).any(|event| {
+        matches!(event, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) if is_mermaid(info))
+    })
+}
+
+/// Render Mermaid source to an SVG document
+pub fn render_mermaid_svg(code: &str) -> Result<String, String> {
+    // The renderer is young; a panic on unusual input must not take the app down
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        render_with_options(code, RenderOptions::mermaid_default())
+    }))
+    .map_err(|_| "Mermaid 渲染器异常退出".to_string())?
+    .map_err(|e| format!("Mermaid 渲染失败: {}", e))
+}
+
+/// Render Mermaid source to SVG or PNG bytes
+pub fn render_mermaid(code: &str, format: ImageFormat) -> Result<Vec<u8>, String> {
+    let svg = render_mermaid_svg(code)?;
+    match format {
+        ImageFormat::Svg => Ok(svg.into_bytes()),
+        ImageFormat::Png => rasterize_svg(&svg, PNG_SCALE),
+    }
+}
+
+/// Build the HTML for a diagram, or a visible placeholder when it cannot be rendered
+pub fn mermaid_to_html(code: &str, format: ImageFormat) -> String {
+    match render_mermaid(code, format) {
+        Ok(bytes) => format!(
+            "<section class=\"mermaid-diagram\" style=\"text-align: center; margin: 16px 0;\"><img src=\"{}\" alt=\"Mermaid 图\" style=\"max-width: 100%; height: auto; margin: 0 auto;\"></section>\n",
+            data_uri(&bytes, format)
+        ),
+        Err(e) => format!(
+            "<section class=\"mermaid-error\" style=\"margin: 16px 0; padding: 12px 16px; border: 1px dashed #e5484d; border-radius: 4px; background-color: #fff5f5; color: #c62828; font-size: 14px;\"><p style=\"margin: 0 0 8px 0; font-weight: bold;\">{}</p><pre style=\"margin: 0; white-space: pre-wrap;\"><code>{}</code></pre></section>\n",
+            escape_html(&e),
+            escape_html(code)
+        ),
+    }
+}
+
+/// Replace fenced `mermaid` blocks in the event stream with rendered diagram images
+pub fn render_mermaid_blocks<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    format: ImageFormat,
+) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut pending: Option<String> = None;
+
+    for event in events {
+        if let Some(code) = pending.as_mut() {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some(code) = pending.take() {
+                        output.push(Event::Html(CowStr::from(mermaid_to_html(&code, format))));
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) if is_mermaid(info) => {
+                pending = Some(String::new());
+            }
+            _ => output.push(event),
+        }
+    }
+
+    output
+}
+
+/// Renders fenced `mermaid` blocks to diagram images
+pub struct MermaidTransform {
+    pub format: ImageFormat,
+}
+
+impl Transform for MermaidTransform {
+    fn name(&self) -> &'static str {
+        "mermaid"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        render_mermaid_blocks(events.into_iter(), self.format)
+    }
+}
+
+/// Render every mermaid block to an image in the assets directory and replace it
+/// with an image reference. Blocks that fail to render are left in place.
+pub fn export_mermaid_blocks(
+    markdown: &str,
+    base_dir: &Path,
+    assets_dir_name: &str,
+    format: ImageFormat,
+) -> Result<AssetExportResult, String> {
+    let mut replacements = Vec::new();
+    let mut errors = Vec::new();
+    let mut current: Option<(std::ops::Range<usize>, String)> = None;
+
+    for (event, range) in Parser::new_ext(markdown, Options::empty()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) if is_mermaid(info) => {
+                current = Some((range, String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = current.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let Some((block_range, code)) = current.take() else {
+                    continue;
+                };
+                if code.trim().is_empty() {
+                    continue;
+                }
+
+                let file_name = asset_file_name("mermaid", &code, "", format);
+                let mut local_path = base_dir.join(assets_dir_name);
+                std::fs::create_dir_all(&local_path).map_err(|e| e.to_string())?;
+                local_path.push(&file_name);
+                if !local_path.exists() {
+                    match render_mermaid(&code, format) {
+                        Ok(bytes) => {
+                            std::fs::write(&local_path, bytes).map_err(|e| e.to_string())?
+                        }
+                        Err(e) => {
+                            errors.push(e);
+                            continue;
+                        }
+                    }
+                }
+
+                let trailing = if markdown[block_range.clone()].ends_with('\n') {
+                    "\n"
+                } else {
+                    ""
+                };
+                let image = format!(
+                    "![Mermaid 图]({}/{}){}",
+                    assets_dir_name, file_name, trailing
+                );
+                replacements.push((block_range, image));
+            }
+            _ => {}
+        }
+    }
+
+    let rendered = replacements.len();
+    let mut updated = markdown.to_string();
+    for (range, image) in replacements.into_iter().rev() {
+        updated.replace_range(range, &image);
+    }
+
+    Ok(AssetExportResult {
+        markdown: updated,
+        rendered,
+        errors,
+    })
+}
+
+/// Render mermaid blocks to image files in the assets directory
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn render_mermaid_to_assets(
+    workspace: State<'_, Workspace>,
+    markdown: String,
+    baseDir: Option<String>,
+    assetsDir: Option<String>,
+    format: Option<String>,
+) -> Result<AssetExportResult, String> {
+    let assets_dir_name = assetsDir.unwrap_or_else(|| "assets".to_string());
+    let base_dir_path = if let Some(dir) = baseDir {
+        workspace.resolve_base_dir(&dir, &assets_dir_name)?
+    } else {
+        return Err(format!(
+            "当前文件尚未保存，无法确定 {} 目录",
+            assets_dir_name
+        ));
+    };
+    // WeChat's material API does not accept SVG, so PNG is the default here
+    let format = ImageFormat::parse(format.as_deref(), ImageFormat::Png);
+
+    export_mermaid_blocks(&markdown, &base_dir_path, &assets_dir_name, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_mermaid_blocks() {
+        let markdown = "~~~Mermaid\ngraph TD\n  A --> B\n~~~\n\n```rust\nfn main() {}\n```\n";
+        let events = render_mermaid_blocks(Parser::new(markdown), ImageFormat::Png);
+        let html: Vec<String> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Html(html) => Some(html.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(html.len(), 1);
+        assert!(html[0].contains("src=\"data:image/png;base64,"));
+        // Other code blocks are left alone
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, Event::Text(text) if text.contains("fn main"))));
+
+        assert!(has_mermaid_blocks(markdown));
+        assert!(!has_mermaid_blocks("`mermaid` is mentioned inline\n"));
+
+        let failed = mermaid_to_html("not a diagram <", ImageFormat::Svg);
+        assert!(failed.contains("mermaid-error") && failed.contains("not a diagram &lt;"));
+    }
+}
@@ -61,3 +61,470 @@ pub struct WechatUploadResult {
     pub markdown: String,
     pub items: Vec<WechatUploadResultEntry>,
 }
+
+/// Markdown after diagrams or formulas were rendered to files in the assets
+/// directory; items that failed keep their source and add an error
+#[derive(Serialize)]
+pub struct AssetExportResult {
+    pub markdown: String,
+    pub rendered: usize,
+    pub errors: Vec<String>,
+}
+
+// ============ Draft recovery ============
+
+/// A journalled editor buffer that was never saved to its file
+#[derive(Serialize, Clone, Debug)]
+pub struct DraftInfo {
+    pub id: String,
+    /// File the draft belongs to; `None` for a post never saved
+    pub path: Option<String>,
+    pub title: String,
+    /// Milliseconds since the Unix epoch of the last autosave
+    pub updated: u64,
+    pub size: u64,
+    pub preview: String,
+}
+
+// ============ File backups ============
+
+/// One saved copy in a file's backup folder
+#[derive(Serialize, Clone, Debug)]
+pub struct BackupInfo {
+    pub path: String,
+    /// Milliseconds since the Unix epoch when the backup was taken
+    pub timestamp: u64,
+    pub size: u64,
+}
+
+/// Character encoding a markdown file was read in and is saved back in
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    /// Also covers GBK and GB2312, which it extends
+    Gb18030,
+    Utf16le,
+    Utf16be,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+/// How a markdown file is stored on disk. The editor always works on
+/// BOM-less text with `\n` line breaks.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFormat {
+    pub encoding: TextEncoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+/// Payload of the `file-changed` and `assets-changed` events
+#[derive(Serialize, Clone, Debug)]
+pub struct FileChangeEvent {
+    pub path: String,
+    /// `modify`, `rename` or `delete`; assets may also report `create`
+    pub kind: String,
+}
+
+// ============ Blog workspace ============
+
+/// A markdown post found under the blog root
+#[derive(Serialize, Clone, Debug)]
+pub struct BlogPost {
+    pub path: String,
+    /// Path relative to the blog root, with `/` separators
+    pub relative_path: String,
+    pub title: String,
+    pub date: Option<String>,
+    pub tags: Vec<String>,
+    pub draft: bool,
+    /// Every image of the post is recorded in its folder's WeChat media log
+    pub published_to_wechat: bool,
+    pub images: usize,
+    pub wechat_images: usize,
+    /// Milliseconds since the Unix epoch
+    pub modified: u64,
+    /// Why the front matter could not be read, if it could not
+    pub error: Option<String>,
+}
+
+/// Filter and order for `list_blog_posts`
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlogPostQuery {
+    /// Matched case-insensitively against title, path and tags
+    pub search: Option<String>,
+    pub tag: Option<String>,
+    pub draft: Option<bool>,
+    pub published: Option<bool>,
+    /// `date` (default), `title` or `modified`
+    pub sort_by: Option<String>,
+    pub ascending: bool,
+}
+
+#[derive(Serialize)]
+pub struct BlogPostList {
+    pub root: String,
+    pub posts: Vec<BlogPost>,
+    /// Number of posts before filtering
+    pub total: usize,
+    /// Every tag used in the blog, sorted
+    pub tags: Vec<String>,
+}
+
+// ============ Full-text search ============
+
+/// A line of a post that matched the search
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchSnippet {
+    /// 1-based line number in the file
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchHit {
+    pub path: String,
+    pub relative_path: String,
+    pub title: String,
+    pub score: f64,
+    pub snippets: Vec<SearchSnippet>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchResults {
+    pub root: String,
+    /// Number of markdown files in the index
+    pub indexed: usize,
+    /// Best matches first
+    pub hits: Vec<SearchHit>,
+}
+
+// ============ Site profiles ============
+
+/// Static-site generator whose path conventions a post follows
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteGenerator {
+    /// No generator config found; image paths are taken as they are
+    Plain,
+    Hexo,
+    Hugo,
+    Jekyll,
+}
+
+/// What was read from the generator config of the site containing a post
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteProfileInfo {
+    pub generator: SiteGenerator,
+    /// Folder holding the generator config
+    pub root: Option<String>,
+    /// Published address of the site root, without a trailing slash
+    pub base_url: Option<String>,
+    /// Folders whose files are published at the site root
+    pub static_dirs: Vec<String>,
+    /// Hexo `post_asset_folder`
+    pub post_asset_folder: bool,
+}
+
+// ============ Article metadata ============
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ArticleMeta {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub digest: Option<String>,
+    pub cover: Option<String>,
+    pub tags: Vec<String>,
+    pub original_url: Option<String>,
+    pub date: Option<String>,
+    /// Front matter keys that are not mapped to a typed field
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Options for `convert_markdown`, sent from the frontend as one object
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertOptions {
+    pub highlight_theme: Option<String>,
+    /// `png` (default) or `svg` for math and diagrams. WeChat does not show SVG
+    /// images, so `svg` is only for previews and other targets
+    pub image_format: Option<String>,
+    pub footnote_allowlist: Option<Vec<String>>,
+    pub footnote_heading: Option<String>,
+    pub image_prefix: Option<String>,
+    pub assets_dir: Option<String>,
+    pub toc_numbered: Option<bool>,
+    /// Deepest heading level listed in the table of contents
+    pub toc_depth: Option<u8>,
+    pub toc_title: Option<String>,
+    /// Directory of the open file, used to resolve local image paths
+    pub base_dir: Option<String>,
+    /// The open file, so images resolve with its site's conventions
+    pub file_path: Option<String>,
+    /// Ordered transform names; `None` runs the default pipeline
+    pub transforms: Option<Vec<String>>,
+    /// Add `data-darkmode-*` colour hints for WeChat's dark mode reader
+    pub dark_mode_hints: Option<bool>,
+    /// Generate the theme from these tokens instead of using the given CSS
+    pub theme_tokens: Option<ThemeTokens>,
+}
+
+#[derive(Serialize)]
+pub struct ConvertMarkdownResult {
+    pub html: String,
+    pub meta: Option<ArticleMeta>,
+    pub outline: Vec<OutlineItem>,
+    pub stats: DocumentStats,
+    pub images: Vec<ImageInfo>,
+    pub links: Vec<LinkInfo>,
+    pub warnings: Vec<ConvertWarning>,
+}
+
+// ============ Document analysis ============
+
+#[derive(Serialize, Clone, Debug)]
+pub struct OutlineItem {
+    pub level: u8,
+    pub text: String,
+    /// Heading `id` written into the HTML
+    pub anchor: String,
+    /// 1-based line in the Markdown source
+    pub line: usize,
+}
+
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct DocumentStats {
+    /// Non-whitespace characters of prose, excluding code blocks
+    pub characters: usize,
+    /// CJK characters count as one word each, other scripts by whitespace
+    pub words: usize,
+    pub reading_minutes: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImageInfo {
+    pub src: String,
+    pub alt: String,
+    pub title: String,
+    /// `remote`, `local` or `data`
+    pub kind: String,
+    /// URL or absolute file path the image loads from; `None` if it cannot be found
+    pub resolved: Option<String>,
+    pub line: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LinkInfo {
+    pub url: String,
+    pub text: String,
+    pub title: String,
+    pub line: usize,
+}
+
+/// A problem that does not stop conversion but is worth showing to the user
+#[derive(Serialize, Clone, Debug)]
+pub struct ConvertWarning {
+    pub kind: String,
+    pub message: String,
+    /// 1-based Markdown line; 0 when the warning is about the theme, not the source
+    pub line: usize,
+}
+
+// ============ WeChat compatibility lint ============
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LintDiagnostic {
+    pub rule: String,
+    pub severity: LintSeverity,
+    pub message: String,
+    /// 1-based line of the Markdown block the problem comes from
+    pub line: usize,
+    /// The offending tag, shortened
+    pub snippet: String,
+}
+
+// ============ Style layer trace ============
+
+/// Where an inlined CSS property came from, lowest priority last
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StyleLayer {
+    /// The element's own `style` attribute, e.g. from code highlighting
+    Inline,
+    Theme,
+    Fallback,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StyleSource {
+    pub property: String,
+    pub value: String,
+    pub layer: StyleLayer,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ElementStyleTrace {
+    /// Tag name followed by its classes, e.g. `p.admonition-title`
+    pub element: String,
+    /// 1-based Markdown line of the enclosing block; 0 for the wrapper
+    pub line: usize,
+    pub properties: Vec<StyleSource>,
+}
+
+// ============ Theme contrast ============
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ContrastCheck {
+    /// Fallback stylesheet selector, without the `.wechat-content` scope
+    pub selector: String,
+    pub color: String,
+    pub background: String,
+    pub ratio: f64,
+    /// Headings, which only need 3:1 for AA
+    pub large_text: bool,
+    /// Ratio needed for WCAG AA
+    pub required: f64,
+    pub passes_aa: bool,
+    pub passes_aaa: bool,
+    /// Nearest text colour with the same hue that passes AA
+    pub suggested_color: Option<String>,
+    /// Nearest background colour with the same hue that passes AA
+    pub suggested_background: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ContrastReport {
+    pub checks: Vec<ContrastCheck>,
+    /// Number of checks below WCAG AA
+    pub failures: usize,
+}
+
+// ============ Theme packages ============
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingStyle {
+    Underline,
+    #[default]
+    LeftBar,
+    Filled,
+    Centered,
+    Plain,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodeStyle {
+    #[default]
+    Light,
+    Dark,
+    Bordered,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuoteStyle {
+    #[default]
+    Bar,
+    Card,
+    Italic,
+}
+
+/// The handful of choices a generated theme is built from
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThemeTokens {
+    pub primary_color: String,
+    /// Body text size in px
+    pub font_size: u32,
+    pub line_height: f64,
+    pub heading_style: HeadingStyle,
+    pub code_style: CodeStyle,
+    pub quote_style: QuoteStyle,
+}
+
+impl Default for ThemeTokens {
+    fn default() -> Self {
+        ThemeTokens {
+            primary_color: "#07c160".to_string(),
+            font_size: 16,
+            line_height: 1.75,
+            heading_style: HeadingStyle::default(),
+            code_style: CodeStyle::default(),
+            quote_style: QuoteStyle::default(),
+        }
+    }
+}
+
+/// `theme.json` at the root of a theme package
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ThemeManifest {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Id or name of a theme whose CSS is applied underneath this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Code highlight palette used with this theme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
+    /// Stylesheet inside the package, `theme.css` when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub css: Option<String>,
+    /// Tokens the stylesheet was generated from, for themes made by the generator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<ThemeTokens>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ThemeInfo {
+    pub id: String,
+    pub name: String,
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub base: Option<String>,
+    pub highlight: Option<String>,
+    pub builtin: bool,
+    pub tokens: Option<ThemeTokens>,
+    /// Full CSS with the base themes layered underneath
+    pub css: String,
+    /// Package directory; `None` for built-in themes
+    pub path: Option<String>,
+}
+
+/// A rule of an imported mdnice or doocs/md theme with no WxTyper equivalent
+#[derive(Serialize, Clone, Debug)]
+pub struct UnmappedRule {
+    pub selector: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ThemeImportResult {
+    pub theme: ThemeInfo,
+    pub unmapped: Vec<UnmappedRule>,
+}
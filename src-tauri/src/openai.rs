@@ -1,5 +1,6 @@
 use reqwest::Client;
 
+use crate::front_matter::strip_front_matter;
 use crate::models::{ChatCompletionRequest, ChatCompletionRequestMessage, ChatCompletionResponse};
 
 #[allow(non_snake_case)]
@@ -31,9 +32,15 @@ pub async fn generate_summary(
         })
         .unwrap_or_else(|| "deepseek-chat".to_string());
 
+    let (meta, body) = strip_front_matter(&markdown);
+    let title_line = meta
+        .and_then(|m| m.title)
+        .map(|t| format!("标题：{}\n\n", t))
+        .unwrap_or_default();
+
     let prompt = format!(
-        "请根据以下微信公众号 Markdown 内容生成一个中文摘要，不超过100个汉字，不要换行，只输出摘要内容：\n\n{}",
-        markdown
+        "请根据以下微信公众号 Markdown 内容生成一个中文摘要，不超过100个汉字，不要换行，只输出摘要内容：\n\n{}{}",
+        title_line, body
     );
 
     let request_body = ChatCompletionRequest {
@@ -0,0 +1,144 @@
+use pulldown_cmark::Event;
+
+use crate::admonition::AdmonitionTransform;
+use crate::footnotes::{FootnoteOptions, FootnoteTransform};
+use crate::highlight::HighlightTransform;
+use crate::image::ImagePrefixTransform;
+use crate::math::MathTransform;
+use crate::mermaid::MermaidTransform;
+use crate::models::{ArticleMeta, ConvertOptions};
+use crate::svg::ImageFormat;
+use crate::toc::TocTransform;
+
+/// A rewrite step over the pulldown-cmark event stream
+pub trait Transform: Send + Sync {
+    /// Name used to select and order the transform in `ConvertOptions::transforms`
+    fn name(&self) -> &'static str;
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>>;
+}
+
+/// Transforms run when the caller does not choose an order
+pub const DEFAULT_TRANSFORMS: &[&str] = &[
+    "toc",
+    "admonitions",
+    "mermaid",
+    "highlight",
+    "math",
+    "images",
+    "footnotes",
+];
+
+/// An ordered list of transforms applied one after another
+#[derive(Default)]
+pub struct Pipeline {
+    transforms: Vec<Box<dyn Transform>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, transform: Box<dyn Transform>) {
+        self.transforms.push(transform);
+    }
+
+    pub fn run<'a>(&self, events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+        self.transforms
+            .iter()
+            .fold(events.collect(), |events, transform| {
+                transform.apply(events)
+            })
+    }
+
+    /// Build the pipeline described by the conversion options and the
+    /// article's front matter
+    pub fn from_options(
+        options: &ConvertOptions,
+        meta: Option<&ArticleMeta>,
+    ) -> Result<Self, String> {
+        let mut pipeline = Pipeline::new();
+        let names: Vec<&str> = match &options.transforms {
+            Some(names) => names.iter().map(|n| n.as_str()).collect(),
+            None => DEFAULT_TRANSFORMS.to_vec(),
+        };
+        for name in names {
+            let transform = builtin_transform(name.trim(), options, meta)?;
+            if pipeline
+                .transforms
+                .iter()
+                .any(|t| t.name() == transform.name())
+            {
+                return Err(format!("转换步骤重复: {}", transform.name()));
+            }
+            pipeline.push(transform);
+        }
+        Ok(pipeline)
+    }
+}
+
+fn builtin_transform(
+    name: &str,
+    options: &ConvertOptions,
+    meta: Option<&ArticleMeta>,
+) -> Result<Box<dyn Transform>, String> {
+    let image_format = ImageFormat::parse(options.image_format.as_deref(), ImageFormat::Png);
+    let transform: Box<dyn Transform> = match name {
+        "toc" => Box::new(TocTransform::from_options(options, meta)),
+        "admonitions" => Box::new(AdmonitionTransform),
+        "mermaid" => Box::new(MermaidTransform {
+            format: image_format,
+        }),
+        "highlight" => Box::new(HighlightTransform {
+            palette: options.highlight_theme.clone(),
+        }),
+        "math" => Box::new(MathTransform {
+            format: image_format,
+        }),
+        "images" => Box::new(ImagePrefixTransform {
+            prefix: options.image_prefix.clone().unwrap_or_default(),
+            assets_dir: options
+                .assets_dir
+                .clone()
+                .unwrap_or_else(|| "assets".to_string()),
+        }),
+        "footnotes" => {
+            let mut footnote_options = FootnoteOptions::default();
+            if let Some(domains) = &options.footnote_allowlist {
+                footnote_options.allowed_domains = domains.clone();
+            }
+            if let Some(heading) = &options.footnote_heading {
+                footnote_options.heading = Some(heading.clone());
+            }
+            Box::new(FootnoteTransform {
+                options: footnote_options,
+            })
+        }
+        other => return Err(format!("未知的转换步骤: {}", other)),
+    };
+    Ok(transform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_options_rejects_unknown_and_duplicate_steps() {
+        let mut options = ConvertOptions::default();
+        assert_eq!(
+            Pipeline::from_options(&options, None)
+                .unwrap()
+                .transforms
+                .len(),
+            DEFAULT_TRANSFORMS.len()
+        );
+
+        options.transforms = Some(vec!["highlight".to_string(), "emoji".to_string()]);
+        assert!(Pipeline::from_options(&options, None).is_err());
+
+        options.transforms = Some(vec!["math".to_string(), " math ".to_string()]);
+        assert!(Pipeline::from_options(&options, None).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::State;
+
+use crate::blog::{markdown_files, relative_path, Stamp};
+use crate::encoding::read_text;
+use crate::front_matter::split_front_matter;
+use crate::models::{SearchHit, SearchResults, SearchSnippet};
+use crate::workspace::Workspace;
+
+/// Hits returned when the frontend does not say otherwise
+const DEFAULT_LIMIT: usize = 50;
+/// Matching lines shown per hit
+const MAX_SNIPPETS: usize = 3;
+/// Characters of a matching line kept around the first match
+const SNIPPET_CHARS: usize = 80;
+/// Title terms count as this many occurrences in the body
+const TITLE_WEIGHT: u32 = 3;
+/// BM25 term-frequency saturation and length normalisation
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Han, kana and hangul, which are written without spaces between words
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FA1F}')
+}
+
+fn push_cjk_run(run: &[char], for_query: bool, tokens: &mut Vec<String>) {
+    // Without a dictionary, overlapping character pairs stand in for words.
+    // Documents also index single characters so one-character queries match.
+    if !for_query || run.len() == 1 {
+        tokens.extend(run.iter().map(char::to_string));
+    }
+    tokens.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+}
+
+/// Split text into lowercase words and, for Chinese and other CJK text,
+/// character bigrams. Queries only use bigrams for runs of two or more
+/// characters, so a match needs the characters side by side.
+fn tokenize(text: &str, for_query: bool) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut run: Vec<char> = Vec::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            push_cjk_run(&run, for_query, &mut tokens);
+            run.clear();
+        }
+        if c.is_alphanumeric() || c == '_' {
+            word.extend(c.to_lowercase());
+        } else if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    if !run.is_empty() {
+        push_cjk_run(&run, for_query, &mut tokens);
+    }
+    tokens
+}
+
+struct Document {
+    stamp: Stamp,
+    relative_path: String,
+    title: String,
+    content: String,
+    terms: HashMap<String, u32>,
+    length: u64,
+}
+
+impl Document {
+    fn read(root: &Path, path: &Path, stamp: Stamp) -> Self {
+        let content = read_text(path).map(|(text, _)| text).unwrap_or_default();
+        let title = split_front_matter(&content)
+            .ok()
+            .and_then(|(meta, _)| meta?.title)
+            .unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        for token in tokenize(&content, false) {
+            *terms.entry(token).or_default() += 1;
+        }
+        for token in tokenize(&title, false) {
+            *terms.entry(token).or_default() += TITLE_WEIGHT;
+        }
+        Document {
+            stamp,
+            relative_path: relative_path(root, path),
+            title,
+            length: terms.values().map(|&n| n as u64).sum(),
+            content,
+            terms,
+        }
+    }
+
+    /// Up to `MAX_SNIPPETS` lines containing the most query terms, in file order
+    fn snippets(&self, terms: &[String]) -> Vec<SearchSnippet> {
+        let mut lines: Vec<(usize, usize, &str)> = self
+            .content
+            .lines()
+            .enumerate()
+            .filter_map(|(number, line)| {
+                let lower = line.to_lowercase();
+                let matched = terms.iter().filter(|t| lower.contains(t.as_str())).count();
+                (matched > 0).then_some((number + 1, matched, line))
+            })
+            .collect();
+        lines.sort_by_key(|&(number, matched, _)| (std::cmp::Reverse(matched), number));
+        lines.truncate(MAX_SNIPPETS);
+        lines.sort_by_key(|&(number, _, _)| number);
+        lines
+            .into_iter()
+            .map(|(line, _, text)| SearchSnippet {
+                line,
+                text: clip_around_match(text.trim(), terms),
+            })
+            .collect()
+    }
+}
+
+/// Cut a long line down to `SNIPPET_CHARS` characters around its first match
+fn clip_around_match(line: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.len() <= SNIPPET_CHARS {
+        return line.to_string();
+    }
+    let lower = line.to_lowercase();
+    let first = terms
+        .iter()
+        .filter_map(|t| lower.find(t.as_str()))
+        .min()
+        .map(|byte| lower[..byte].chars().count())
+        .unwrap_or(0);
+    let start = first
+        .saturating_sub(SNIPPET_CHARS / 4)
+        .min(chars.len() - SNIPPET_CHARS);
+    let end = start + SNIPPET_CHARS;
+    format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        chars[start..end].iter().collect::<String>(),
+        if end < chars.len() { "…" } else { "" }
+    )
+}
+
+/// Inverted index over the markdown files under one root, updated only for
+/// files that changed since the last search
+struct TextIndex {
+    root: PathBuf,
+    docs: HashMap<PathBuf, Document>,
+    postings: HashMap<String, HashSet<PathBuf>>,
+}
+
+impl TextIndex {
+    fn new(root: PathBuf) -> Self {
+        TextIndex {
+            root,
+            docs: HashMap::new(),
+            postings: HashMap::new(),
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(doc) = self.docs.remove(path) else {
+            return;
+        };
+        for term in doc.terms.keys() {
+            if let Some(paths) = self.postings.get_mut(term) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+    }
+
+    fn refresh(&mut self) {
+        let mut seen = HashSet::new();
+        for (path, stamp) in markdown_files(&self.root) {
+            seen.insert(path.clone());
+            if self.docs.get(&path).is_some_and(|d| d.stamp == stamp) {
+                continue;
+            }
+            self.remove(&path);
+            let doc = Document::read(&self.root, &path, stamp);
+            for term in doc.terms.keys() {
+                self.postings
+                    .entry(term.clone())
+                    .or_default()
+                    .insert(path.clone());
+            }
+            self.docs.insert(path, doc);
+        }
+        let deleted: Vec<PathBuf> = self
+            .docs
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+        for path in deleted {
+            self.remove(&path);
+        }
+    }
+
+    /// Files containing every query term, ranked by BM25
+    fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let mut terms = tokenize(query, true);
+        terms.sort();
+        terms.dedup();
+        let Some(mut candidates) = terms
+            .iter()
+            .map(|t| self.postings.get(t))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Vec::new();
+        };
+        candidates.sort_by_key(|paths| paths.len());
+        let Some((rarest, rest)) = candidates.split_first() else {
+            return Vec::new();
+        };
+
+        let count = self.docs.len() as f64;
+        let average_length = self.docs.values().map(|d| d.length).sum::<u64>() as f64 / count;
+        let idf: Vec<f64> = terms
+            .iter()
+            .map(|t| {
+                let df = self.postings[t].len() as f64;
+                (1.0 + (count - df + 0.5) / (df + 0.5)).ln()
+            })
+            .collect();
+
+        let mut hits: Vec<SearchHit> = rarest
+            .iter()
+            .filter(|path| rest.iter().all(|paths| paths.contains(*path)))
+            .filter_map(|path| {
+                let doc = self.docs.get(path)?;
+                let norm = 1.0 - BM25_B + BM25_B * doc.length as f64 / average_length;
+                let score = terms
+                    .iter()
+                    .zip(&idf)
+                    .map(|(term, idf)| {
+                        let tf = doc.terms.get(term).copied().unwrap_or_default() as f64;
+                        idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm)
+                    })
+                    .sum();
+                Some(SearchHit {
+                    path: path.to_string_lossy().to_string(),
+                    relative_path: doc.relative_path.clone(),
+                    title: doc.title.clone(),
+                    score,
+                    snippets: doc.snippets(&terms),
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.relative_path.cmp(&b.relative_path))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Full-text index of the most recently searched root
+#[derive(Default)]
+pub struct SearchIndex {
+    index: Mutex<Option<TextIndex>>,
+}
+
+/// Search the markdown files under `root`, indexing new and changed files first
+#[tauri::command]
+pub fn search_markdown(
+    workspace: State<'_, Workspace>,
+    search: State<'_, SearchIndex>,
+    root: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<SearchResults, String> {
+    let root = workspace.resolve(&root)?;
+    if !root.is_dir() {
+        return Err(format!("搜索目录不存在: {}", root.display()));
+    }
+    let mut guard = search.index.lock().map_err(|e| e.to_string())?;
+    let index = match guard.take() {
+        Some(index) if index.root == root => index,
+        _ => TextIndex::new(root),
+    };
+    let index = guard.insert(index);
+    index.refresh();
+    Ok(SearchResults {
+        root: index.root.to_string_lossy().to_string(),
+        indexed: index.docs.len(),
+        hits: index.search(&query, limit.unwrap_or(DEFAULT_LIMIT)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_tokenize_and_ranked_incremental_search() {
+        assert_eq!(
+            tokenize("用Rust写工具", false),
+            ["用", "rust", "写", "工", "具", "写工", "工具"]
+        );
+        assert_eq!(
+            tokenize("微信工具 API", true),
+            ["微信", "信工", "工具", "api"]
+        );
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().to_path_buf();
+        fs::create_dir_all(root.join("posts")).unwrap();
+        fs::create_dir_all(root.join("node_modules")).unwrap();
+        fs::write(
+            root.join("posts/tools.md"),
+            "---\ntitle: 常用工具\n---\n\n介绍几个写作工具。\n\n![架构图](assets/arch.png)\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("posts/other.md"),
+            "# 随笔\n\n今天没有用到什么工具，只是散步。\n",
+        )
+        .unwrap();
+        fs::write(root.join("node_modules/readme.md"), "工具").unwrap();
+
+        let mut index = TextIndex::new(root.clone());
+        index.refresh();
+        assert_eq!(index.docs.len(), 2);
+
+        let hits = index.search("工具", 10);
+        let paths: Vec<&str> = hits.iter().map(|h| h.relative_path.as_str()).collect();
+        assert_eq!(paths, ["posts/tools.md", "posts/other.md"]);
+        assert_eq!(hits[0].title, "常用工具");
+        assert_eq!(hits[1].snippets[0].line, 3);
+        assert_eq!(index.search("架构图", 10)[0].snippets[0].line, 7);
+        assert!(index.search("工具 不存在", 10).is_empty());
+        assert_eq!(index.search("散", 10).len(), 1);
+
+        // Edited files are re-indexed and deleted files dropped
+        fs::write(root.join("posts/other.md"), "# 随笔\n\n散步。\n").unwrap();
+        fs::remove_file(root.join("posts/tools.md")).unwrap();
+        index.refresh();
+        assert!(index.search("工具", 10).is_empty());
+        assert!(!index.postings.contains_key("工具"));
+        assert_eq!(index.search("散步", 10).len(), 1);
+    }
+}
@@ -0,0 +1,726 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use tauri::State;
+
+use crate::front_matter::{strip_front_matter, toml_to_json};
+use crate::models::{ArticleMeta, SiteGenerator, SiteProfileInfo};
+use crate::workspace::Workspace;
+
+/// Hugo config files, newest name first
+const HUGO_CONFIGS: &[&str] = &[
+    "hugo.toml",
+    "hugo.yaml",
+    "hugo.yml",
+    "hugo.json",
+    "config.toml",
+    "config.yaml",
+    "config.yml",
+    "config.json",
+    "config/_default/hugo.toml",
+    "config/_default/hugo.yaml",
+    "config/_default/config.toml",
+    "config/_default/config.yaml",
+];
+/// Config shared by Hexo and Jekyll
+const UNDERSCORE_CONFIGS: &[&str] = &["_config.yml", "_config.yaml"];
+const HEXO_DEFAULT_PERMALINK: &str = ":year/:month/:day/:title/";
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+fn is_remote(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http:", "https:", "data:", "//"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+fn read_config(path: &Path) -> Option<Value> {
+    let text = fs::read_to_string(path).ok()?;
+    match path.extension()?.to_str()? {
+        "toml" => toml::from_str::<toml::Value>(&text).ok().map(toml_to_json),
+        "json" => serde_json::from_str(&text).ok(),
+        _ => serde_yaml::from_str::<serde_yaml::Value>(&text)
+            .ok()
+            .and_then(|yaml| serde_json::to_value(yaml).ok()),
+    }
+}
+
+/// Hugo matches config keys case-insensitively; the others never differ in case
+fn config_value<'a>(config: &'a Value, key: &str) -> Option<&'a Value> {
+    config
+        .as_object()?
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn config_str(config: &Value, key: &str) -> Option<String> {
+    config_value(config, key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn config_bool(config: &Value, key: &str) -> bool {
+    config_value(config, key).and_then(Value::as_bool) == Some(true)
+}
+
+/// A string or a list of strings
+fn config_list(config: &Value, key: &str) -> Vec<String> {
+    match config_value(config, key) {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `https://example.com/blog/` becomes `https://example.com/blog`
+fn normalize_url(url: &str) -> Option<String> {
+    Some(url.trim().trim_end_matches('/').to_string()).filter(|u| !u.is_empty())
+}
+
+/// Path part of a site URL, e.g. `/blog` for `https://example.com/blog`
+fn url_path(url: &str) -> String {
+    let after_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    after_scheme
+        .find('/')
+        .map(|i| after_scheme[i..].trim_end_matches('/').to_string())
+        .unwrap_or_default()
+}
+
+fn join_url(base: &str, path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./").trim_start_matches('/');
+    format!("{}/{}", base.trim_end_matches('/'), path)
+}
+
+/// Year, month and day of a front matter date such as `2024-03-05 10:00:00`
+fn date_parts(date: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = date.get(..10)?.split('-');
+    Some((parts.next()?, parts.next()?, parts.next()?))
+}
+
+/// Hugo's `urlize`: whitespace becomes `-`, characters unsafe in a path are dropped
+fn urlize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+        .collect()
+}
+
+/// Path of `path` under `dir` with `/` separators
+fn relative_to(path: &Path, dir: &Path) -> Option<String> {
+    let rel = path.strip_prefix(dir).ok()?;
+    Some(
+        rel.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// The post an image reference appears in
+pub struct PostContext {
+    pub dir: PathBuf,
+    pub file: Option<PathBuf>,
+    pub meta: ArticleMeta,
+}
+
+impl PostContext {
+    pub fn new(dir: PathBuf, file: Option<PathBuf>, markdown: &str) -> Self {
+        let (meta, _) = strip_front_matter(markdown);
+        PostContext {
+            dir,
+            file,
+            meta: meta.unwrap_or_default(),
+        }
+    }
+
+    fn stem(&self) -> Option<String> {
+        self.file
+            .as_ref()?
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+    }
+
+    fn front_matter_str(&self, key: &str) -> Option<&str> {
+        self.meta
+            .extra
+            .get(key)
+            .and_then(Value::as_str)
+            .filter(|s| !s.trim().is_empty())
+    }
+}
+
+/// Where an image reference points on disk and once published
+#[derive(Debug, Default, PartialEq)]
+pub struct ResolvedImage {
+    pub local: Option<PathBuf>,
+    pub url: Option<String>,
+}
+
+/// Path conventions of the site a post belongs to, read from its generator config
+pub struct SiteProfile {
+    generator: SiteGenerator,
+    root: Option<PathBuf>,
+    base_url: Option<String>,
+    /// Path part of the base URL, which root-relative references may include
+    base_path: String,
+    static_dirs: Vec<PathBuf>,
+    /// Hugo `contentDir` or Hexo `source/_posts`
+    content_dir: Option<PathBuf>,
+    post_asset_folder: bool,
+    /// Hexo `permalink`
+    permalink: String,
+    /// Hugo `[permalinks]`, by content section
+    section_permalinks: Vec<(String, String)>,
+    lowercase_paths: bool,
+}
+
+/// `[permalinks]` patterns by section. Newer Hugo versions nest them under
+/// the page kind, e.g. `[permalinks.page]`.
+fn hugo_permalinks(config: &Value) -> Vec<(String, String)> {
+    let Some(Value::Object(permalinks)) = config_value(config, "permalinks") else {
+        return Vec::new();
+    };
+    let sections = match permalinks.get("page") {
+        Some(Value::Object(page)) => page,
+        _ => permalinks,
+    };
+    sections
+        .iter()
+        .filter_map(|(section, pattern)| Some((section.clone(), pattern.as_str()?.to_string())))
+        .collect()
+}
+
+/// Expand a Hugo permalink pattern for a page named `name` in the content
+/// folders `sections`. `None` when it uses a token that cannot be filled in.
+fn hugo_permalink_path(
+    pattern: &str,
+    post: &PostContext,
+    sections: &[String],
+    name: &str,
+) -> Option<String> {
+    let mut path = pattern.to_string();
+    // Dates first, so a title can never be mistaken for a token
+    if [":year", ":month", ":day"].iter().any(|t| path.contains(t)) {
+        let (year, month, day) = date_parts(post.meta.date.as_deref()?)?;
+        let month_name = MONTH_NAMES.get(month.parse::<usize>().ok()?.checked_sub(1)?)?;
+        path = path
+            .replace(":year", year)
+            .replace(":monthname", month_name)
+            .replace(":month", month)
+            .replace(":day", day);
+    }
+    let title = post.meta.title.as_deref().map(urlize);
+    let slug = post
+        .front_matter_str("slug")
+        .map(urlize)
+        .or_else(|| title.clone());
+    let slug_or_name = slug.clone().unwrap_or_else(|| name.to_string());
+    path = path
+        .replace(":sections", &sections.join("/"))
+        .replace(":section", sections.first()?)
+        .replace(":slugorcontentbasename", &slug_or_name)
+        .replace(":slugorfilename", &slug_or_name)
+        .replace(":contentbasename", name)
+        .replace(":filename", name);
+    if path.contains(":title") {
+        path = path.replace(":title", title.as_deref()?);
+    }
+    if path.contains(":slug") {
+        path = path.replace(":slug", slug.as_deref()?);
+    }
+    // `:weekday`, `:yearday` and the like
+    if path.contains(':') {
+        return None;
+    }
+    Some(path.trim_matches('/').to_string())
+}
+
+impl SiteProfile {
+    fn plain(site_prefix: Option<String>) -> Self {
+        SiteProfile {
+            generator: SiteGenerator::Plain,
+            root: None,
+            base_path: site_prefix.as_deref().map(url_path).unwrap_or_default(),
+            base_url: site_prefix,
+            static_dirs: Vec::new(),
+            content_dir: None,
+            post_asset_folder: false,
+            permalink: String::new(),
+            section_permalinks: Vec::new(),
+            lowercase_paths: false,
+        }
+    }
+
+    fn hugo(root: &Path, config: &Value, site_prefix: Option<String>) -> Self {
+        let base_url = config_str(config, "baseURL")
+            .and_then(|u| normalize_url(&u))
+            .or(site_prefix);
+        let mut static_dirs = config_list(config, "staticDir");
+        if static_dirs.is_empty() {
+            static_dirs.push("static".to_string());
+        }
+        let content_dir = config_str(config, "contentDir").unwrap_or_else(|| "content".into());
+        SiteProfile {
+            generator: SiteGenerator::Hugo,
+            root: Some(root.to_path_buf()),
+            base_path: base_url.as_deref().map(url_path).unwrap_or_default(),
+            base_url,
+            static_dirs: static_dirs.iter().map(|d| root.join(d)).collect(),
+            content_dir: Some(root.join(content_dir)),
+            post_asset_folder: false,
+            permalink: String::new(),
+            section_permalinks: hugo_permalinks(config),
+            lowercase_paths: !config_bool(config, "disablePathToLower"),
+        }
+    }
+
+    fn hexo(root: &Path, config: &Value, site_prefix: Option<String>) -> Self {
+        let base_url = config_str(config, "url")
+            .and_then(|u| normalize_url(&u))
+            .or(site_prefix);
+        let base_path = config_str(config, "root")
+            .map(|r| r.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| base_url.as_deref().map(url_path).unwrap_or_default());
+        let source = root.join(config_str(config, "source_dir").unwrap_or_else(|| "source".into()));
+        SiteProfile {
+            generator: SiteGenerator::Hexo,
+            root: Some(root.to_path_buf()),
+            base_url,
+            base_path,
+            content_dir: Some(source.join("_posts")),
+            static_dirs: vec![source],
+            post_asset_folder: config_bool(config, "post_asset_folder"),
+            permalink: config_str(config, "permalink")
+                .unwrap_or_else(|| HEXO_DEFAULT_PERMALINK.into()),
+            section_permalinks: Vec::new(),
+            lowercase_paths: false,
+        }
+    }
+
+    fn jekyll(root: &Path, config: &Value, site_prefix: Option<String>) -> Self {
+        let base_path = config_str(config, "baseurl")
+            .map(|b| format!("/{}", b.trim_matches('/')))
+            .filter(|b| b != "/")
+            .unwrap_or_default();
+        let base_url = config_str(config, "url")
+            .and_then(|u| normalize_url(&format!("{}{}", u.trim_end_matches('/'), base_path)))
+            .or(site_prefix);
+        let source = root.join(config_str(config, "source").unwrap_or_default());
+        SiteProfile {
+            generator: SiteGenerator::Jekyll,
+            root: Some(root.to_path_buf()),
+            base_url,
+            base_path,
+            static_dirs: vec![source],
+            content_dir: None,
+            post_asset_folder: false,
+            permalink: String::new(),
+            section_permalinks: Vec::new(),
+            lowercase_paths: false,
+        }
+    }
+
+    /// Walk up from the post's folder to the nearest generator config. The
+    /// site prefix setting stands in when the config has no site URL.
+    pub fn detect(post_dir: &Path, site_prefix: Option<&str>) -> Self {
+        let site_prefix = site_prefix.and_then(normalize_url);
+        for dir in post_dir.ancestors().filter(|d| !d.as_os_str().is_empty()) {
+            for name in HUGO_CONFIGS {
+                let Some(config) = read_config(&dir.join(name)) else {
+                    continue;
+                };
+                // `config.*` is too generic a name to be sure on its own
+                let is_hugo = name.starts_with("hugo.")
+                    || name.starts_with("config/")
+                    || config_value(&config, "baseURL").is_some()
+                    || dir.join("archetypes").is_dir();
+                if is_hugo {
+                    return Self::hugo(dir, &config, site_prefix);
+                }
+            }
+            for name in UNDERSCORE_CONFIGS {
+                let Some(config) = read_config(&dir.join(name)) else {
+                    continue;
+                };
+                let is_hexo = fs::read_to_string(dir.join("package.json"))
+                    .is_ok_and(|package| package.contains("\"hexo\""))
+                    || config_value(&config, "post_asset_folder").is_some()
+                    || config_value(&config, "new_post_name").is_some();
+                return if is_hexo {
+                    Self::hexo(dir, &config, site_prefix)
+                } else {
+                    Self::jekyll(dir, &config, site_prefix)
+                };
+            }
+        }
+        Self::plain(site_prefix)
+    }
+
+    pub fn info(&self) -> SiteProfileInfo {
+        SiteProfileInfo {
+            generator: self.generator,
+            root: self.root.as_ref().map(|r| r.to_string_lossy().to_string()),
+            base_url: self.base_url.clone(),
+            static_dirs: self
+                .static_dirs
+                .iter()
+                .map(|d| d.to_string_lossy().to_string())
+                .collect(),
+            post_asset_folder: self.post_asset_folder,
+        }
+    }
+
+    /// A root-relative reference without the site's base path or leading `/`
+    fn site_path<'a>(&self, path: &'a str) -> &'a str {
+        let rest = if self.base_path.is_empty() {
+            path
+        } else {
+            path.strip_prefix(&self.base_path)
+                .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+                .unwrap_or(path)
+        };
+        rest.trim_start_matches('/')
+    }
+
+    /// Folder whose files are published under the post's own URL: a Hexo post
+    /// asset folder or a Hugo page bundle
+    fn page_dir(&self, post: &PostContext) -> Option<PathBuf> {
+        match self.generator {
+            SiteGenerator::Hexo if self.post_asset_folder => Some(post.dir.join(post.stem()?)),
+            SiteGenerator::Hugo => Some(post.dir.clone()),
+            _ => None,
+        }
+    }
+
+    /// Published URL of the post's page, without a trailing slash
+    fn page_url(&self, post: &PostContext) -> Option<String> {
+        let base = self.base_url.as_deref()?;
+        let file = post.file.as_ref()?;
+        let rel = relative_to(file, self.content_dir.as_ref()?)?;
+        match self.generator {
+            SiteGenerator::Hugo => {
+                if let Some(url) = post.front_matter_str("url") {
+                    return Some(join_url(base, url.trim_end_matches('/')));
+                }
+                let stem = post.stem()?;
+                let mut segments: Vec<String> = rel.split('/').map(str::to_string).collect();
+                segments.pop();
+                let bundle = stem == "index" || stem == "_index";
+                let pattern = segments.first().and_then(|section| {
+                    self.section_permalinks
+                        .iter()
+                        .find(|(s, _)| s == section)
+                        .map(|(_, pattern)| pattern.as_str())
+                });
+                let path = match pattern {
+                    Some(pattern) if stem != "_index" => {
+                        // Bundles are named after their folder
+                        let name = if bundle { segments.pop()? } else { stem };
+                        hugo_permalink_path(pattern, post, &segments, &name)?
+                    }
+                    _ => {
+                        if !bundle {
+                            segments.push(stem);
+                        }
+                        if let Some(slug) = post.front_matter_str("slug") {
+                            segments.pop();
+                            segments.push(slug.to_string());
+                        }
+                        segments.join("/")
+                    }
+                };
+                let path = if self.lowercase_paths {
+                    path.to_lowercase()
+                } else {
+                    path
+                };
+                Some(join_url(base, &path))
+            }
+            SiteGenerator::Hexo => {
+                let mut path = self.permalink.clone();
+                if [":year", ":month", ":day"].iter().any(|t| path.contains(t)) {
+                    let (year, month, day) = date_parts(post.meta.date.as_deref()?)?;
+                    path = path
+                        .replace(":year", year)
+                        .replace(":i_month", month.trim_start_matches('0'))
+                        .replace(":month", month)
+                        .replace(":i_day", day.trim_start_matches('0'))
+                        .replace(":day", day);
+                }
+                let title = rel.rsplit_once('.').map_or(rel.as_str(), |(t, _)| t);
+                path = path
+                    .replace(":post_title", title)
+                    .replace(":title", title)
+                    .replace(":name", &post.stem()?);
+                // Permalinks like `:title.html` keep their assets in a folder of that name
+                let path = path.trim_end_matches('/').trim_end_matches(".html");
+                Some(join_url(base, path))
+            }
+            _ => None,
+        }
+    }
+
+    /// The first candidate for `path` that is a file inside the workspace
+    fn find_local(&self, workspace: &Workspace, post: &PostContext, path: &str) -> Option<PathBuf> {
+        let readable = |candidate: PathBuf| {
+            workspace
+                .resolve(&candidate.to_string_lossy())
+                .ok()
+                .filter(|file| file.is_file())
+        };
+        if path.starts_with('/') {
+            // Typora and similar editors write absolute filesystem paths;
+            // anything else is relative to the site's published root
+            let site_path = self.site_path(path);
+            return std::iter::once(PathBuf::from(path))
+                .chain(self.static_dirs.iter().map(|dir| dir.join(site_path)))
+                .find_map(readable);
+        }
+        let mut candidates = vec![post.dir.join(path)];
+        if let (SiteGenerator::Hexo, Some(dir)) = (self.generator, self.page_dir(post)) {
+            candidates.push(dir.join(path));
+        }
+        candidates.into_iter().find_map(readable)
+    }
+
+    fn published_url(
+        &self,
+        post: &PostContext,
+        path: &str,
+        local: Option<&Path>,
+    ) -> Option<String> {
+        let base = self.base_url.as_deref()?;
+        let Some(local) = local else {
+            return if path.starts_with('/') {
+                Some(join_url(base, self.site_path(path)))
+            } else if self.generator == SiteGenerator::Plain {
+                Some(join_url(base, path))
+            } else {
+                self.page_url(post).map(|page| join_url(&page, path))
+            };
+        };
+
+        if let Some(rel) = self.page_dir(post).and_then(|dir| relative_to(local, &dir)) {
+            return self.page_url(post).map(|page| join_url(&page, &rel));
+        }
+        self.static_dirs
+            .iter()
+            .find_map(|dir| {
+                let rel = relative_to(local, dir)?;
+                // Hexo and Jekyll do not publish `_posts`, `_drafts` and the like
+                let hidden = rel.split('/').any(|part| part.starts_with('_'));
+                (!hidden).then(|| join_url(base, &rel))
+            })
+            .or_else(|| (self.generator == SiteGenerator::Plain).then(|| join_url(base, path)))
+    }
+
+    /// Resolve an image reference of `post` to a local file if one exists in
+    /// the workspace and to the URL it is published at if the site URL is known
+    pub fn resolve_image(
+        &self,
+        workspace: &Workspace,
+        post: &PostContext,
+        src: &str,
+    ) -> ResolvedImage {
+        let src = src.split_whitespace().next().unwrap_or_default();
+        if is_remote(src) {
+            return ResolvedImage {
+                local: None,
+                url: Some(src.to_string()),
+            };
+        }
+        let path = src.split(['?', '#']).next().unwrap_or_default();
+        if path.is_empty() {
+            return ResolvedImage::default();
+        }
+        let local = self.find_local(workspace, post, path);
+        let url = self.published_url(post, path, local.as_deref());
+        ResolvedImage { local, url }
+    }
+}
+
+/// The generator, site URL and published folders detected for a post
+#[allow(non_snake_case)]
+#[tauri::command]
+pub fn get_site_profile(
+    workspace: State<'_, Workspace>,
+    filePath: String,
+    sitePrefix: Option<String>,
+) -> Result<SiteProfileInfo, String> {
+    let file = workspace.resolve(&filePath)?;
+    let dir = file.parent().unwrap_or(Path::new("."));
+    Ok(SiteProfile::detect(dir, sitePrefix.as_deref()).info())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn post(file: &Path, markdown: &str) -> PostContext {
+        PostContext::new(
+            file.parent().unwrap().to_path_buf(),
+            Some(file.to_path_buf()),
+            markdown,
+        )
+    }
+
+    #[test]
+    fn test_resolve_images_per_generator() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(temp.path()).unwrap();
+        let workspace = Workspace::load(None);
+        workspace.approve(&root).unwrap();
+
+        // Hugo: static folder and page bundles
+        let hugo = root.join("hugo");
+        write(
+            &hugo.join("hugo.toml"),
+            "baseURL = \"https://example.com/\"\n",
+        );
+        write(&hugo.join("static/images/logo.png"), "png");
+        let bundle = hugo.join("content/posts/My-Trip/index.md");
+        write(&bundle, "");
+        write(&hugo.join("content/posts/My-Trip/map.png"), "png");
+        let profile = SiteProfile::detect(bundle.parent().unwrap(), None);
+        assert_eq!(profile.generator, SiteGenerator::Hugo);
+        let ctx = post(&bundle, "");
+        assert_eq!(
+            profile.resolve_image(&workspace, &ctx, "/images/logo.png"),
+            ResolvedImage {
+                local: Some(hugo.join("static/images/logo.png")),
+                url: Some("https://example.com/images/logo.png".into()),
+            }
+        );
+        assert_eq!(
+            profile
+                .resolve_image(&workspace, &ctx, "map.png \"Map\"")
+                .url
+                .as_deref(),
+            Some("https://example.com/posts/my-trip/map.png")
+        );
+
+        // Hugo: `[permalinks]` for the post's section
+        let dated = root.join("dated");
+        write(
+            &dated.join("hugo.toml"),
+            "baseURL = \"https://example.com/\"\n[permalinks]\nposts = \"/:year/:month/:slug/\"\n",
+        );
+        let bundle = dated.join("content/posts/trip/index.md");
+        write(&bundle, "");
+        let profile = SiteProfile::detect(bundle.parent().unwrap(), None);
+        let ctx = post(&bundle, "---\ntitle: My Trip\ndate: 2024-03-05\n---\n");
+        assert_eq!(
+            profile
+                .resolve_image(&workspace, &ctx, "map.png")
+                .url
+                .as_deref(),
+            Some("https://example.com/2024/03/my-trip/map.png")
+        );
+
+        // Hexo: post asset folder and permalink
+        let hexo = root.join("hexo");
+        write(
+            &hexo.join("_config.yml"),
+            "url: https://blog.example.com\npost_asset_folder: true\npermalink: :year/:month/:title/\n",
+        );
+        let hexo_post = hexo.join("source/_posts/hello.md");
+        write(&hexo_post, "");
+        write(&hexo.join("source/_posts/hello/cover.jpg"), "jpg");
+        let profile = SiteProfile::detect(hexo_post.parent().unwrap(), None);
+        let ctx = post(&hexo_post, "---\ndate: 2024-03-05 10:00:00\n---\n");
+        assert_eq!(
+            profile.resolve_image(&workspace, &ctx, "cover.jpg"),
+            ResolvedImage {
+                local: Some(hexo.join("source/_posts/hello/cover.jpg")),
+                url: Some("https://blog.example.com/2024/03/hello/cover.jpg".into()),
+            }
+        );
+
+        // Jekyll: /assets under baseurl; missing files still get a URL
+        let jekyll = root.join("jekyll");
+        write(
+            &jekyll.join("_config.yml"),
+            "url: https://me.github.io\nbaseurl: /notes\n",
+        );
+        write(&jekyll.join("assets/img/a.png"), "png");
+        let jekyll_post = jekyll.join("_posts/2024-01-01-a.md");
+        write(&jekyll_post, "");
+        let profile = SiteProfile::detect(jekyll_post.parent().unwrap(), Some("https://ignored"));
+        let ctx = post(&jekyll_post, "");
+        assert_eq!(
+            profile.resolve_image(&workspace, &ctx, "/notes/assets/img/a.png"),
+            ResolvedImage {
+                local: Some(jekyll.join("assets/img/a.png")),
+                url: Some("https://me.github.io/notes/assets/img/a.png".into()),
+            }
+        );
+        assert_eq!(
+            profile.resolve_image(&workspace, &ctx, "/assets/img/gone.png"),
+            ResolvedImage {
+                local: None,
+                url: Some("https://me.github.io/notes/assets/img/gone.png".into()),
+            }
+        );
+
+        // Without a generator config the site prefix is used as before
+        let plain = root.join("plain/post.md");
+        write(&plain, "");
+        let profile =
+            SiteProfile::detect(plain.parent().unwrap(), Some("https://cdn.example.com/"));
+        assert_eq!(profile.generator, SiteGenerator::Plain);
+        assert_eq!(
+            profile
+                .resolve_image(&workspace, &post(&plain, ""), "/img/x.png")
+                .url
+                .as_deref(),
+            Some("https://cdn.example.com/img/x.png")
+        );
+
+        // Absolute filesystem paths, as Typora writes them, are found if
+        // they lie inside the workspace
+        let picture = root.join("Pictures/a.png");
+        write(&picture, "png");
+        let absolute = picture.to_string_lossy().to_string();
+        assert_eq!(
+            profile
+                .resolve_image(&workspace, &post(&plain, ""), &absolute)
+                .local,
+            Some(picture)
+        );
+        let outside = std::env::current_dir().unwrap().join("Cargo.toml");
+        assert_eq!(
+            profile
+                .resolve_image(&workspace, &post(&plain, ""), &outside.to_string_lossy())
+                .local,
+            None
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use resvg::{tiny_skia, usvg};
+use std::sync::Arc;
+
+/// System fonts are loaded once, since diagrams with text labels need them to rasterise
+static FONT_DB: Lazy<Arc<usvg::fontdb::Database>> = Lazy::new(|| {
+    let mut db = usvg::fontdb::Database::new();
+    db.load_system_fonts();
+    Arc::new(db)
+});
+
+/// Output format for images rendered by the backend (math, diagrams)
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    /// Parse a user supplied format name, falling back to `default`
+    pub fn parse(value: Option<&str>, default: ImageFormat) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("png") => ImageFormat::Png,
+            Some("svg") => ImageFormat::Svg,
+            _ => default,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "image/svg+xml",
+            ImageFormat::Png => "image/png",
+        }
+    }
+}
+
+/// Rasterise an SVG document to PNG bytes at the given scale
+pub fn rasterize_svg(svg: &str, scale: f32) -> Result<Vec<u8>, String> {
+    let options = usvg::Options {
+        fontdb: FONT_DB.clone(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| format!("SVG 解析失败: {}", e))?;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(scale)
+        .ok_or_else(|| "SVG 尺寸无效".to_string())?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| "无法创建画布".to_string())?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("PNG 编码失败: {}", e))
+}
+
+/// Encode image bytes as a `data:` URI
+pub fn data_uri(bytes: &[u8], format: ImageFormat) -> String {
+    format!(
+        "data:{};base64,{}",
+        format.mime(),
+        general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+/// 64-bit FNV-1a, fixed across Rust releases unlike `DefaultHasher`
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET;
+    for (i, part) in parts.iter().enumerate() {
+        // Separate the parts so ("ab", "c") and ("a", "bc") differ
+        let separator: &[u8] = if i == 0 { b"" } else { b"\0" };
+        for byte in separator.iter().chain(part.iter()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// Stable file name for a rendered asset, so re-exporting the same source reuses the file
+pub fn asset_file_name(prefix: &str, source: &str, variant: &str, format: ImageFormat) -> String {
+    let hash = fnv1a(&[source.trim().as_bytes(), variant.as_bytes()]);
+    format!("{}-{:016x}.{}", prefix, hash, format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_names_and_rasterize() {
+        // Pinned so a toolchain upgrade cannot rename exported assets
+        assert_eq!(fnv1a(&[b"a"]), 0xaf63_dc4c_8601_ec8c);
+        let name = asset_file_name("math", " x^2 ", "inline", ImageFormat::Png);
+        assert_eq!(
+            name,
+            asset_file_name("math", "x^2", "inline", ImageFormat::Png)
+        );
+        assert_ne!(
+            name,
+            asset_file_name("math", "x^2", "display", ImageFormat::Png)
+        );
+        assert!(name.starts_with("math-") && name.ends_with(".png"));
+
+        assert!(ImageFormat::parse(Some(" PNG "), ImageFormat::Svg) == ImageFormat::Png);
+        assert!(ImageFormat::parse(Some("gif"), ImageFormat::Svg) == ImageFormat::Svg);
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="2"><rect width="4" height="2" fill="red"/></svg>"#;
+        let png = rasterize_svg(svg, 2.0).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        assert!(data_uri(&png, ImageFormat::Png).starts_with("data:image/png;base64,iVBOR"));
+        assert!(rasterize_svg("<svg", 1.0).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use std::fs;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::css::parse_rules;
+use crate::models::{ThemeImportResult, ThemeManifest, UnmappedRule};
+use crate::themes::{install_stylesheet, pick_theme_file, themes_dir, THEMES_CHANGED_EVENT};
+
+/// Preview containers of mdnice (`#nice`) and doocs/md (`#output`)
+const ROOT_SELECTORS: &[&str] = &["#nice", "#output"];
+/// Element selectors that mean the same thing in our HTML
+const KNOWN_TAGS: &[&str] = &[
+    "*",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "p",
+    "blockquote",
+    "ul",
+    "ol",
+    "li",
+    "pre",
+    "code",
+    "a",
+    "strong",
+    "em",
+    "del",
+    "s",
+    "img",
+    "hr",
+    "table",
+    "thead",
+    "tbody",
+    "tr",
+    "th",
+    "td",
+    "span",
+    "sup",
+    "sub",
+    "br",
+];
+/// Pseudo-classes css_inline can evaluate when inlining
+const STRUCTURAL_PSEUDO: &[&str] = &[
+    ":first-child",
+    ":last-child",
+    ":only-child",
+    ":nth-child",
+    ":nth-last-child",
+    ":first-of-type",
+    ":last-of-type",
+    ":nth-of-type",
+    ":not",
+];
+
+/// A theme translated to `.wechat-content` selectors
+pub struct ImportedTheme {
+    pub css: String,
+    pub unmapped: Vec<UnmappedRule>,
+}
+
+fn is_heading(compound: &str) -> bool {
+    matches!(compound, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// Our selector for a single mdnice/doocs class, as compounds
+fn map_class(class: &str, previous: Option<&str>) -> Result<Vec<String>, String> {
+    let mapped = match class {
+        // mdnice wraps heading text in `.content` between `.prefix` and `.suffix`
+        "content" if previous.is_some_and(is_heading) => vec![],
+        "prefix" | "suffix" => return Err("标题前后缀装饰元素在 WxTyper 中不存在".to_string()),
+        "footnote-ref" => vec![".footnote-ref".to_string()],
+        "footnotes-sep" => vec![".footnotes-title".to_string()],
+        "footnote-item" => vec![".footnotes".to_string(), "li".to_string()],
+        "footnote-num" | "footnote-word" => {
+            return Err("WxTyper 的脚注没有单独的编号和链接文字元素".to_string())
+        }
+        "code__pre" => vec!["pre".to_string()],
+        _ => {
+            if let Some(depth) = class
+                .strip_prefix("multiquote-")
+                .and_then(|d| d.parse::<usize>().ok())
+            {
+                vec!["blockquote".to_string(); depth.max(1)]
+            } else if class.starts_with("hljs") {
+                return Err("代码高亮颜色由 WxTyper 的高亮主题决定".to_string());
+            } else {
+                return Err(format!("未知的类名 .{}", class));
+            }
+        }
+    };
+    Ok(mapped)
+}
+
+/// Translate one compound selector such as `h1`, `.content` or `li:first-child`
+fn map_compound(compound: &str, previous: Option<&str>) -> Result<Vec<String>, String> {
+    let (base, pseudo) = match compound.find(':') {
+        Some(index) => compound.split_at(index),
+        None => (compound, ""),
+    };
+    if !pseudo.is_empty() {
+        let lower = pseudo.to_ascii_lowercase();
+        if lower.starts_with("::")
+            || [":before", ":after", ":first-letter", ":first-line"]
+                .iter()
+                .any(|p| lower.starts_with(p))
+        {
+            return Err("微信不支持伪元素".to_string());
+        }
+        if !STRUCTURAL_PSEUDO.iter().any(|p| lower.starts_with(p)) {
+            return Err(format!("{} 等交互状态无法内联", pseudo));
+        }
+    }
+
+    let tag_end = base.find(['.', '#']).unwrap_or(base.len());
+    let tag = base[..tag_end].to_ascii_lowercase();
+    let qualifiers = &base[tag_end..];
+    if qualifiers.starts_with('#') {
+        return Err(format!("未知的 id 选择器 {}", qualifiers));
+    }
+    let class = qualifiers.trim_start_matches('.');
+
+    let mut mapped = if class.is_empty() {
+        match tag.as_str() {
+            // mdnice wraps list item content in a section; doocs wraps the article in one
+            "section" if previous.is_none_or(|p| p == "li") => vec![],
+            "figure" | "figcaption" => return Err("WxTyper 不会把图片包裹在 figure 中".to_string()),
+            _ if KNOWN_TAGS.contains(&tag.as_str()) => vec![tag.clone()],
+            _ => return Err(format!("未知的元素 {}", tag)),
+        }
+    } else if class.contains(['.', '#']) {
+        return Err("不支持组合多个类名".to_string());
+    } else {
+        map_class(class, previous)?
+    };
+
+    if !pseudo.is_empty() {
+        match mapped.last_mut() {
+            Some(last) => last.push_str(pseudo),
+            None => return Err(format!("{} 没有可对应的元素", compound)),
+        }
+    }
+    Ok(mapped)
+}
+
+/// Translate a full selector, dropping the editor's root container
+fn map_selector(selector: &str) -> Result<String, String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut tokens = selector.split_whitespace().peekable();
+    if let Some(first) = tokens.peek() {
+        let id = first.find('#').map(|i| &first[i..]).unwrap_or_default();
+        if ROOT_SELECTORS.contains(first) || ROOT_SELECTORS.contains(&id) {
+            tokens.next();
+        }
+    }
+
+    for token in tokens {
+        if matches!(token, ">" | "+" | "~") {
+            parts.push(token.to_string());
+            continue;
+        }
+        let previous = parts
+            .iter()
+            .rev()
+            .find(|p| !matches!(p.as_str(), ">" | "+" | "~"))
+            .map(|p| p.split(':').next().unwrap_or_default());
+        let mapped = map_compound(token, previous)?;
+        if mapped.is_empty() {
+            // A dropped compound takes its combinator with it
+            if parts
+                .last()
+                .is_some_and(|p| matches!(p.as_str(), ">" | "+" | "~"))
+            {
+                parts.pop();
+            }
+        }
+        parts.extend(mapped);
+    }
+
+    if parts.is_empty() {
+        Ok(".wechat-content".to_string())
+    } else {
+        Ok(format!(".wechat-content {}", parts.join(" ")))
+    }
+}
+
+/// Translate an mdnice or doocs/md theme into WxTyper's `.wechat-content`
+/// selector model. Declarations are kept as they are; rules that have no
+/// equivalent element are left out and listed with the reason.
+pub fn import_theme_css(css: &str) -> ImportedTheme {
+    let mut output = String::new();
+    let mut unmapped = Vec::new();
+
+    for rule in parse_rules(css) {
+        if rule.declarations.is_empty() {
+            continue;
+        }
+        if let Some(at_rule) = &rule.at_rule {
+            unmapped.extend(rule.selectors.iter().map(|selector| UnmappedRule {
+                selector: selector.clone(),
+                reason: format!("{} 中的规则无法内联到微信", at_rule),
+            }));
+            continue;
+        }
+
+        let mut selectors: Vec<String> = Vec::new();
+        for selector in &rule.selectors {
+            match map_selector(selector) {
+                Ok(mapped) if !selectors.contains(&mapped) => selectors.push(mapped),
+                Ok(_) => {}
+                Err(reason) => unmapped.push(UnmappedRule {
+                    selector: selector.clone(),
+                    reason,
+                }),
+            }
+        }
+        if selectors.is_empty() {
+            continue;
+        }
+        output.push_str(&selectors.join(",\n"));
+        output.push_str(" {\n");
+        for (property, value) in &rule.declarations {
+            output.push_str(&format!("  {}: {};\n", property, value));
+        }
+        output.push_str("}\n\n");
+    }
+
+    ImportedTheme {
+        css: output,
+        unmapped,
+    }
+}
+
+/// Translate an mdnice or doocs/md theme file chosen in a native dialog and
+/// install it as a theme package named after the file. `None` when the
+/// dialog is cancelled.
+#[tauri::command]
+pub async fn import_community_theme(app: AppHandle) -> Result<Option<ThemeImportResult>, String> {
+    let Some(path) = pick_theme_file(&app, "CSS", &["css"])? else {
+        return Ok(None);
+    };
+    let css = fs::read_to_string(&path).map_err(|e| format!("无法读取主题文件: {}", e))?;
+    let imported = import_theme_css(&css);
+    if imported.css.trim().is_empty() {
+        return Err("没有可以转换的样式规则".to_string());
+    }
+
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let manifest = ThemeManifest {
+        name,
+        description: Some("从 mdnice / doocs 主题导入".to_string()),
+        ..Default::default()
+    };
+    let css = format!(
+        "/* Imported from {} */\n\n{}",
+        path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        imported.css
+    );
+    let theme = install_stylesheet(&themes_dir(&app)?, &manifest, &css)?;
+    let _ = app.emit(THEMES_CHANGED_EVENT, ());
+    Ok(Some(ThemeImportResult {
+        theme,
+        unmapped: imported.unmapped,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maps_mdnice_selectors_and_lists_the_rest() {
+        let css = r#"
+#nice { font-size: 15px; }
+#nice h2 .content { color: #fff; background: #07c160; }
+#nice h2 .prefix, #nice h2:before { display: none; }
+#nice ul li section, #nice ol > li > section { margin: 4px 0; }
+#nice .multiquote-2 { border-left-color: #ccc; }
+#nice .footnote-item, #nice .footnotes-sep { font-size: 12px; }
+#nice p code, #nice li:first-child { color: red; }
+#nice a:hover { color: blue; }
+@media (max-width: 600px) { #nice p { font-size: 14px; } }
+.code__pre { padding: 0; }
+"#;
+        let imported = import_theme_css(css);
+
+        assert!(imported
+            .css
+            .contains(".wechat-content {\n  font-size: 15px;"));
+        assert!(imported
+            .css
+            .contains(".wechat-content h2 {\n  color: #fff;\n  background: #07c160;"));
+        assert!(imported
+            .css
+            .contains(".wechat-content ul li,\n.wechat-content ol > li {"));
+        assert!(imported
+            .css
+            .contains(".wechat-content blockquote blockquote {"));
+        assert!(imported
+            .css
+            .contains(".wechat-content .footnotes li,\n.wechat-content .footnotes-title {"));
+        assert!(imported
+            .css
+            .contains(".wechat-content p code,\n.wechat-content li:first-child {"));
+        assert!(imported
+            .css
+            .contains(".wechat-content pre {\n  padding: 0;"));
+        assert!(!imported.css.contains("#nice"));
+
+        let unmapped: Vec<&str> = imported
+            .unmapped
+            .iter()
+            .map(|u| u.selector.as_str())
+            .collect();
+        assert_eq!(
+            unmapped,
+            [
+                "#nice h2 .prefix",
+                "#nice h2:before",
+                "#nice a:hover",
+                "#nice p"
+            ]
+        );
+    }
+}
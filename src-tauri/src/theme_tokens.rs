@@ -0,0 +1,337 @@
+use std::borrow::Cow;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::color::{adjust_for_contrast, contrast_ratio, parse_color, Rgba};
+use crate::models::{
+    ArticleMeta, CodeStyle, ConvertOptions, ConvertWarning, HeadingStyle, QuoteStyle, ThemeInfo,
+    ThemeManifest, ThemeTokens,
+};
+use crate::themes::{install_stylesheet, themes_dir, THEMES_CHANGED_EVENT};
+
+/// Front matter keys that override a token, and the token field they set
+const FRONT_MATTER_KEYS: &[(&str, &str)] = &[
+    ("theme_primary_color", "primaryColor"),
+    ("theme_font_size", "fontSize"),
+    ("theme_line_height", "lineHeight"),
+    ("theme_heading_style", "headingStyle"),
+    ("theme_code_style", "codeStyle"),
+    ("theme_quote_style", "quoteStyle"),
+];
+
+const WHITE: Rgba = Rgba::rgb(255, 255, 255);
+
+/// `color` at `alpha` over white, for light tinted backgrounds
+fn tint(color: Rgba, alpha: f64) -> String {
+    Rgba { a: alpha, ..color }.over(WHITE).to_hex()
+}
+
+/// Black or white, whichever reads better on `background`
+fn text_on(background: Rgba) -> &'static str {
+    if contrast_ratio(WHITE, background) >= contrast_ratio(Rgba::rgb(0, 0, 0), background) {
+        "#ffffff"
+    } else {
+        "#000000"
+    }
+}
+
+fn heading_css(style: HeadingStyle, primary: Rgba, accent: &str) -> String {
+    let primary_hex = primary.to_hex();
+    match style {
+        HeadingStyle::Underline => format!(
+            ".wechat-content h2 {{\n  color: {accent};\n  border-bottom: 2px solid {primary_hex};\n  padding-bottom: 6px;\n}}\n"
+        ),
+        HeadingStyle::LeftBar => format!(
+            ".wechat-content h2 {{\n  color: {accent};\n  border-left: 4px solid {primary_hex};\n  padding-left: 10px;\n}}\n"
+        ),
+        // `display: table` shrinks the block to its text, which WeChat keeps
+        HeadingStyle::Filled => format!(
+            ".wechat-content h2 {{\n  display: table;\n  color: {};\n  background-color: {primary_hex};\n  padding: 4px 12px;\n  border-radius: 4px;\n}}\n",
+            text_on(primary)
+        ),
+        HeadingStyle::Centered => format!(
+            ".wechat-content h2 {{\n  color: {accent};\n  text-align: center;\n  border-bottom: 1px solid {};\n  padding-bottom: 8px;\n}}\n",
+            tint(primary, 0.4)
+        ),
+        HeadingStyle::Plain => format!(".wechat-content h2 {{\n  color: {accent};\n}}\n"),
+    }
+}
+
+fn code_css(style: CodeStyle, primary: Rgba, accent: &str) -> String {
+    let (pre, pre_code) = match style {
+        CodeStyle::Light => (
+            "  background-color: #f6f8fa;\n  border-radius: 6px;\n".to_string(),
+            "#24292e",
+        ),
+        CodeStyle::Dark => (
+            "  background-color: #282c34;\n  border-radius: 6px;\n".to_string(),
+            "#abb2bf",
+        ),
+        CodeStyle::Bordered => (
+            format!(
+                "  background-color: #ffffff;\n  border: 1px solid #e1e4e8;\n  border-left: 3px solid {};\n",
+                primary.to_hex()
+            ),
+            "#24292e",
+        ),
+    };
+    format!(
+        ".wechat-content code {{\n  color: {accent};\n  background-color: {};\n}}\n\n\
+         .wechat-content pre {{\n{pre}  padding: 16px;\n}}\n\n\
+         .wechat-content pre code {{\n  color: {pre_code};\n  background-color: transparent;\n}}\n",
+        tint(primary, 0.08)
+    )
+}
+
+fn quote_css(style: QuoteStyle, primary: Rgba) -> String {
+    match style {
+        QuoteStyle::Bar => format!(
+            ".wechat-content blockquote {{\n  color: #555555;\n  background-color: {};\n  border-left: 4px solid {};\n  border-radius: 0 4px 4px 0;\n}}\n",
+            tint(primary, 0.06),
+            primary.to_hex()
+        ),
+        QuoteStyle::Card => format!(
+            ".wechat-content blockquote {{\n  color: #555555;\n  background-color: #f7f7f7;\n  border: 1px solid {};\n  border-left: 1px solid {};\n  border-radius: 8px;\n}}\n",
+            tint(primary, 0.2),
+            tint(primary, 0.2)
+        ),
+        QuoteStyle::Italic => ".wechat-content blockquote {\n  color: #666666;\n  font-style: italic;\n  background-color: transparent;\n  border-left: 2px solid #dddddd;\n}\n".to_string(),
+    }
+}
+
+/// The opaque primary colour of `tokens`, after checking every token is in range
+fn validate_tokens(tokens: &ThemeTokens) -> Result<Rgba, String> {
+    let primary = parse_color(&tokens.primary_color)
+        .filter(|c| c.a >= 1.0)
+        .ok_or_else(|| format!("无法识别的主题色: {}", tokens.primary_color))?;
+    if !(12..=24).contains(&tokens.font_size) {
+        return Err(format!(
+            "正文字号需在 12 到 24px 之间: {}",
+            tokens.font_size
+        ));
+    }
+    if !(1.2..=2.5).contains(&tokens.line_height) {
+        return Err(format!("行高需在 1.2 到 2.5 之间: {}", tokens.line_height));
+    }
+    Ok(primary)
+}
+
+/// Build a complete theme for the fallback stylesheet's selectors from tokens
+pub fn generate_theme_css(tokens: &ThemeTokens) -> Result<String, String> {
+    let primary = validate_tokens(tokens)?;
+
+    // The primary colour darkened as far as needed to read as text on white
+    let accent = adjust_for_contrast(primary, WHITE, 4.5)
+        .unwrap_or(primary)
+        .to_hex();
+    let size = tokens.font_size as f64;
+    let px = |scale: f64| format!("{}px", (size * scale).round());
+
+    let mut css = format!(
+        "/* Generated from theme tokens: {} {}px / {} heading={:?} code={:?} quote={:?} */\n\n",
+        primary.to_hex(),
+        tokens.font_size,
+        tokens.line_height,
+        tokens.heading_style,
+        tokens.code_style,
+        tokens.quote_style
+    );
+    css.push_str(&format!(
+        ".wechat-content {{\n  font-size: {};\n  line-height: {};\n  color: #333333;\n}}\n\n",
+        px(1.0),
+        tokens.line_height
+    ));
+    css.push_str(&format!(
+        ".wechat-content h1 {{\n  font-size: {};\n  color: {accent};\n  text-align: center;\n  border-bottom: none;\n}}\n\n",
+        px(1.5)
+    ));
+    css.push_str(&format!(
+        ".wechat-content h2 {{\n  font-size: {};\n}}\n\n",
+        px(1.3)
+    ));
+    css.push_str(&heading_css(tokens.heading_style, primary, &accent));
+    css.push_str(&format!(
+        "\n.wechat-content h3 {{\n  font-size: {};\n  color: {accent};\n}}\n\n",
+        px(1.15)
+    ));
+    css.push_str(&format!(
+        ".wechat-content p {{\n  margin-bottom: {};\n}}\n\n",
+        px(1.0)
+    ));
+    css.push_str(&quote_css(tokens.quote_style, primary));
+    css.push('\n');
+    css.push_str(&code_css(tokens.code_style, primary, &accent));
+    css.push_str(&format!(
+        "\n.wechat-content a {{\n  color: {accent};\n}}\n\n\
+         .wechat-content th {{\n  background-color: {};\n}}\n\n\
+         .wechat-content .footnote-ref,\n.wechat-content .toc-number,\n.wechat-content .admonition-title {{\n  color: {accent};\n}}\n\n\
+         .wechat-content .admonition {{\n  border-left-color: {};\n  background-color: {};\n}}\n",
+        tint(primary, 0.1),
+        primary.to_hex(),
+        tint(primary, 0.06)
+    ));
+    Ok(css)
+}
+
+/// The selected theme's tokens with the article's `theme_*` front matter keys
+/// applied on top. `None` when the theme was not generated from tokens, in
+/// which case the keys are ignored. An invalid key is reported and the
+/// theme's own value kept.
+pub fn resolve_tokens(
+    options: &ConvertOptions,
+    meta: Option<&ArticleMeta>,
+    warnings: &mut Vec<ConvertWarning>,
+) -> Result<Option<ThemeTokens>, String> {
+    let overrides: Vec<(&str, &str, &serde_json::Value)> = FRONT_MATTER_KEYS
+        .iter()
+        .filter_map(|(key, field)| Some((*key, *field, meta?.extra.get(*key)?)))
+        .collect();
+    let Some(mut tokens) = options.theme_tokens.clone() else {
+        if let Some((key, _, _)) = overrides.first() {
+            warnings.push(ConvertWarning {
+                kind: "theme-override-ignored".to_string(),
+                message: format!(
+                    "当前主题不是由主题生成器创建的，Front Matter 中的 {} 等主题参数已忽略",
+                    key
+                ),
+                line: 0,
+            });
+        }
+        return Ok(None);
+    };
+
+    for (key, field, override_value) in overrides {
+        let mut value = serde_json::to_value(&tokens).map_err(|e| e.to_string())?;
+        if let Some(object) = value.as_object_mut() {
+            object.insert(field.to_string(), override_value.clone());
+        }
+        let applied = serde_json::from_value::<ThemeTokens>(value)
+            .map_err(|e| e.to_string())
+            .and_then(|candidate| validate_tokens(&candidate).map(|_| candidate));
+        match applied {
+            Ok(candidate) => tokens = candidate,
+            Err(e) => warnings.push(ConvertWarning {
+                kind: "theme-override-invalid".to_string(),
+                message: format!(
+                    "Front Matter 中的 {} 无效（{}），已使用主题自身的设置",
+                    key, e
+                ),
+                line: 0,
+            }),
+        }
+    }
+    Ok(Some(tokens))
+}
+
+/// The CSS to inline: generated from the theme's tokens and the article's
+/// overrides when the theme has tokens, otherwise the theme CSS as given
+pub fn theme_css_for<'a>(
+    css: &'a str,
+    options: &ConvertOptions,
+    meta: Option<&ArticleMeta>,
+    warnings: &mut Vec<ConvertWarning>,
+) -> Result<Cow<'a, str>, String> {
+    match resolve_tokens(options, meta, warnings)? {
+        Some(tokens) => Ok(Cow::Owned(generate_theme_css(&tokens)?)),
+        None => Ok(Cow::Borrowed(css)),
+    }
+}
+
+/// Generate theme CSS from design tokens
+#[tauri::command]
+pub fn generate_theme(tokens: ThemeTokens) -> Result<String, String> {
+    generate_theme_css(&tokens)
+}
+
+/// Generate a theme from design tokens and install it under `name`
+#[tauri::command]
+pub fn save_generated_theme(
+    app: AppHandle,
+    name: String,
+    tokens: ThemeTokens,
+) -> Result<ThemeInfo, String> {
+    let css = generate_theme_css(&tokens)?;
+    let manifest = ThemeManifest {
+        name,
+        description: Some("由主题生成器创建".to_string()),
+        highlight: Some(
+            match tokens.code_style {
+                CodeStyle::Dark => "ocean-dark",
+                CodeStyle::Light | CodeStyle::Bordered => "github",
+            }
+            .to_string(),
+        ),
+        tokens: Some(tokens),
+        ..Default::default()
+    };
+    let theme = install_stylesheet(&themes_dir(&app)?, &manifest, &css)?;
+    let _ = app.emit(THEMES_CHANGED_EVENT, ());
+    Ok(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_overrides_tokens() {
+        let mut meta = ArticleMeta::default();
+        meta.extra
+            .insert("theme_primary_color".into(), "#e91e63".into());
+        meta.extra
+            .insert("theme_heading_style".into(), "filled".into());
+        let options = ConvertOptions {
+            theme_tokens: Some(ThemeTokens::default()),
+            ..Default::default()
+        };
+
+        let mut warnings = Vec::new();
+        let tokens = resolve_tokens(&options, Some(&meta), &mut warnings)
+            .unwrap()
+            .unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(tokens.primary_color, "#e91e63");
+        assert_eq!(tokens.heading_style, HeadingStyle::Filled);
+        assert_eq!(tokens.font_size, 16);
+
+        let css = generate_theme_css(&tokens).unwrap();
+        assert!(css.contains("background-color: #e91e63;"));
+        assert!(css.contains("display: table;"));
+        for selector in [
+            ".wechat-content h2",
+            ".wechat-content blockquote",
+            ".wechat-content pre code",
+        ] {
+            assert!(css.contains(selector), "{}", selector);
+        }
+
+        // Invalid values are reported and the theme's own value kept
+        meta.extra.insert("theme_code_style".into(), "neon".into());
+        meta.extra.insert("theme_font_size".into(), 40.into());
+        let tokens = resolve_tokens(&options, Some(&meta), &mut warnings)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tokens.code_style, ThemeTokens::default().code_style);
+        assert_eq!(tokens.font_size, 16);
+        assert_eq!(tokens.primary_color, "#e91e63");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().all(|w| w.kind == "theme-override-invalid"));
+
+        // A theme not built from tokens keeps its own CSS
+        let mut warnings = Vec::new();
+        let css = theme_css_for(
+            "p { color: red; }",
+            &ConvertOptions::default(),
+            Some(&meta),
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(css, "p { color: red; }");
+        assert_eq!(warnings[0].kind, "theme-override-ignored");
+        assert!(
+            resolve_tokens(&ConvertOptions::default(), None, &mut warnings)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
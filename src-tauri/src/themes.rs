@@ -0,0 +1,566 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::models::{ThemeInfo, ThemeManifest};
+
+/// Event emitted whenever the installed themes change on disk
+pub const THEMES_CHANGED_EVENT: &str = "themes-changed";
+/// Manifest file at the root of every theme package
+const MANIFEST_FILE: &str = "theme.json";
+/// Stylesheet name used when the manifest does not name one
+const DEFAULT_CSS_FILE: &str = "theme.css";
+/// Longest chain of base themes followed when layering CSS
+const MAX_BASE_DEPTH: usize = 8;
+
+struct BuiltinTheme {
+    id: &'static str,
+    name: &'static str,
+    highlight: &'static str,
+    css: &'static str,
+}
+
+/// Themes shipped with the app; installed packages can use them as a base
+const BUILTIN_THEMES: &[BuiltinTheme] = &[
+    BuiltinTheme {
+        id: "default",
+        name: "Default (Green)",
+        highlight: "github",
+        css: include_str!("../../src/themes/default.css"),
+    },
+    BuiltinTheme {
+        id: "lapis",
+        name: "Lapis (Blue)",
+        highlight: "ocean-dark",
+        css: include_str!("../../src/themes/lapis.css"),
+    },
+    BuiltinTheme {
+        id: "sakura",
+        name: "Sakura (Pink)",
+        highlight: "solarized-light",
+        css: include_str!("../../src/themes/sakura.css"),
+    },
+    BuiltinTheme {
+        id: "tech",
+        name: "Tech (Dark)",
+        highlight: "ocean-dark",
+        css: include_str!("../../src/themes/tech.css"),
+    },
+];
+
+/// Keeps the themes directory watcher alive for the lifetime of the app
+pub struct ThemeWatcher {
+    _watcher: Mutex<notify::RecommendedWatcher>,
+}
+
+/// Directory name for a theme: letters and digits of its name, joined by hyphens
+fn theme_id(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() {
+            id.extend(c.to_lowercase());
+        } else if !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    id.trim_matches('-').to_string()
+}
+
+pub(crate) fn themes_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("无法获取应用数据目录: {}", e))?
+        .join("themes");
+    fs::create_dir_all(&dir).map_err(|e| format!("无法创建主题目录: {}", e))?;
+    Ok(dir)
+}
+
+fn parse_manifest(text: &str) -> Result<ThemeManifest, String> {
+    let manifest: ThemeManifest =
+        serde_json::from_str(text).map_err(|e| format!("主题清单格式错误: {}", e))?;
+    if theme_id(&manifest.name).is_empty() {
+        return Err("主题清单缺少名称".to_string());
+    }
+    Ok(manifest)
+}
+
+/// CSS file name from the manifest, rejecting paths that leave the package
+fn css_file_name(manifest: &ThemeManifest) -> Result<String, String> {
+    let name = manifest
+        .css
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CSS_FILE.to_string());
+    let path = Path::new(&name);
+    if path.is_absolute()
+        || path
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(format!("主题样式文件路径无效: {}", name));
+    }
+    Ok(name)
+}
+
+/// A theme before its base themes are layered in
+struct LoadedTheme {
+    id: String,
+    manifest: ThemeManifest,
+    css: String,
+    builtin: bool,
+    path: Option<PathBuf>,
+}
+
+fn load_package(dir: &Path) -> Result<LoadedTheme, String> {
+    let manifest_text = fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+    let manifest = parse_manifest(&manifest_text)?;
+    let css = fs::read_to_string(dir.join(css_file_name(&manifest)?))
+        .map_err(|e| format!("无法读取主题样式: {}", e))?;
+    let id = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| theme_id(&manifest.name));
+    Ok(LoadedTheme {
+        id,
+        manifest,
+        css,
+        builtin: false,
+        path: Some(dir.to_path_buf()),
+    })
+}
+
+fn load_all(themes_dir: &Path) -> Vec<LoadedTheme> {
+    let mut themes: Vec<LoadedTheme> = BUILTIN_THEMES
+        .iter()
+        .map(|theme| LoadedTheme {
+            id: theme.id.to_string(),
+            manifest: ThemeManifest {
+                name: theme.name.to_string(),
+                author: Some("WxTyper".to_string()),
+                highlight: Some(theme.highlight.to_string()),
+                ..Default::default()
+            },
+            css: theme.css.to_string(),
+            builtin: true,
+            path: None,
+        })
+        .collect();
+
+    let mut installed: Vec<LoadedTheme> = fs::read_dir(themes_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        // A broken package should not hide the others
+        .filter_map(|path| load_package(&path).ok())
+        .collect();
+    installed.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    themes.extend(installed);
+    themes
+}
+
+/// Concatenate the CSS of a theme's base chain, base first, so the theme's
+/// own rules come last and win
+fn layered_css(theme: &LoadedTheme, all: &[LoadedTheme]) -> String {
+    let mut chain = vec![theme];
+    let mut current = theme;
+    while let Some(base) = current.manifest.base.as_deref() {
+        let base = base.trim();
+        let Some(next) = all.iter().find(|t| {
+            t.id.eq_ignore_ascii_case(base) || t.manifest.name.eq_ignore_ascii_case(base)
+        }) else {
+            break;
+        };
+        if chain.iter().any(|t| t.id == next.id) || chain.len() > MAX_BASE_DEPTH {
+            break;
+        }
+        chain.push(next);
+        current = next;
+    }
+    chain
+        .iter()
+        .rev()
+        .map(|t| t.css.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn theme_infos(themes_dir: &Path) -> Vec<ThemeInfo> {
+    let themes = load_all(themes_dir);
+    themes
+        .iter()
+        .map(|theme| ThemeInfo {
+            id: theme.id.clone(),
+            name: theme.manifest.name.clone(),
+            author: theme.manifest.author.clone(),
+            version: theme.manifest.version.clone(),
+            description: theme.manifest.description.clone(),
+            base: theme.manifest.base.clone(),
+            highlight: theme.manifest.highlight.clone(),
+            builtin: theme.builtin,
+            tokens: theme.manifest.tokens.clone(),
+            css: layered_css(theme, &themes),
+            path: theme.path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        })
+        .collect()
+}
+
+/// Build the package for `manifest` in a staging folder and swap it in only
+/// once `fill` succeeds, so a broken package never replaces a working theme
+fn install_package(
+    themes_dir: &Path,
+    manifest: &ThemeManifest,
+    fill: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<PathBuf, String> {
+    let id = theme_id(&manifest.name);
+    if BUILTIN_THEMES.iter().any(|t| t.id == id) {
+        return Err(format!("不能覆盖内置主题: {}", manifest.name));
+    }
+    // Dot-prefixed folders are skipped by `load_all`
+    let staging = themes_dir.join(format!(".{}.installing", id));
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).map_err(|e| e.to_string())?;
+    let dir = themes_dir.join(&id);
+    let result = fill(&staging).and_then(|()| {
+        replace_dir(&staging, &dir, &themes_dir.join(format!(".{}.old", id)))
+            .map_err(|e| format!("无法替换已安装的主题: {}", e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result.map(|()| dir)
+}
+
+/// Move `staging` to `dir`, putting the previous `dir` back if that fails
+fn replace_dir(staging: &Path, dir: &Path, old: &Path) -> std::io::Result<()> {
+    if !dir.exists() {
+        return fs::rename(staging, dir);
+    }
+    let _ = fs::remove_dir_all(old);
+    fs::rename(dir, old)?;
+    if let Err(e) = fs::rename(staging, dir) {
+        let _ = fs::rename(old, dir);
+        return Err(e);
+    }
+    let _ = fs::remove_dir_all(old);
+    Ok(())
+}
+
+fn install_css_file(themes_dir: &Path, path: &Path) -> Result<PathBuf, String> {
+    let css = fs::read_to_string(path).map_err(|e| format!("无法读取主题文件: {}", e))?;
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let manifest = ThemeManifest {
+        name,
+        ..Default::default()
+    };
+    if theme_id(&manifest.name).is_empty() {
+        return Err("无法从文件名确定主题名称".to_string());
+    }
+    write_stylesheet_package(themes_dir, &manifest, &css)
+}
+
+/// Write a package holding a generated manifest and a single stylesheet
+fn write_stylesheet_package(
+    themes_dir: &Path,
+    manifest: &ThemeManifest,
+    css: &str,
+) -> Result<PathBuf, String> {
+    let manifest_json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    install_package(themes_dir, manifest, |dir| {
+        fs::write(dir.join(MANIFEST_FILE), manifest_json).map_err(|e| e.to_string())?;
+        fs::write(dir.join(DEFAULT_CSS_FILE), css).map_err(|e| e.to_string())
+    })
+}
+
+/// Install CSS produced by the app itself, such as an imported or generated theme
+pub fn install_stylesheet(
+    themes_dir: &Path,
+    manifest: &ThemeManifest,
+    css: &str,
+) -> Result<ThemeInfo, String> {
+    if theme_id(&manifest.name).is_empty() {
+        return Err("主题名称不能为空".to_string());
+    }
+    let dir = write_stylesheet_package(themes_dir, manifest, css)?;
+    installed_info(themes_dir, &dir)
+}
+
+fn installed_info(themes_dir: &Path, dir: &Path) -> Result<ThemeInfo, String> {
+    let id = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    theme_infos(themes_dir)
+        .into_iter()
+        .find(|theme| theme.id == id)
+        .ok_or_else(|| "主题安装后无法加载".to_string())
+}
+
+fn install_manifest_file(themes_dir: &Path, path: &Path) -> Result<PathBuf, String> {
+    let source_dir = path.parent().unwrap_or(Path::new("."));
+    let manifest_text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let manifest = parse_manifest(&manifest_text)?;
+    let css_name = css_file_name(&manifest)?;
+    let css = fs::read_to_string(source_dir.join(&css_name))
+        .map_err(|e| format!("无法读取主题样式 {}: {}", css_name, e))?;
+
+    install_package(themes_dir, &manifest, |dir| {
+        fs::write(dir.join(MANIFEST_FILE), manifest_text).map_err(|e| e.to_string())?;
+        fs::write(dir.join(&css_name), css).map_err(|e| e.to_string())
+    })
+}
+
+/// Install a zipped package. The manifest may sit at the archive root or in a
+/// single top-level folder; only entries next to or below it are extracted.
+fn install_zip(themes_dir: &Path, path: &Path) -> Result<PathBuf, String> {
+    let file = fs::File::open(path).map_err(|e| format!("无法打开主题包: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("主题包格式错误: {}", e))?;
+
+    let mut manifest_entry: Option<(usize, PathBuf)> = None;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        if name.file_name().is_some_and(|n| n == MANIFEST_FILE)
+            && name.components().count() <= 2
+            && manifest_entry
+                .as_ref()
+                .is_none_or(|(_, found)| name.components().count() < found.components().count())
+        {
+            manifest_entry = Some((i, name));
+        }
+    }
+    let (manifest_index, manifest_path) =
+        manifest_entry.ok_or_else(|| format!("主题包中缺少 {}", MANIFEST_FILE))?;
+    let prefix = manifest_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+
+    let mut manifest_text = String::new();
+    archive
+        .by_index(manifest_index)
+        .map_err(|e| e.to_string())?
+        .read_to_string(&mut manifest_text)
+        .map_err(|e| format!("无法读取主题清单: {}", e))?;
+    let manifest = parse_manifest(&manifest_text)?;
+    let css_name = css_file_name(&manifest)?;
+
+    install_package(themes_dir, &manifest, |dir| {
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            // enclosed_name rejects absolute paths and `..`, so nothing escapes `dir`
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            let Ok(relative) = name.strip_prefix(&prefix) else {
+                continue;
+            };
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+            let target = dir.join(relative);
+            if entry.is_dir() {
+                fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let mut out = fs::File::create(&target).map_err(|e| e.to_string())?;
+            std::io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+        }
+
+        if !dir.join(&css_name).is_file() {
+            return Err(format!("主题包中缺少样式文件 {}", css_name));
+        }
+        Ok(())
+    })
+}
+
+/// Install a theme from a `.zip` package, a `theme.json` manifest or a bare `.css` file
+pub fn install_theme_from(themes_dir: &Path, path: &Path) -> Result<ThemeInfo, String> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let dir = match extension.as_str() {
+        "zip" => install_zip(themes_dir, path)?,
+        "css" => install_css_file(themes_dir, path)?,
+        "json" => install_manifest_file(themes_dir, path)?,
+        _ => return Err("仅支持 .zip 主题包、theme.json 或 .css 文件".to_string()),
+    };
+    installed_info(themes_dir, &dir)
+}
+
+/// Remove an installed theme package; built-in themes cannot be removed
+pub fn remove_theme_from(themes_dir: &Path, id: &str) -> Result<(), String> {
+    if BUILTIN_THEMES.iter().any(|t| t.id == id) {
+        return Err("内置主题不能删除".to_string());
+    }
+    // Ids are generated by theme_id, so anything else could point outside the folder
+    if id.is_empty() || theme_id(id) != id {
+        return Err(format!("无效的主题 ID: {}", id));
+    }
+    let dir = themes_dir.join(id);
+    if !dir.join(MANIFEST_FILE).is_file() {
+        return Err(format!("未找到主题: {}", id));
+    }
+    fs::remove_dir_all(&dir).map_err(|e| format!("删除主题失败: {}", e))
+}
+
+/// Watch the themes directory and emit `themes-changed` when packages are
+/// added, edited or removed, so the frontend can reload them
+pub fn watch_themes(app: &AppHandle) -> Result<(), String> {
+    let dir = themes_dir(app)?;
+    let handle = app.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                let _ = handle.emit(THEMES_CHANGED_EVENT, ());
+            }
+        }
+    })
+    .map_err(|e| format!("无法监听主题目录: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("无法监听主题目录: {}", e))?;
+    app.manage(ThemeWatcher {
+        _watcher: Mutex::new(watcher),
+    });
+    Ok(())
+}
+
+/// List built-in and installed themes, with each theme's CSS layered over its base
+#[tauri::command]
+pub fn list_themes(app: AppHandle) -> Result<Vec<ThemeInfo>, String> {
+    Ok(theme_infos(&themes_dir(&app)?))
+}
+
+/// Rescan the themes directory and notify the frontend
+#[tauri::command]
+pub fn reload_themes(app: AppHandle) -> Result<Vec<ThemeInfo>, String> {
+    let themes = theme_infos(&themes_dir(&app)?);
+    let _ = app.emit(THEMES_CHANGED_EVENT, ());
+    Ok(themes)
+}
+
+/// Let the user pick a theme file in a native dialog. The dialog runs here
+/// rather than in the webview, so a script cannot name an arbitrary file.
+pub(crate) fn pick_theme_file(
+    app: &AppHandle,
+    filter: &str,
+    extensions: &[&str],
+) -> Result<Option<PathBuf>, String> {
+    let Some(file) = app
+        .dialog()
+        .file()
+        .add_filter(filter, extensions)
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+    file.into_path().map(Some).map_err(|e| e.to_string())
+}
+
+/// Install a theme package from a `.zip`, `theme.json` or `.css` file chosen
+/// in a native dialog. `None` when the dialog is cancelled.
+#[tauri::command]
+pub async fn install_theme(app: AppHandle) -> Result<Option<ThemeInfo>, String> {
+    let Some(path) = pick_theme_file(&app, "主题包", &["zip", "css", "json"])? else {
+        return Ok(None);
+    };
+    let theme = install_theme_from(&themes_dir(&app)?, &path)?;
+    let _ = app.emit(THEMES_CHANGED_EVENT, ());
+    Ok(Some(theme))
+}
+
+/// Remove an installed theme package
+#[tauri::command]
+pub fn remove_theme(app: AppHandle, id: String) -> Result<(), String> {
+    remove_theme_from(&themes_dir(&app)?, &id)?;
+    let _ = app.emit(THEMES_CHANGED_EVENT, ());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_install_zip_layers_base_and_remove() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let themes = root.join("themes");
+        fs::create_dir_all(&themes).unwrap();
+
+        let zip_path = root.join("house.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("house/theme.json", options).unwrap();
+        writer
+            .write_all(br#"{"name": "House Style", "author": "Team", "version": "1.0.0", "base": "Tech (Dark)", "highlight": "mocha-dark"}"#)
+            .unwrap();
+        writer.start_file("house/theme.css", options).unwrap();
+        writer.write_all(b"h1 { color: red; }").unwrap();
+        writer.start_file("../evil.css", options).unwrap();
+        writer.write_all(b"x").unwrap();
+        writer.finish().unwrap();
+
+        let theme = install_theme_from(&themes, &zip_path).unwrap();
+        assert_eq!(theme.id, "house-style");
+        assert_eq!(theme.highlight.as_deref(), Some("mocha-dark"));
+        assert!(theme.css.starts_with("/* Tech Theme"));
+        assert!(theme.css.ends_with("h1 { color: red; }"));
+        assert!(!root.join("evil.css").exists());
+
+        // A broken package keeps the installed version
+        let broken_path = root.join("broken.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&broken_path).unwrap());
+        writer.start_file("theme.json", options).unwrap();
+        writer.write_all(br#"{"name": "House Style"}"#).unwrap();
+        writer.finish().unwrap();
+        assert!(install_theme_from(&themes, &broken_path).is_err());
+        let kept = theme_infos(&themes);
+        assert_eq!(kept.iter().filter(|t| !t.builtin).count(), 1);
+        assert!(kept
+            .iter()
+            .any(|t| t.id == "house-style" && t.css.ends_with("color: red; }")));
+        assert_eq!(fs::read_dir(&themes).unwrap().count(), 1);
+        // and a good one replaces it
+        install_theme_from(&themes, &zip_path).unwrap();
+        assert_eq!(fs::read_dir(&themes).unwrap().count(), 1);
+
+        assert!(remove_theme_from(&themes, "tech").is_err());
+        assert!(remove_theme_from(&themes, "../house-style").is_err());
+        remove_theme_from(&themes, "house-style").unwrap();
+        assert!(theme_infos(&themes).iter().all(|t| t.builtin));
+    }
+
+    #[test]
+    fn test_install_plain_css_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let css_path = root.join("My Theme.css");
+        fs::write(&css_path, "p { color: blue; }").unwrap();
+
+        let theme = install_theme_from(&root.join("themes"), &css_path).unwrap();
+        assert_eq!(theme.id, "my-theme");
+        assert_eq!(theme.name, "My Theme");
+        assert_eq!(theme.css, "p { color: blue; }");
+    }
+}
@@ -0,0 +1,260 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::html::escape_html;
+use crate::models::{ArticleMeta, ConvertOptions};
+use crate::pipeline::Transform;
+
+/// Default title shown above the contents list
+pub const DEFAULT_TOC_TITLE: &str = "目录";
+/// Headings deeper than this are left out of the contents by default
+const DEFAULT_TOC_DEPTH: u8 = 3;
+
+/// Builds a table of contents from the document headings. The list is plain
+/// styled text, since WeChat strips in-page anchors and links would not work.
+pub struct TocTransform {
+    /// Insert the contents at the top even without a `[TOC]` marker
+    pub auto_insert: bool,
+    pub numbered: bool,
+    pub depth: u8,
+    pub title: Option<String>,
+}
+
+impl TocTransform {
+    /// Read TOC settings from the conversion options, letting front matter
+    /// (`toc`, `toc_numbered`, `toc_depth`, `toc_title`) override them per article
+    pub fn from_options(options: &ConvertOptions, meta: Option<&ArticleMeta>) -> Self {
+        let mut toc = TocTransform {
+            auto_insert: false,
+            numbered: options.toc_numbered.unwrap_or(false),
+            depth: options.toc_depth.unwrap_or(DEFAULT_TOC_DEPTH),
+            title: Some(
+                options
+                    .toc_title
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_TOC_TITLE.to_string()),
+            ),
+        };
+        let Some(extra) = meta.map(|meta| &meta.extra) else {
+            return toc;
+        };
+
+        match extra.get("toc") {
+            Some(serde_json::Value::Bool(enabled)) => toc.auto_insert = *enabled,
+            Some(serde_json::Value::String(mode)) if mode.eq_ignore_ascii_case("numbered") => {
+                toc.auto_insert = true;
+                toc.numbered = true;
+            }
+            _ => {}
+        }
+        if let Some(numbered) = extra.get("toc_numbered").and_then(|v| v.as_bool()) {
+            toc.numbered = numbered;
+        }
+        if let Some(depth) = extra.get("toc_depth").and_then(|v| v.as_u64()) {
+            toc.depth = depth.clamp(1, 6) as u8;
+        }
+        if let Some(title) = extra.get("toc_title").and_then(|v| v.as_str()) {
+            toc.title = Some(title.to_string());
+        }
+        toc
+    }
+}
+
+/// Collect `(level, text)` for every heading in the event stream
+fn collect_headings(events: &[Event]) -> Vec<(u8, String)> {
+    let mut headings = Vec::new();
+    let mut current: Option<(u8, String)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((*level as u8, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, buffer)) = current.as_mut() {
+                    buffer.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text)) = current.take() {
+                    let text = text.trim().to_string();
+                    if !text.is_empty() {
+                        headings.push((level, text));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// Index of the paragraph end if `events[start]` opens a paragraph holding only `[TOC]`
+fn toc_marker_end(events: &[Event], start: usize) -> Option<usize> {
+    if !matches!(events.get(start), Some(Event::Start(Tag::Paragraph))) {
+        return None;
+    }
+    let mut text = String::new();
+    for (offset, event) in events[start + 1..].iter().enumerate() {
+        match event {
+            Event::Text(t) => text.push_str(t),
+            Event::End(TagEnd::Paragraph) => {
+                return text
+                    .trim()
+                    .eq_ignore_ascii_case("[toc]")
+                    .then_some(start + 1 + offset);
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Render headings as nested lists. Levels are made relative to the shallowest
+/// heading and never skip more than one step, so `#` then `###` still nests once.
+pub fn render_toc(headings: &[(u8, String)], numbered: bool, title: Option<&str>) -> String {
+    let mut html = String::from("<section class=\"toc\">\n");
+    if let Some(title) = title.map(str::trim).filter(|t| !t.is_empty()) {
+        html.push_str(&format!(
+            "<p class=\"toc-title\">{}</p>\n",
+            escape_html(title)
+        ));
+    }
+
+    let min_level = headings.iter().map(|(level, _)| *level).min().unwrap_or(1);
+    let mut counters: Vec<usize> = Vec::new();
+    for (level, text) in headings {
+        let depth = ((level - min_level) as usize).min(counters.len());
+        while counters.len() > depth + 1 {
+            counters.pop();
+            html.push_str("</li>\n</ul>\n");
+        }
+        if counters.len() == depth + 1 {
+            html.push_str("</li>\n");
+            counters[depth] += 1;
+        } else {
+            html.push_str(&format!(
+                "<ul class=\"toc-list toc-level-{}\">\n",
+                depth + 1
+            ));
+            counters.push(1);
+        }
+
+        html.push_str("<li class=\"toc-item\">");
+        if numbered {
+            let number = counters
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(".");
+            html.push_str(&format!("<span class=\"toc-number\">{}</span> ", number));
+        }
+        html.push_str(&format!(
+            "<span class=\"toc-text\">{}</span>",
+            escape_html(text)
+        ));
+    }
+    for _ in 0..counters.len() {
+        html.push_str("</li>\n</ul>\n");
+    }
+
+    html.push_str("</section>\n");
+    html
+}
+
+impl Transform for TocTransform {
+    fn name(&self) -> &'static str {
+        "toc"
+    }
+
+    fn apply<'a>(&self, events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+        let headings: Vec<(u8, String)> = collect_headings(&events)
+            .into_iter()
+            .filter(|(level, _)| *level <= self.depth)
+            .collect();
+        let toc = || {
+            if headings.is_empty() {
+                Event::Html(CowStr::from(""))
+            } else {
+                Event::Html(CowStr::from(render_toc(
+                    &headings,
+                    self.numbered,
+                    self.title.as_deref(),
+                )))
+            }
+        };
+
+        let mut output = Vec::with_capacity(events.len() + 1);
+        let mut found = false;
+        let mut skip_until = None;
+        for (index, event) in events.iter().enumerate() {
+            if let Some(end) = skip_until {
+                if index <= end {
+                    continue;
+                }
+                skip_until = None;
+            }
+            if let Some(end) = toc_marker_end(&events, index) {
+                output.push(toc());
+                found = true;
+                skip_until = Some(end);
+                continue;
+            }
+            output.push(event.clone());
+        }
+
+        if !found && self.auto_insert && !headings.is_empty() {
+            output.insert(0, toc());
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Parser};
+
+    fn render(markdown: &str, toc: &TocTransform) -> String {
+        let events = toc.apply(Parser::new(markdown).collect());
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+        output
+    }
+
+    #[test]
+    fn test_marker_is_replaced_with_numbered_nested_list() {
+        let toc = TocTransform {
+            auto_insert: false,
+            numbered: true,
+            depth: 3,
+            title: Some(DEFAULT_TOC_TITLE.to_string()),
+        };
+        let html = render(
+            "[TOC]\n\n## 安装\n\n### 依赖\n\n#### 深层\n\n## 使用\n",
+            &toc,
+        );
+
+        assert!(!html.contains("[TOC]"));
+        assert!(html.contains("<p class=\"toc-title\">目录</p>"));
+        assert!(html.contains(
+            "<span class=\"toc-number\">1.1</span> <span class=\"toc-text\">依赖</span>"
+        ));
+        assert!(html
+            .contains("<span class=\"toc-number\">2</span> <span class=\"toc-text\">使用</span>"));
+        assert!(!html.contains("<span class=\"toc-text\">深层</span>"));
+        assert_eq!(html.matches("<ul").count(), html.matches("</ul>").count());
+        assert!(!html.contains("<a "));
+    }
+
+    #[test]
+    fn test_front_matter_switch_inserts_at_top() {
+        let mut meta = ArticleMeta::default();
+        meta.extra
+            .insert("toc".to_string(), serde_json::Value::Bool(true));
+        let toc = TocTransform::from_options(&ConvertOptions::default(), Some(&meta));
+        let html = render("Intro\n\n# One\n", &toc);
+        assert!(html.starts_with("<section class=\"toc\">"));
+
+        let toc = TocTransform::from_options(&ConvertOptions::default(), None);
+        assert!(!render("Intro\n\n# One\n", &toc).contains("class=\"toc\""));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::models::FileChangeEvent;
+use crate::workspace::Workspace;
+
+/// Emitted when the open file is modified, renamed or deleted by another program
+pub const FILE_CHANGED_EVENT: &str = "file-changed";
+/// Emitted when anything inside the open file's assets directory changes
+pub const ASSETS_CHANGED_EVENT: &str = "assets-changed";
+/// Start of the error `save_markdown_file` returns on a conflict; the
+/// frontend matches on it to offer overwriting
+pub const CONFLICT_ERROR: &str = "文件已在外部修改";
+
+fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// What the app last read from or wrote to each file, and the watcher for the
+/// file being edited
+#[derive(Default)]
+pub struct FileWatch {
+    versions: Arc<Mutex<HashMap<PathBuf, u64>>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl FileWatch {
+    /// Remember `bytes` as the version of `path` the editor holds
+    pub fn record(&self, path: &Path, bytes: &[u8]) {
+        if let Ok(mut versions) = self.versions.lock() {
+            versions.insert(path.to_path_buf(), content_hash(bytes));
+        }
+    }
+
+    /// Run `write`, which puts `bytes` into `path`, with `bytes` recorded
+    /// beforehand so the watcher does not report the app's own write as an
+    /// external change. The previous version is kept if the write fails.
+    pub fn write_recorded(
+        &self,
+        path: &Path,
+        bytes: &[u8],
+        write: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        let previous = self
+            .versions
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(path.to_path_buf(), content_hash(bytes));
+        let result = write();
+        if result.is_err() {
+            if let Ok(mut versions) = self.versions.lock() {
+                match previous {
+                    Some(previous) => versions.insert(path.to_path_buf(), previous),
+                    None => versions.remove(path),
+                };
+            }
+        }
+        result
+    }
+
+    /// Fail if `path` changed on disk since the app last read or wrote it.
+    /// Files the app has not seen and files deleted since are not conflicts.
+    pub fn check_unchanged(&self, path: &Path) -> Result<(), String> {
+        let known = self
+            .versions
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(path)
+            .copied();
+        match (known, fs::read(path)) {
+            (Some(known), Ok(current)) if content_hash(&current) != known => Err(format!(
+                "{}，为避免覆盖他人的修改已取消保存: {}",
+                CONFLICT_ERROR,
+                path.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How an event changed the watched file, or `None` for the app's own writes
+fn file_change_kind(
+    versions: &Mutex<HashMap<PathBuf, u64>>,
+    path: &Path,
+    kind: &EventKind,
+) -> Option<&'static str> {
+    if !path.exists() {
+        return Some(match kind {
+            EventKind::Modify(ModifyKind::Name(_)) => "rename",
+            _ => "delete",
+        });
+    }
+    let current = content_hash(&fs::read(path).ok()?);
+    let known = versions.lock().ok()?.get(path).copied();
+    (known != Some(current)).then_some("modify")
+}
+
+fn asset_change_kind(kind: &EventKind) -> &'static str {
+    match kind {
+        EventKind::Create(_) => "create",
+        EventKind::Modify(ModifyKind::Name(_)) => "rename",
+        EventKind::Remove(_) => "delete",
+        _ => "modify",
+    }
+}
+
+/// Watch `file` and `assets` in place of whatever was watched before
+fn start_watching(
+    app: AppHandle,
+    watch: &FileWatch,
+    file: PathBuf,
+    assets: Option<PathBuf>,
+) -> Result<(), String> {
+    let versions = watch.versions.clone();
+    let watched_file = file.clone();
+    let watched_assets = assets.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in &event.paths {
+            if path == &watched_file {
+                if let Some(kind) = file_change_kind(&versions, path, &event.kind) {
+                    let _ = app.emit(
+                        FILE_CHANGED_EVENT,
+                        FileChangeEvent {
+                            path: path.to_string_lossy().to_string(),
+                            kind: kind.to_string(),
+                        },
+                    );
+                }
+            } else if watched_assets.as_ref().is_some_and(|a| path.starts_with(a)) {
+                let _ = app.emit(
+                    ASSETS_CHANGED_EVENT,
+                    FileChangeEvent {
+                        path: path.to_string_lossy().to_string(),
+                        kind: asset_change_kind(&event.kind).to_string(),
+                    },
+                );
+            }
+        }
+    })
+    .map_err(|e| format!("无法监听文件: {}", e))?;
+
+    // Editors often save by renaming a new file over the old one, which a
+    // watch on the file itself would lose track of, so watch its folder
+    let dir = file.parent().unwrap_or(Path::new("."));
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("无法监听文件: {}", e))?;
+    if let Some(assets) = assets.filter(|a| a.is_dir()) {
+        watcher
+            .watch(&assets, RecursiveMode::Recursive)
+            .map_err(|e| format!("无法监听资源目录: {}", e))?;
+    }
+    *watch.watcher.lock().map_err(|e| e.to_string())? = Some(watcher);
+    Ok(())
+}
+
+/// Watch the open markdown file and its assets directory for changes made by
+/// other programs
+#[tauri::command]
+pub fn watch_open_file(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    watch: State<'_, FileWatch>,
+    path: String,
+    assets_dir: Option<String>,
+) -> Result<(), String> {
+    let file = workspace.resolve(&path)?;
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let assets_dir = assets_dir.unwrap_or_else(|| "assets".to_string());
+    let assets = workspace
+        .resolve(&dir.join(&assets_dir).to_string_lossy())
+        .ok();
+    start_watching(app, &watch, file, assets)
+}
+
+/// Stop watching the previously opened file
+#[tauri::command]
+pub fn unwatch_open_file(watch: State<'_, FileWatch>) -> Result<(), String> {
+    *watch.watcher.lock().map_err(|e| e.to_string())? = None;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_only_after_external_change() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        let post = dir.join("post.md");
+        let watch = FileWatch::default();
+
+        // Unknown files and files written by the app are not conflicts
+        fs::write(&post, "v1").unwrap();
+        assert!(watch.check_unchanged(&post).is_ok());
+        watch.record(&post, b"v1");
+        assert!(watch.check_unchanged(&post).is_ok());
+        let modify = EventKind::Modify(ModifyKind::Any);
+        assert_eq!(file_change_kind(&watch.versions, &post, &modify), None);
+
+        fs::write(&post, "edited elsewhere").unwrap();
+        let error = watch.check_unchanged(&post).unwrap_err();
+        assert!(error.starts_with(CONFLICT_ERROR));
+        assert_eq!(
+            file_change_kind(&watch.versions, &post, &modify),
+            Some("modify")
+        );
+
+        fs::remove_file(&post).unwrap();
+        assert!(watch.check_unchanged(&post).is_ok());
+        assert_eq!(
+            file_change_kind(
+                &watch.versions,
+                &post,
+                &EventKind::Remove(notify::event::RemoveKind::File)
+            ),
+            Some("delete")
+        );
+    }
+}
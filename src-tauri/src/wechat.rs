@@ -4,11 +4,18 @@ use reqwest::{multipart, Client};
 use std::collections::{HashMap, HashSet};
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use tauri::State;
 
-use crate::models::{WechatTokenResponse, WechatUploadResponse, WechatUploadResult, WechatUploadResultEntry};
+use crate::image::download_image;
+use crate::math::{export_math, has_math};
+use crate::mermaid::{export_mermaid_blocks, has_mermaid_blocks};
+use crate::site::{PostContext, SiteProfile};
+use crate::workspace::Workspace;
+use crate::models::{AssetExportResult, WechatTokenResponse, WechatUploadResponse, WechatUploadResult, WechatUploadResultEntry};
+use crate::svg::ImageFormat;
 
 // ============ Token cache ============
 
@@ -121,15 +128,64 @@ pub async fn test_wechat_access_token(appId: String, appSecret: String) -> Resul
     Ok(format!("access_token 获取成功（部分）: {}", short))
 }
 
+fn is_remote_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Keep a downloaded image in the post's assets folder; failures only cost the copy
+fn keep_local_copy(base_dir: Option<&Path>, assets_dir_name: &str, name: &str, bytes: &[u8]) {
+    let Some(dir) = base_dir else {
+        return;
+    };
+    let assets_dir = dir.join(assets_dir_name);
+    if let Err(e) = std::fs::create_dir_all(&assets_dir) {
+        eprintln!("创建 assets 目录失败 {}: {}", assets_dir.display(), e);
+        return;
+    }
+    let local_path = assets_dir.join(name);
+    if let Err(e) = std::fs::write(&local_path, bytes) {
+        eprintln!("保存下载图片到本地失败 {}: {}", local_path.display(), e);
+    }
+}
+
+type AssetExport = fn(&str, &Path, &str, ImageFormat) -> Result<AssetExportResult, String>;
+
+/// Render `label` items to PNG files next to the post so they upload like any
+/// other image; nothing is uploaded if one of them fails
+fn export_to_assets(
+    markdown: &str,
+    base_dir: Option<&Path>,
+    assets_dir_name: &str,
+    label: &str,
+    export: AssetExport,
+) -> Result<String, String> {
+    let Some(dir) = base_dir else {
+        return Err(format!(
+            "当前文件尚未保存，无法将{}渲染到 {} 目录",
+            label, assets_dir_name
+        ));
+    };
+    let exported = export(markdown, dir, assets_dir_name, ImageFormat::Png)?;
+    if !exported.errors.is_empty() {
+        return Err(format!("{}渲染失败: {}", label, exported.errors.join("; ")));
+    }
+    Ok(exported.markdown)
+}
+
 #[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn wechat_upload_and_replace_images(
+    workspace: State<'_, Workspace>,
     markdown: String,
     appId: String,
     appSecret: String,
     baseDir: Option<String>,
+    filePath: Option<String>,
     sitePrefix: Option<String>,
+    assetsDir: Option<String>,
 ) -> Result<WechatUploadResult, String> {
+    let assets_dir_name = assetsDir.unwrap_or_else(|| "assets".to_string());
     let app_id = {
         let trimmed = appId.trim();
         if !trimmed.is_empty() {
@@ -155,6 +211,43 @@ pub async fn wechat_upload_and_replace_images(
     let client = Client::new();
     let access_token = get_wechat_access_token(&client, &app_id, &app_secret).await?;
 
+    let base_dir_path: Option<PathBuf> = baseDir
+        .map(|dir| workspace.resolve_base_dir(&dir, &assets_dir_name))
+        .transpose()?;
+    let file_path = filePath.map(|path| workspace.resolve(&path)).transpose()?;
+
+    // Mermaid diagrams and formulas become PNG assets first so they upload
+    // like any other image
+    let mut markdown = markdown;
+    if has_mermaid_blocks(&markdown) {
+        markdown = export_to_assets(
+            &markdown,
+            base_dir_path.as_deref(),
+            &assets_dir_name,
+            "Mermaid 图",
+            export_mermaid_blocks,
+        )?;
+    }
+    if has_math(&markdown) {
+        markdown = export_to_assets(
+            &markdown,
+            base_dir_path.as_deref(),
+            &assets_dir_name,
+            "公式",
+            export_math,
+        )?;
+    }
+
+    let profile = SiteProfile::detect(
+        base_dir_path.as_deref().unwrap_or(Path::new("")),
+        sitePrefix.as_deref(),
+    );
+    let post = PostContext::new(
+        base_dir_path.clone().unwrap_or_default(),
+        file_path,
+        &markdown,
+    );
+
     let re = Regex::new(r"!\[[^\]]*]\(([^)]+)\)").map_err(|e| e.to_string())?;
     let mut unique_urls: HashSet<String> = HashSet::new();
     for caps in re.captures_iter(&markdown) {
@@ -170,7 +263,14 @@ pub async fn wechat_upload_and_replace_images(
         });
     }
 
-    let base_dir_path: Option<PathBuf> = baseDir.map(PathBuf::from);
+    if base_dir_path.is_none() {
+        if let Some(local) = unique_urls.iter().find(|url| !is_remote_url(url)) {
+            return Err(format!(
+                "当前文件尚未保存，无法读取本地图片 {}，请先保存文件",
+                local
+            ));
+        }
+    }
 
     let mut existing_entries: HashMap<String, WechatUploadResultEntry> = HashMap::new();
     let mut new_entries: Vec<WechatUploadResultEntry> = Vec::new();
@@ -205,130 +305,36 @@ pub async fn wechat_upload_and_replace_images(
             continue;
         }
 
-        let is_remote = url.starts_with("http://") || url.starts_with("https://");
-
-        let (bytes, filename) = if is_remote {
-            let resp = client.get(url).send().await.map_err(|e| e.to_string())?;
-            if !resp.status().is_success() {
-                return Err(format!("下载远程图片失败 {}: {}", url, resp.status()));
-            }
-            let body = resp.bytes().await.map_err(|e| e.to_string())?;
-            let name = url
-                .split('/')
-                .last()
-                .filter(|s| !s.is_empty())
-                .unwrap_or("image.png")
-                .to_string();
-
-            let bytes_vec = body.to_vec();
-
-            if let Some(dir) = &base_dir_path {
-                let mut assets_dir = dir.clone();
-                assets_dir.push("assets");
-                if let Err(e) = std::fs::create_dir_all(&assets_dir) {
-                    eprintln!("创建 assets 目录失败 {}: {}", assets_dir.display(), e);
-                } else {
-                    let mut local_path = assets_dir.clone();
-                    local_path.push(&name);
-                    if let Err(e) = std::fs::write(&local_path, &bytes_vec) {
-                        eprintln!("保存下载图片到本地失败 {}: {}", local_path.display(), e);
-                    }
-                }
-            }
-
-            (bytes_vec, name)
+        let (bytes, filename) = if is_remote_url(url) {
+            let (bytes, name) = download_image(&client, url).await?;
+            keep_local_copy(base_dir_path.as_deref(), &assets_dir_name, &name, &bytes);
+            (bytes, name)
         } else {
-            let path = if let Some(dir) = &base_dir_path {
-                dir.join(url)
-            } else {
-                PathBuf::from(url)
-            };
-
-            match std::fs::read(&path) {
-                Ok(data) => {
-                    let name = path
+            let resolved = profile.resolve_image(&workspace, &post, url);
+            match (resolved.local, resolved.url) {
+                (Some(local), _) => {
+                    let local = workspace.resolve(&local.to_string_lossy())?;
+                    let data = std::fs::read(&local)
+                        .map_err(|e| format!("读取本地图片失败 {}: {}", local.display(), e))?;
+                    let name = local
                         .file_name()
                         .and_then(|s| s.to_str())
                         .unwrap_or("image.png")
                         .to_string();
                     (data, name)
                 }
-                Err(local_err) => {
-                    if let Some(prefix) = &sitePrefix {
-                        let prefix_trimmed = prefix.trim();
-                        if !prefix_trimmed.is_empty() {
-                            let full_url =
-                                format!("{}{}", prefix_trimmed.trim_end_matches('/'), url);
-                            match client.get(&full_url).send().await {
-                                Ok(resp) if resp.status().is_success() => {
-                                    let body = resp.bytes().await.map_err(|e| e.to_string())?;
-                                    let name = url
-                                        .split('/')
-                                        .last()
-                                        .filter(|s| !s.is_empty())
-                                        .unwrap_or("image.png")
-                                        .to_string();
-
-                                    let bytes_vec = body.to_vec();
-
-                                    if let Some(dir) = &base_dir_path {
-                                        let mut assets_dir = dir.clone();
-                                        assets_dir.push("assets");
-                                        if let Err(e) = std::fs::create_dir_all(&assets_dir) {
-                                            eprintln!(
-                                                "创建 assets 目录失败 {}: {}",
-                                                assets_dir.display(),
-                                                e
-                                            );
-                                        } else {
-                                            let mut local_path = assets_dir.clone();
-                                            local_path.push(&name);
-                                            if let Err(e) = std::fs::write(&local_path, &bytes_vec)
-                                            {
-                                                eprintln!(
-                                                    "保存下载图片到本地失败 {}: {}",
-                                                    local_path.display(),
-                                                    e
-                                                );
-                                            }
-                                        }
-                                    }
-
-                                    (bytes_vec, name)
-                                }
-                                Ok(resp) => {
-                                    return Err(format!(
-                                        "读取本地图片失败 {}: {}；尝试下载 {} 也失败: HTTP {}",
-                                        path.display(),
-                                        local_err,
-                                        full_url,
-                                        resp.status()
-                                    ));
-                                }
-                                Err(download_err) => {
-                                    return Err(format!(
-                                        "读取本地图片失败 {}: {}；尝试下载 {} 也失败: {}",
-                                        path.display(),
-                                        local_err,
-                                        full_url,
-                                        download_err
-                                    ));
-                                }
-                            }
-                        } else {
-                            return Err(format!(
-                                "读取本地图片失败 {}: {}",
-                                path.display(),
-                                local_err
-                            ));
-                        }
-                    } else {
-                        return Err(format!(
-                            "读取本地图片失败 {}: {}（未配置网站前缀，无法尝试下载）",
-                            path.display(),
-                            local_err
-                        ));
-                    }
+                (None, Some(published)) => {
+                    let (bytes, name) = download_image(&client, &published)
+                        .await
+                        .map_err(|e| format!("未找到本地图片 {}；{}", url, e))?;
+                    keep_local_copy(base_dir_path.as_deref(), &assets_dir_name, &name, &bytes);
+                    (bytes, name)
+                }
+                (None, None) => {
+                    return Err(format!(
+                        "未找到本地图片 {}，也无法确定其发布地址（未检测到站点配置中的网址，也未配置网站前缀）",
+                        url
+                    ));
                 }
             }
         };
@@ -0,0 +1,283 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+
+/// Approved roots, kept in the app data directory across restarts
+const WORKSPACE_FILE: &str = "workspace.json";
+
+/// Folders the user has picked through a native dialog. File commands only
+/// touch paths inside one of them, so a script running in the webview cannot
+/// read or write anywhere else.
+pub struct Workspace {
+    roots: Mutex<Vec<PathBuf>>,
+    store: Option<PathBuf>,
+}
+
+impl Workspace {
+    /// Load the roots saved in `store`, dropping any that no longer exist
+    pub fn load(store: Option<PathBuf>) -> Self {
+        let roots = store
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str::<Vec<PathBuf>>(&text).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|root| fs::canonicalize(root).ok())
+            .filter(|root| root.is_dir())
+            .collect();
+        Workspace {
+            roots: Mutex::new(roots),
+            store,
+        }
+    }
+
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    fn save(&self, roots: &[PathBuf]) {
+        if let Some(store) = &self.store {
+            if let Some(parent) = store.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Ok(text) = serde_json::to_string_pretty(roots) {
+                let _ = crate::file::write_atomic(store, text.as_bytes());
+            }
+        }
+    }
+
+    /// Add `dir` as a root. Only call this for folders the user chose in a
+    /// native dialog, never for paths sent by the webview.
+    pub fn approve(&self, dir: &Path) -> Result<PathBuf, String> {
+        let dir =
+            fs::canonicalize(dir).map_err(|e| format!("无法访问目录 {}: {}", dir.display(), e))?;
+        if !dir.is_dir() {
+            return Err(format!("不是目录: {}", dir.display()));
+        }
+        let mut roots = self.roots.lock().map_err(|e| e.to_string())?;
+        if !roots.iter().any(|root| dir.starts_with(root)) {
+            // A new parent folder replaces the roots it contains
+            roots.retain(|root| !root.starts_with(&dir));
+            roots.push(dir.clone());
+            self.save(&roots);
+        }
+        Ok(dir)
+    }
+
+    pub fn remove(&self, dir: &Path) -> Result<(), String> {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let mut roots = self.roots.lock().map_err(|e| e.to_string())?;
+        roots.retain(|root| root != &dir);
+        self.save(&roots);
+        Ok(())
+    }
+
+    /// Resolve a post's folder, checking that its assets folder (which may be
+    /// given relative to it) stays inside the workspace as well
+    pub fn resolve_base_dir(&self, base_dir: &str, assets_dir: &str) -> Result<PathBuf, String> {
+        let base = self.resolve(base_dir)?;
+        self.resolve(&base.join(assets_dir).to_string_lossy())?;
+        Ok(base)
+    }
+
+    /// Canonical form of `path` if it lies inside an approved root.
+    ///
+    /// The path may not exist yet (a file about to be saved, a directory
+    /// about to be created): its deepest existing ancestor is canonicalised,
+    /// which resolves symlinks, and the missing components are appended as
+    /// plain names. `..` in the missing part and dangling symlinks are refused.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let requested = Path::new(path);
+        if !requested.is_absolute() {
+            return Err(format!("路径必须是绝对路径: {}", path));
+        }
+
+        let mut existing = requested;
+        let mut missing: Vec<&std::ffi::OsStr> = Vec::new();
+        while fs::symlink_metadata(existing).is_err() {
+            // `file_name` is `None` for `..`, which cannot be resolved under a
+            // directory that does not exist yet
+            let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                return Err(format!("路径中不能包含 ..: {}", path));
+            };
+            missing.push(name);
+            existing = parent;
+        }
+
+        let mut resolved = fs::canonicalize(existing)
+            .map_err(|e| format!("无法解析路径 {}（可能是失效的符号链接）: {}", path, e))?;
+        for name in missing.into_iter().rev() {
+            resolved.push(name);
+        }
+
+        let roots = self.roots.lock().map_err(|e| e.to_string())?;
+        if roots.iter().any(|root| resolved.starts_with(root)) {
+            Ok(resolved)
+        } else if roots.is_empty() {
+            Err("尚未授权任何工作区目录，请先通过打开文件或添加工作区目录授权".to_string())
+        } else {
+            Err(format!(
+                "路径不在已授权的工作区内: {}（实际指向 {}）",
+                path,
+                resolved.display()
+            ))
+        }
+    }
+}
+
+/// Load the saved roots and let the asset protocol serve files inside them
+pub fn init_workspace(app: &AppHandle) {
+    let store = app
+        .path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join(WORKSPACE_FILE));
+    let workspace = Workspace::load(store);
+    for root in workspace.roots() {
+        let _ = app.asset_protocol_scope().allow_directory(&root, true);
+    }
+    app.manage(workspace);
+}
+
+pub(crate) fn approve_and_expose(
+    app: &AppHandle,
+    workspace: &Workspace,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    let root = workspace.approve(dir)?;
+    app.asset_protocol_scope()
+        .allow_directory(&root, true)
+        .map_err(|e| e.to_string())?;
+    Ok(root)
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Let the user pick a markdown file; its folder becomes part of the workspace
+#[tauri::command]
+pub async fn pick_markdown_file(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+) -> Result<Option<String>, String> {
+    let Some(file) = app
+        .dialog()
+        .file()
+        .add_filter("Markdown", &["md", "markdown"])
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+    let path = file.into_path().map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        approve_and_expose(&app, &workspace, parent)?;
+    }
+    workspace
+        .resolve(&path.to_string_lossy())
+        .map(path_string)
+        .map(Some)
+}
+
+/// Let the user choose where to save a new markdown file; its folder becomes
+/// part of the workspace
+#[tauri::command]
+pub async fn pick_markdown_save_path(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    default_name: Option<String>,
+) -> Result<Option<String>, String> {
+    let Some(file) = app
+        .dialog()
+        .file()
+        .add_filter("Markdown", &["md", "markdown"])
+        .set_file_name(default_name.unwrap_or_else(|| "Untitled.md".to_string()))
+        .blocking_save_file()
+    else {
+        return Ok(None);
+    };
+    let path = file.into_path().map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        approve_and_expose(&app, &workspace, parent)?;
+    }
+    workspace
+        .resolve(&path.to_string_lossy())
+        .map(path_string)
+        .map(Some)
+}
+
+/// Let the user pick a folder to add to the workspace
+#[tauri::command]
+pub async fn add_workspace_root(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+) -> Result<Option<String>, String> {
+    let Some(folder) = app.dialog().file().blocking_pick_folder() else {
+        return Ok(None);
+    };
+    let dir = folder.into_path().map_err(|e| e.to_string())?;
+    approve_and_expose(&app, &workspace, &dir)
+        .map(path_string)
+        .map(Some)
+}
+
+/// Folders file commands may access
+#[tauri::command]
+pub fn list_workspace_roots(workspace: State<'_, Workspace>) -> Vec<String> {
+    workspace.roots().into_iter().map(path_string).collect()
+}
+
+/// Stop allowing file commands inside `path`. Images already served from it
+/// stay visible until the app restarts.
+#[tauri::command]
+pub fn remove_workspace_root(workspace: State<'_, Workspace>, path: String) -> Result<(), String> {
+    workspace.remove(Path::new(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_rejects_escapes() {
+        let temp = tempfile::tempdir().unwrap();
+        let base = temp.path().to_path_buf();
+        let root = base.join("blog");
+        let outside = base.join("secrets");
+        fs::create_dir_all(root.join("posts")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("key.txt"), "x").unwrap();
+
+        let workspace = Workspace::load(None);
+        let at = |p: &Path| p.to_string_lossy().to_string();
+        assert!(workspace.resolve(&at(&root.join("a.md"))).is_err());
+
+        let root = workspace.approve(&root).unwrap();
+        assert_eq!(
+            workspace
+                .resolve(&at(&root.join("posts/new/a.md")))
+                .unwrap(),
+            root.join("posts/new/a.md")
+        );
+        assert!(workspace.resolve(&at(&root.join("posts/../a.md"))).is_ok());
+        assert!(workspace
+            .resolve(&at(&root.join("../secrets/key.txt")))
+            .is_err());
+        assert!(workspace
+            .resolve(&at(&root.join("new/../../x.md")))
+            .is_err());
+        assert!(workspace.resolve("posts/a.md").is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+            std::os::unix::fs::symlink(outside.join("gone.txt"), root.join("dangling")).unwrap();
+            assert!(workspace.resolve(&at(&root.join("link/key.txt"))).is_err());
+            assert!(workspace.resolve(&at(&root.join("link/new.md"))).is_err());
+            assert!(workspace.resolve(&at(&root.join("dangling"))).is_err());
+        }
+    }
+}
@@ -1,19 +1,35 @@
 import { useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { open, save } from "@tauri-apps/plugin-dialog";
 import { Toolbar } from "./components/Toolbar";
 import { EditorPane } from "./components/EditorPane";
+import { PostList } from "./components/PostList";
+import { DraftRecovery } from "./components/DraftRecovery";
 import { PreviewPane } from "./components/PreviewPane";
 import { SettingsPane } from "./components/Settings";
 import {
-  ensureMermaidInitialized,
-  inlineMermaid,
-  renderMermaidSvg,
-  svgToPngBytes,
-} from "./utils/mermaid";
-import { useMarkdownConverter, type CustomTheme } from "./hooks/useMarkdownConverter";
+  buildConvertRequest,
+  useMarkdownConverter,
+  type ConvertOptions,
+  type CustomTheme,
+  type ElementStyleTrace,
+  type LintDiagnostic,
+} from "./hooks/useMarkdownConverter";
 import { useProcessedHtml } from "./hooks/useProcessedHtml";
 import { usePersistentState } from "./hooks/usePersistentState";
+import { useFileBackups, type BackupInfo } from "./hooks/useFileBackups";
+import { useWorkspace } from "./hooks/useWorkspace";
+import { useBlogIndex, type BlogPost } from "./hooks/useBlogIndex";
+import { FILE_CONFLICT_ERROR, useFileWatcher } from "./hooks/useFileWatcher";
+import { useAutosave, type DraftInfo } from "./hooks/useAutosave";
+import { useFileFormat } from "./hooks/useFileFormat";
+import { useSiteProfile } from "./hooks/useSiteProfile";
+import {
+  useThemeRegistry,
+  type ContrastReport,
+  type ThemeImportResult,
+  type ThemeInfo,
+  type ThemeTokens,
+} from "./hooks/useThemeRegistry";
 import "./App.css";
 
 const DEFAULT_MARKDOWN = [
@@ -85,6 +101,12 @@ function App() {
   const [sitePrefix, setSitePrefix] = usePersistentState("sitePrefix", "");
   const [imagePrefix, setImagePrefix] = usePersistentState("imagePrefix", "");
   const [assetsDir, setAssetsDir] = usePersistentState("assetsDir", "assets");
+  const [footnoteHeading, setFootnoteHeading] = usePersistentState("footnoteHeading", "");
+  const [footnoteAllowlist, setFootnoteAllowlist] = usePersistentState("footnoteAllowlist", "");
+  const [tocNumbered, setTocNumbered] = usePersistentState("tocNumbered", "false");
+  const [darkModeHints, setDarkModeHints] = usePersistentState("darkModeHints", "false");
+  const [backupCount, setBackupCount] = usePersistentState("backupCount", "10");
+  const [normalizeOnSave, setNormalizeOnSave] = usePersistentState("normalizeOnSave", "false");
   const [openaiUrl, setOpenaiUrl] = usePersistentState("openaiUrl", "");
   const [openaiToken, setOpenaiToken] = usePersistentState("openaiToken", "");
   const [openaiModel, setOpenaiModel] = usePersistentState("openaiModel", "");
@@ -97,15 +119,53 @@ function App() {
   const [isUploadingWechatImages, setIsUploadingWechatImages] = useState(false);
   const [isGeneratingCoverImage, setIsGeneratingCoverImage] = useState(false);
   const [debugLogs, setDebugLogs] = useState<string[]>([]);
+  const [jumpTo, setJumpTo] = useState<{ line: number; nonce: number } | null>(null);
   const [openaiTestStatus, setOpenaiTestStatus] = useState("");
   const [wechatTestStatus, setWechatTestStatus] = useState("");
   const [isTestingOpenai, setIsTestingOpenai] = useState(false);
   const [isTestingWechat, setIsTestingWechat] = useState(false);
   const [settingsSaveStatus, setSettingsSaveStatus] = useState("");
   const previewRef = useRef<HTMLIFrameElement>(null);
-
-  const rawHtml = useMarkdownConverter(markdown, currentTheme, customTheme);
-  const html = useProcessedHtml(rawHtml, imagePrefix, currentFilePath, assetsDir);
+  const { themes, refresh: refreshThemes, installTheme, removeTheme } = useThemeRegistry();
+  const { backups, refresh: refreshBackups } = useFileBackups(currentFilePath);
+  const { format: fileFormat, refresh: refreshFileFormat } = useFileFormat(currentFilePath);
+  const siteProfile = useSiteProfile(currentFilePath, sitePrefix);
+  const {
+    roots: workspaceRoots,
+    refresh: refreshWorkspaceRoots,
+    addRoot: addWorkspaceRoot,
+    removeRoot: removeWorkspaceRoot,
+  } = useWorkspace();
+  const {
+    list: blogPosts,
+    query: blogQuery,
+    setQuery: setBlogQuery,
+    refresh: refreshBlogPosts,
+    openBlog,
+    closeBlog,
+  } = useBlogIndex();
+  const { drafts, markClean, startBuffer, dismissDraft } = useAutosave(markdown, currentFilePath, DEFAULT_MARKDOWN);
+  const parsedBackupCount = parseInt(backupCount, 10);
+  const backupCountOption = Number.isNaN(parsedBackupCount) ? undefined : Math.max(0, parsedBackupCount);
+
+  const convertOptions: ConvertOptions = {
+    footnoteHeading,
+    footnoteAllowlist,
+    tocNumbered: tocNumbered === "true",
+    darkModeHints: darkModeHints === "true",
+    imagePrefix,
+    assetsDir,
+    baseDir: currentFilePath ? currentFilePath.replace(/[\\/][^\\/]*$/, "") : null,
+    filePath: currentFilePath,
+  };
+  const { rawHtml, result: conversion } = useMarkdownConverter(
+    markdown,
+    currentTheme,
+    customTheme,
+    themes,
+    convertOptions,
+  );
+  const html = useProcessedHtml(rawHtml, currentFilePath, assetsDir);
 
   const appendDebugLog = (message: string) => {
     const time = new Date().toLocaleString();
@@ -113,6 +173,30 @@ function App() {
     setDebugLogs((prev) => [line, ...prev].slice(0, 200));
   };
 
+  useFileWatcher(currentFilePath, assetsDir, {
+    onFileChanged: async (event) => {
+      if (event.kind !== "modify") {
+        appendDebugLog(`${event.path} 已被外部${event.kind === "rename" ? "重命名或移动" : "删除"}，保存时将重新创建`);
+        return;
+      }
+      appendDebugLog(`${event.path} 已在外部修改`);
+      if (!confirm("当前文件已被其他程序修改，是否重新加载？未保存的修改将丢失。")) {
+        return;
+      }
+      try {
+        const content = await invoke<string>("open_markdown_file", { path: event.path });
+        setMarkdown(content);
+        markClean(content);
+        refreshFileFormat();
+      } catch (e) {
+        alert("重新加载失败: " + String(e));
+      }
+    },
+    onAssetsChanged: (event) => {
+      appendDebugLog(`资源目录变化（${event.kind}）：${event.path}`);
+    },
+  });
+
   const copyToClipboard = async () => {
     try {
       let htmlToCopy = html;
@@ -121,7 +205,6 @@ function App() {
         const processedBody = iframeDoc.body.innerHTML;
         htmlToCopy = `<!DOCTYPE html><html><head><meta charset="utf-8"></head><body>${processedBody}</body></html>`;
       }
-      htmlToCopy = await inlineMermaid(htmlToCopy);
       const blob = new Blob([htmlToCopy], { type: "text/html" });
       const textBlob = new Blob([markdown], { type: "text/plain" });
       const data = [
@@ -147,22 +230,59 @@ function App() {
         alert("打开文件仅在 Tauri 应用中可用。");
         return;
       }
-      const selected = await open({
-        multiple: false,
-        filters: [{ name: "Markdown", extensions: ["md", "markdown"] }],
-      });
-      if (!selected || Array.isArray(selected)) {
+      // The dialog runs in the backend so the chosen folder is added to the workspace
+      const selected = await invoke<string | null>("pick_markdown_file");
+      if (!selected) {
         return;
       }
       const content = await invoke<string>("open_markdown_file", { path: selected });
       setMarkdown(content);
+      startBuffer(content);
       setCurrentFilePath(selected);
+      refreshWorkspaceRoots();
     } catch (e) {
       console.error("Open markdown file failed", e);
-      alert("打开 Markdown 文件失败");
+      alert("打开 Markdown 文件失败: " + String(e));
     }
   };
 
+  const handleOpenBlog = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("博客目录仅在 Tauri 应用中可用。");
+        return;
+      }
+      const opened = await openBlog();
+      if (opened) {
+        appendDebugLog(`已打开博客目录 ${opened.root}，共 ${opened.total} 篇文章`);
+        refreshWorkspaceRoots();
+      }
+    } catch (e) {
+      console.error("Open blog failed", e);
+      alert("打开博客目录失败: " + String(e));
+    }
+  };
+
+  const openPostAt = async (path: string, line: number | null) => {
+    try {
+      if (path !== currentFilePath) {
+        const content = await invoke<string>("open_markdown_file", { path });
+        setMarkdown(content);
+        startBuffer(content);
+        setCurrentFilePath(path);
+      }
+      if (line !== null) {
+        setJumpTo({ line, nonce: Date.now() });
+      }
+    } catch (e) {
+      console.error("Open post failed", e);
+      alert("打开文章失败: " + String(e));
+    }
+  };
+
+  const handleOpenPost = (post: BlogPost) => openPostAt(post.path, null);
+
   const handleSaveMarkdown = async () => {
     try {
       const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
@@ -173,110 +293,371 @@ function App() {
 
       let targetPath = currentFilePath;
       if (!targetPath) {
-        const selected = await save({
-          defaultPath: "Untitled.md",
-          filters: [{ name: "Markdown", extensions: ["md", "markdown"] }],
+        const selected = await invoke<string | null>("pick_markdown_save_path", {
+          defaultName: "Untitled.md",
         });
         if (!selected) {
           return;
         }
         targetPath = selected;
+        refreshWorkspaceRoots();
       }
 
-      await invoke("save_markdown_file", { path: targetPath, content: markdown });
+      const request = {
+        path: targetPath,
+        content: markdown,
+        backupCount: backupCountOption,
+        normalize: normalizeOnSave === "true",
+      };
+      try {
+        await invoke("save_markdown_file", request);
+      } catch (e) {
+        if (!String(e).startsWith(FILE_CONFLICT_ERROR)) {
+          throw e;
+        }
+        if (!confirm("文件在打开后已被其他程序修改。仍要用编辑器中的内容覆盖吗？启用了文件备份时，磁盘上的版本会先保存为备份。")) {
+          return;
+        }
+        await invoke("save_markdown_file", { ...request, force: true });
+      }
       setCurrentFilePath(targetPath);
+      markClean(markdown);
+      refreshBackups();
+      refreshFileFormat();
+      refreshBlogPosts();
       alert("保存成功");
     } catch (e) {
       console.error("Save markdown file failed", e);
-      alert("保存 Markdown 文件失败");
+      alert("保存 Markdown 文件失败: " + String(e));
     }
   };
 
-  const handleExportMermaidToPng = async () => {
+  const handleAddWorkspaceRoot = async () => {
     try {
-      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
-      if (!isTauri) {
-        alert("导出 Mermaid 图片仅在 Tauri 应用中可用。");
-        appendDebugLog("导出 Mermaid 图片失败：当前不在 Tauri 环境中。");
-        return;
+      const root = await addWorkspaceRoot();
+      if (root) {
+        appendDebugLog(`已添加工作区目录：${root}`);
       }
+    } catch (e) {
+      console.error("Add workspace root failed", e);
+      alert("添加工作区目录失败: " + String(e));
+    }
+  };
 
-      const mermaidBlocks: string[] = [];
-      const re = /```mermaid([\s\S]*?)```/g;
-      let match: RegExpExecArray | null;
-      while ((match = re.exec(markdown)) !== null) {
-        const code = (match[1] || "").trim();
-        if (code) {
-          mermaidBlocks.push(code);
+  const handleRestoreBackup = async (backup: BackupInfo) => {
+    if (!currentFilePath) {
+      return;
+    }
+    const time = new Date(backup.timestamp).toLocaleString();
+    if (!confirm(`用 ${time} 的备份覆盖当前文件？当前内容会先另存为一份备份。`)) {
+      return;
+    }
+    try {
+      const content = await invoke<string>("restore_file_backup", {
+        path: currentFilePath,
+        backupPath: backup.path,
+        backupCount: backupCountOption,
+      });
+      setMarkdown(content);
+      markClean(content);
+      appendDebugLog(`已从 ${time} 的备份恢复 ${currentFilePath}`);
+      refreshBackups();
+      refreshFileFormat();
+    } catch (e) {
+      console.error("Restore backup failed", e);
+      alert("恢复备份失败: " + String(e));
+    }
+  };
+
+  const handleRestoreDraft = async (draft: DraftInfo) => {
+    if (markdown !== DEFAULT_MARKDOWN && !confirm(`用草稿“${draft.title}”替换编辑器中的内容？`)) {
+      return;
+    }
+    try {
+      const content = await invoke<string>("load_draft", { id: draft.id });
+      let onDisk = "";
+      if (draft.path) {
+        // Read the file first so saving the draft back still detects external edits
+        try {
+          onDisk = await invoke<string>("open_markdown_file", { path: draft.path });
+        } catch (e) {
+          appendDebugLog(`草稿对应的文件无法打开，将按新文件保存：${String(e)}`);
         }
       }
+      setMarkdown(content);
+      startBuffer(onDisk, draft.id);
+      setCurrentFilePath(draft.path);
+      appendDebugLog(`已恢复草稿：${draft.title}`);
+    } catch (e) {
+      console.error("Restore draft failed", e);
+      alert("恢复草稿失败: " + String(e));
+    }
+  };
 
-      if (mermaidBlocks.length === 0) {
-        alert("当前文档中没有 Mermaid 代码块。");
-        appendDebugLog("导出 Mermaid 图片失败：未找到 Mermaid 代码块。");
+  const handleSaveDraftAs = async (draft: DraftInfo) => {
+    try {
+      const selected = await invoke<string | null>("pick_markdown_save_path", {
+        defaultName: `${draft.title.replace(/[\\/:*?"<>|]/g, "_")}.md`,
+      });
+      if (!selected) {
         return;
       }
+      const request = {
+        id: draft.id,
+        path: selected,
+        backupCount: backupCountOption,
+        normalize: normalizeOnSave === "true",
+      };
+      let content: string;
+      try {
+        content = await invoke<string>("promote_draft", request);
+      } catch (e) {
+        if (!String(e).startsWith(FILE_CONFLICT_ERROR)) {
+          throw e;
+        }
+        if (!confirm("目标文件在打开后已被其他程序修改。仍要用草稿内容覆盖吗？启用了文件备份时，磁盘上的版本会先保存为备份。")) {
+          return;
+        }
+        content = await invoke<string>("promote_draft", { ...request, force: true });
+      }
+      setMarkdown(content);
+      startBuffer(content);
+      dismissDraft(draft.id, true);
+      setCurrentFilePath(selected);
+      refreshWorkspaceRoots();
+      refreshBackups();
+      refreshFileFormat();
+      refreshBlogPosts();
+      appendDebugLog(`已将草稿“${draft.title}”保存为 ${selected}`);
+    } catch (e) {
+      console.error("Save draft failed", e);
+      alert("保存草稿失败: " + String(e));
+    }
+  };
 
-      if (!currentFilePath) {
-        alert("请先保存 Markdown 文件，再导出 Mermaid 图片。");
-        appendDebugLog("导出 Mermaid 图片失败：当前文件尚未保存，无法确定图片目录。");
+  const handleDiscardDraft = async (draft: DraftInfo) => {
+    if (!confirm(`丢弃草稿“${draft.title}”？此操作无法撤销。`)) {
+      return;
+    }
+    try {
+      await dismissDraft(draft.id);
+    } catch (e) {
+      console.error("Discard draft failed", e);
+      alert("丢弃草稿失败: " + String(e));
+    }
+  };
+
+  const handleCheckCompatibility = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("兼容性检查仅在 Tauri 应用中可用。");
         return;
       }
 
-      const lastSepIndex = Math.max(currentFilePath.lastIndexOf("\\"), currentFilePath.lastIndexOf("/"));
-      const dir = lastSepIndex >= 0 ? currentFilePath.slice(0, lastSepIndex) : "";
-      const sep = currentFilePath.includes("\\") ? "\\" : "/";
-
-      const targetDir = dir ? `${dir}${sep}${assetsDir}` : assetsDir;
-      try {
-        await invoke("create_directory", { path: targetDir });
-      } catch (e) {
-        console.log(`${assetsDir} 目录可能已存在:`, e);
+      const diagnostics = await invoke<LintDiagnostic[]>(
+        "lint_wechat_html",
+        buildConvertRequest(markdown, currentTheme, customTheme, themes, convertOptions),
+      );
+      if (diagnostics.length === 0) {
+        appendDebugLog("微信兼容性检查通过，未发现问题。");
+        alert("未发现微信兼容性问题。");
+        return;
       }
 
-      ensureMermaidInitialized();
+      const labels = { error: "错误", warning: "警告", info: "提示" } as const;
+      for (const d of [...diagnostics].reverse()) {
+        appendDebugLog(`[${labels[d.severity]}] 第 ${d.line} 行：${d.message}（${d.rule}）`);
+      }
+      const errors = diagnostics.filter((d) => d.severity === "error").length;
+      const warnings = diagnostics.filter((d) => d.severity === "warning").length;
+      alert(
+        `发现 ${errors} 个错误、${warnings} 个警告、${diagnostics.length - errors - warnings} 个提示，详情见设置页调试日志。\n\n` +
+          diagnostics
+            .slice(0, 10)
+            .map((d) => `第 ${d.line} 行 [${labels[d.severity]}] ${d.message}`)
+            .join("\n"),
+      );
+    } catch (e) {
+      console.error("Compatibility check failed", e);
+      appendDebugLog("兼容性检查失败: " + String(e));
+      alert("兼容性检查失败: " + String(e));
+    }
+  };
 
-      let newMarkdown = "";
-      let lastIndex = 0;
-      let blockIndex = 0;
-      const blockRegex = /```mermaid([\s\S]*?)```/g;
-      let m: RegExpExecArray | null;
+  const handleTraceStyles = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("样式追踪仅在 Tauri 应用中可用。");
+        return;
+      }
+      const traces = await invoke<ElementStyleTrace[]>(
+        "trace_theme_styles",
+        buildConvertRequest(markdown, currentTheme, customTheme, themes, convertOptions),
+      );
+      const labels = { inline: "内联", theme: "主题", fallback: "兜底" } as const;
+      for (const trace of [...traces].reverse()) {
+        const properties = trace.properties
+          .map((p) => `${p.property}: ${p.value}（${labels[p.layer]}）`)
+          .join("; ");
+        appendDebugLog(`第 ${trace.line} 行 <${trace.element}> ${properties}`);
+      }
+      appendDebugLog(`样式来源追踪：共 ${traces.length} 个元素`);
+    } catch (e) {
+      console.error("Trace styles failed", e);
+      appendDebugLog("样式追踪失败: " + String(e));
+    }
+  };
 
-      while ((m = blockRegex.exec(markdown)) !== null) {
-        const matchStart = m.index;
-        const matchEnd = m.index + m[0].length;
-        const code = (m[1] || "").trim();
+  const handleCheckContrast = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("对比度检查仅在 Tauri 应用中可用。");
+        return;
+      }
+      const { css } = buildConvertRequest(markdown, currentTheme, customTheme, themes, convertOptions);
+      const report = await invoke<ContrastReport>("check_theme_contrast", { css });
+      const failing = report.checks.filter((c) => !c.passes_aa);
+      for (const c of [...failing].reverse()) {
+        const suggestions = [
+          c.suggested_color && `文字改为 ${c.suggested_color}`,
+          c.suggested_background && `或背景改为 ${c.suggested_background}`,
+        ]
+          .filter(Boolean)
+          .join(" ");
+        appendDebugLog(
+          `${c.selector}：${c.color} / ${c.background} 对比度 ${c.ratio}，低于 ${c.required}。${suggestions}`,
+        );
+      }
+      appendDebugLog(`主题「${currentTheme}」对比度检查：${report.checks.length} 项，${report.failures} 项未达到 WCAG AA`);
+      alert(
+        report.failures === 0
+          ? "所有文字颜色均达到 WCAG AA 对比度要求。"
+          : `${report.failures} 项未达到 WCAG AA 对比度要求，修改建议见调试日志。`,
+      );
+    } catch (e) {
+      console.error("Contrast check failed", e);
+      alert("对比度检查失败: " + String(e));
+    }
+  };
 
-        newMarkdown += markdown.slice(lastIndex, matchStart);
+  const handleInstallTheme = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("安装主题仅在 Tauri 应用中可用。");
+        return;
+      }
+      // The dialog runs in the backend so only a file the user picked is read
+      const theme = await installTheme();
+      if (!theme) {
+        return;
+      }
+      appendDebugLog(`已安装主题：${theme.name}`);
+      setCurrentTheme(theme.name);
+    } catch (e) {
+      console.error("Install theme failed", e);
+      appendDebugLog("安装主题失败: " + String(e));
+      alert("安装主题失败: " + String(e));
+    }
+  };
 
-        if (!code) {
-          newMarkdown += markdown.slice(matchStart, matchEnd);
-          lastIndex = matchEnd;
-          continue;
-        }
+  const handleSaveGeneratedTheme = async (name: string, tokens: ThemeTokens) => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("主题生成仅在 Tauri 应用中可用。");
+        return;
+      }
+      const theme = await invoke<ThemeInfo>("save_generated_theme", { name, tokens });
+      await refreshThemes();
+      appendDebugLog(`已生成主题：${theme.name}`);
+      setCurrentTheme(theme.name);
+    } catch (e) {
+      console.error("Generate theme failed", e);
+      appendDebugLog("生成主题失败: " + String(e));
+      alert("生成主题失败: " + String(e));
+    }
+  };
 
-        const id = `export-mermaid-${Date.now()}-${blockIndex}`;
-        const svg = await renderMermaidSvg(code, id);
-        const pngBytes = await svgToPngBytes(svg);
+  const handleImportCommunityTheme = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("导入主题仅在 Tauri 应用中可用。");
+        return;
+      }
+      const result = await invoke<ThemeImportResult | null>("import_community_theme");
+      if (!result) {
+        return;
+      }
+      for (const rule of [...result.unmapped].reverse()) {
+        appendDebugLog(`未转换的规则 ${rule.selector}：${rule.reason}`);
+      }
+      appendDebugLog(`已导入主题：${result.theme.name}，${result.unmapped.length} 条规则未转换`);
+      setCurrentTheme(result.theme.name);
+      if (result.unmapped.length > 0) {
+        alert(`主题已导入，${result.unmapped.length} 条规则无法转换，详情见调试日志。`);
+      }
+    } catch (e) {
+      console.error("Import theme failed", e);
+      appendDebugLog("导入主题失败: " + String(e));
+      alert("导入主题失败: " + String(e));
+    }
+  };
 
-        const timestamp = Date.now();
-        const fileName = mermaidBlocks.length === 1 ? `${timestamp}.png` : `${timestamp}-${blockIndex + 1}.png`;
-        const fullPath = `${targetDir}${sep}${fileName}`;
+  const handleRemoveTheme = async (id: string) => {
+    const theme = themes.find((t) => t.id === id);
+    if (!theme || !confirm(`确定删除主题「${theme.name}」吗？`)) {
+      return;
+    }
+    try {
+      await removeTheme(id);
+      appendDebugLog(`已删除主题：${theme.name}`);
+      if (currentTheme === theme.name) {
+        setCurrentTheme("Default (Green)");
+      }
+    } catch (e) {
+      console.error("Remove theme failed", e);
+      alert("删除主题失败: " + String(e));
+    }
+  };
 
-        await invoke("save_binary_file", { path: fullPath, bytes: Array.from(pngBytes) });
+  const handleExportMermaidToPng = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("导出 Mermaid 图片仅在 Tauri 应用中可用。");
+        appendDebugLog("导出 Mermaid 图片失败：当前不在 Tauri 环境中。");
+        return;
+      }
 
-        const imageMarkdown = `![Mermaid 图](${assetsDir}/${fileName})`;
-        newMarkdown += imageMarkdown;
+      if (!markdown.includes("```mermaid")) {
+        alert("当前文档中没有 Mermaid 代码块。");
+        appendDebugLog("导出 Mermaid 图片失败：未找到 Mermaid 代码块。");
+        return;
+      }
 
-        lastIndex = matchEnd;
-        blockIndex += 1;
+      if (!currentFilePath) {
+        alert("请先保存 Markdown 文件，再导出 Mermaid 图片。");
+        appendDebugLog("导出 Mermaid 图片失败：当前文件尚未保存，无法确定图片目录。");
+        return;
       }
 
-      newMarkdown += markdown.slice(lastIndex);
-      setMarkdown(newMarkdown);
+      const baseDir = currentFilePath.replace(/[\\/][^\\/]*$/, "");
+      const result = await invoke<{ markdown: string; rendered: number; errors: string[] }>(
+        "render_mermaid_to_assets",
+        { markdown, baseDir, assetsDir, format: "png" },
+      );
+      setMarkdown(result.markdown);
+      for (const error of result.errors) {
+        appendDebugLog("Mermaid 图渲染失败: " + error);
+      }
 
-      alert(`Mermaid 图已导出为 PNG 并替换为图片引用（共 ${blockIndex} 个）。`);
-      appendDebugLog(`导出 Mermaid 图片并替换 Markdown 成功，数量: ${blockIndex}。`);
+      alert(`Mermaid 图已导出为 PNG 并替换为图片引用（共 ${result.rendered} 个）。`);
+      appendDebugLog(`导出 Mermaid 图片并替换 Markdown 成功，数量: ${result.rendered}。`);
     } catch (e) {
       console.error("Export Mermaid to PNG failed", e);
       appendDebugLog("导出 Mermaid 图片失败: " + String(e));
@@ -284,6 +665,45 @@ function App() {
     }
   };
 
+  const handleExportMathToPng = async () => {
+    try {
+      const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
+      if (!isTauri) {
+        alert("导出公式图片仅在 Tauri 应用中可用。");
+        appendDebugLog("导出公式图片失败：当前不在 Tauri 环境中。");
+        return;
+      }
+
+      if (!currentFilePath) {
+        alert("请先保存 Markdown 文件，再导出公式图片。");
+        appendDebugLog("导出公式图片失败：当前文件尚未保存，无法确定图片目录。");
+        return;
+      }
+
+      const baseDir = currentFilePath.replace(/[\\/][^\\/]*$/, "");
+      const result = await invoke<{ markdown: string; rendered: number; errors: string[] }>(
+        "render_math_to_assets",
+        { markdown, baseDir, assetsDir, format: "png" },
+      );
+      if (result.rendered === 0 && result.errors.length === 0) {
+        alert("当前文档中没有公式。");
+        appendDebugLog("导出公式图片失败：未找到公式。");
+        return;
+      }
+      setMarkdown(result.markdown);
+      for (const error of result.errors) {
+        appendDebugLog("公式渲染失败: " + error);
+      }
+
+      alert(`公式已导出为 PNG 并替换为图片引用（共 ${result.rendered} 个）。`);
+      appendDebugLog(`导出公式图片并替换 Markdown 成功，数量: ${result.rendered}。`);
+    } catch (e) {
+      console.error("Export math to PNG failed", e);
+      appendDebugLog("导出公式图片失败: " + String(e));
+      alert("导出公式图片失败");
+    }
+  };
+
   const handleUploadImagesToWechat = async () => {
     try {
       const isTauri = typeof window !== "undefined" && (window as any).__TAURI_INTERNALS__;
@@ -298,13 +718,6 @@ function App() {
         return;
       }
 
-      if (markdown.includes("```mermaid")) {
-        alert(
-          "检测到 Mermaid 代码块，请先使用工具栏的“导出 Mermaid 图片”按钮，将 Mermaid 图保存为 PNG 并替换为图片后，再上传到公众号。",
-        );
-        return;
-      }
-
       setIsUploadingWechatImages(true);
 
       let baseDir: string | null = null;
@@ -317,7 +730,9 @@ function App() {
         appId: wechatAppId,
         appSecret: wechatAppSecret,
         baseDir,
+        filePath: currentFilePath,
         sitePrefix,
+        assetsDir,
       });
 
       if (result && typeof result.markdown === "string") {
@@ -359,6 +774,7 @@ function App() {
       const result = await invoke<string>("localize_images_to_assets", {
         markdown,
         baseDir,
+        filePath: currentFilePath,
         sitePrefix,
         assetsDir,
       });
@@ -565,16 +981,20 @@ function App() {
         currentTheme={currentTheme}
         setCurrentTheme={setCurrentTheme}
         customTheme={customTheme}
+        themes={themes}
         setCustomTheme={(theme) => setCustomTheme(theme)}
         imagePrefix={imagePrefix}
         setImagePrefix={setImagePrefix}
         handleOpenMarkdown={handleOpenMarkdown}
+        handleOpenBlog={handleOpenBlog}
         handleSaveMarkdown={handleSaveMarkdown}
         handleGenerateSummary={handleGenerateSummary}
         handleGenerateCoverImage={handleGenerateCoverImage}
         handleLocalizeImages={handleLocalizeImages}
         handleExportMermaidToPng={handleExportMermaidToPng}
+        handleExportMathToPng={handleExportMathToPng}
         handleUploadImagesToWechat={handleUploadImagesToWechat}
+        handleCheckCompatibility={handleCheckCompatibility}
         copyToClipboard={copyToClipboard}
         toggleSettings={toggleSettings}
         isSummarizing={isSummarizing}
@@ -582,23 +1002,66 @@ function App() {
         isUploadingWechatImages={isUploadingWechatImages}
         activePage={activePage}
       />
+      {activePage === "editor" && (
+        <DraftRecovery
+          drafts={drafts}
+          onRestore={handleRestoreDraft}
+          onSaveAs={handleSaveDraftAs}
+          onDiscard={handleDiscardDraft}
+        />
+      )}
       {activePage === "editor" ? (
         <div className="workspace">
+          {blogPosts && (
+            <PostList
+              list={blogPosts}
+              query={blogQuery}
+              setQuery={setBlogQuery}
+              currentFilePath={currentFilePath}
+              onOpenPost={handleOpenPost}
+              onOpenSearchHit={openPostAt}
+              onClose={closeBlog}
+            />
+          )}
           <EditorPane
             markdown={markdown}
             setMarkdown={setMarkdown}
             summary={summary}
             isSummarizing={isSummarizing}
             currentFilePath={currentFilePath}
+            fileFormat={fileFormat}
+            stats={conversion?.stats ?? null}
+            warnings={conversion?.warnings ?? []}
+            jumpTo={jumpTo}
           />
           <PreviewPane html={html} previewRef={previewRef} />
         </div>
       ) : (
         <SettingsPane
           sitePrefix={sitePrefix}
+          siteProfile={siteProfile}
           setSitePrefix={setSitePrefix}
           assetsDir={assetsDir}
           setAssetsDir={setAssetsDir}
+          footnoteHeading={footnoteHeading}
+          setFootnoteHeading={setFootnoteHeading}
+          footnoteAllowlist={footnoteAllowlist}
+          tocNumbered={tocNumbered}
+          setTocNumbered={setTocNumbered}
+          darkModeHints={darkModeHints}
+          setDarkModeHints={setDarkModeHints}
+          backupCount={backupCount}
+          setBackupCount={setBackupCount}
+          normalizeOnSave={normalizeOnSave}
+          setNormalizeOnSave={setNormalizeOnSave}
+          fileFormat={fileFormat}
+          currentFilePath={currentFilePath}
+          backups={backups}
+          handleRestoreBackup={handleRestoreBackup}
+          workspaceRoots={workspaceRoots}
+          handleAddWorkspaceRoot={handleAddWorkspaceRoot}
+          handleRemoveWorkspaceRoot={removeWorkspaceRoot}
+          setFootnoteAllowlist={setFootnoteAllowlist}
           openaiUrl={openaiUrl}
           setOpenaiUrl={setOpenaiUrl}
           openaiToken={openaiToken}
@@ -625,6 +1088,13 @@ function App() {
           wechatTestStatus={wechatTestStatus}
           settingsSaveStatus={settingsSaveStatus}
           handleSettingsSave={handleSettingsSave}
+          themes={themes}
+          handleInstallTheme={handleInstallTheme}
+          handleRemoveTheme={handleRemoveTheme}
+          handleImportCommunityTheme={handleImportCommunityTheme}
+          handleSaveGeneratedTheme={handleSaveGeneratedTheme}
+          handleCheckContrast={handleCheckContrast}
+          handleTraceStyles={handleTraceStyles}
           debugLogs={debugLogs}
         />
       )}
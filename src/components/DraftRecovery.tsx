@@ -0,0 +1,40 @@
+import type { DraftInfo } from "../hooks/useAutosave";
+
+interface DraftRecoveryProps {
+  drafts: DraftInfo[];
+  onRestore: (draft: DraftInfo) => void;
+  onSaveAs: (draft: DraftInfo) => void;
+  onDiscard: (draft: DraftInfo) => void;
+}
+
+/** Unsaved buffers found in the draft store, shown until each is handled */
+export function DraftRecovery({ drafts, onRestore, onSaveAs, onDiscard }: DraftRecoveryProps) {
+  if (drafts.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="draft-recovery">
+      <div className="draft-recovery-header">发现 {drafts.length} 份未保存的草稿</div>
+      {drafts.map((draft) => (
+        <div key={draft.id} className="draft-recovery-item">
+          <div className="draft-recovery-info" title={draft.preview}>
+            <div className="post-list-title">{draft.title}</div>
+            <div className="post-list-meta">
+              {new Date(draft.updated).toLocaleString()} · {draft.path ?? "从未保存"}
+            </div>
+          </div>
+          <button className="btn" onClick={() => onRestore(draft)}>
+            恢复
+          </button>
+          <button className="btn" onClick={() => onSaveAs(draft)}>
+            另存为
+          </button>
+          <button className="btn" onClick={() => onDiscard(draft)}>
+            丢弃
+          </button>
+        </div>
+      ))}
+    </div>
+  );
+}
@@ -1,9 +1,18 @@
+import { useEffect, useRef } from "react";
+import type { ConvertWarning, DocumentStats } from "../hooks/useMarkdownConverter";
+import { describeTextFormat, type TextFormat } from "../hooks/useFileFormat";
+
 interface EditorPaneProps {
   markdown: string;
   setMarkdown: (value: string) => void;
   summary: string;
   isSummarizing: boolean;
   currentFilePath: string | null;
+  fileFormat: TextFormat | null;
+  stats: DocumentStats | null;
+  warnings: ConvertWarning[];
+  /** Select a 1-based line once, e.g. after opening a search result; `nonce` repeats a jump */
+  jumpTo?: { line: number; nonce: number } | null;
 }
 
 export function EditorPane({
@@ -12,12 +21,43 @@ export function EditorPane({
   summary,
   isSummarizing,
   currentFilePath,
+  fileFormat,
+  stats,
+  warnings,
+  jumpTo,
 }: EditorPaneProps) {
+  const textareaRef = useRef<HTMLTextAreaElement>(null);
+
+  useEffect(() => {
+    const textarea = textareaRef.current;
+    if (!jumpTo || !textarea) {
+      return;
+    }
+    const lines = textarea.value.split("\n");
+    const index = Math.min(Math.max(jumpTo.line, 1), lines.length) - 1;
+    const start = lines.slice(0, index).reduce((offset, line) => offset + line.length + 1, 0);
+    textarea.focus();
+    textarea.setSelectionRange(start, start + lines[index].length);
+    const lineHeight = parseFloat(getComputedStyle(textarea).lineHeight) || 20;
+    textarea.scrollTop = Math.max(0, index * lineHeight - textarea.clientHeight / 3);
+  }, [jumpTo]);
+
   return (
     <div className="editor-pane">
       <div className="editor-header">
         <span>Markdown Editor</span>
-        <span>{currentFilePath ? currentFilePath.split(/[\\/]/).pop() : "Untitled"}</span>
+        <span title={fileFormat ? describeTextFormat(fileFormat) : undefined}>
+          {currentFilePath ? currentFilePath.split(/[\\/]/).pop() : "Untitled"}
+        </span>
+        {stats && (
+          <span
+            className="editor-stats"
+            title={warnings.map((w) => (w.line > 0 ? `第 ${w.line} 行：${w.message}` : w.message)).join("\n")}
+          >
+            {stats.characters} 字 · 约 {stats.reading_minutes} 分钟
+            {warnings.length > 0 ? ` · ${warnings.length} 个提示` : ""}
+          </span>
+        )}
       </div>
       <div className="summary-panel">
         <div className="summary-title">AI 摘要（≤100字）</div>
@@ -26,6 +66,7 @@ export function EditorPane({
         </div>
       </div>
       <textarea
+        ref={textareaRef}
         value={markdown}
         onChange={(e) => setMarkdown(e.target.value)}
         placeholder="Type Markdown here..."
@@ -0,0 +1,165 @@
+import { X } from "lucide-react";
+import type { BlogPost, BlogPostList, BlogPostQuery } from "../hooks/useBlogIndex";
+import { useFullTextSearch } from "../hooks/useFullTextSearch";
+
+interface PostListProps {
+  list: BlogPostList;
+  query: BlogPostQuery;
+  setQuery: (query: BlogPostQuery) => void;
+  currentFilePath: string | null;
+  onOpenPost: (post: BlogPost) => void;
+  /** Open a file from the search results at a 1-based line */
+  onOpenSearchHit: (path: string, line: number | null) => void;
+  onClose: () => void;
+}
+
+/** Parse a `<select>` value for an optional boolean filter */
+function parseFilter(value: string): boolean | undefined {
+  return value === "" ? undefined : value === "true";
+}
+
+export function PostList({
+  list,
+  query,
+  setQuery,
+  currentFilePath,
+  onOpenPost,
+  onOpenSearchHit,
+  onClose,
+}: PostListProps) {
+  const update = (patch: BlogPostQuery) => setQuery({ ...query, ...patch });
+  const fullText = useFullTextSearch(list.root);
+
+  return (
+    <div className="post-list">
+      <div className="editor-header">
+        <span title={list.root}>
+          文章 {list.posts.length} / {list.total}
+        </span>
+        <button className="btn btn-icon" onClick={onClose} title="关闭博客目录">
+          <X size={14} />
+        </button>
+      </div>
+      <div className="post-list-filters">
+        <input
+          className="input"
+          type="search"
+          value={fullText.query}
+          onChange={(e) => fullText.setQuery(e.target.value)}
+          placeholder="全文搜索"
+        />
+      </div>
+      {fullText.query.trim() ? (
+        <div className="post-list-items">
+          {fullText.error && <div className="post-list-meta post-list-notice">{fullText.error}</div>}
+          {fullText.results && (
+            <div className="post-list-meta post-list-notice">
+              在 {fullText.results.indexed} 篇文章中找到 {fullText.results.hits.length} 篇
+            </div>
+          )}
+          {fullText.results?.hits.map((hit) => (
+            <div
+              key={hit.path}
+              className={`post-list-item${hit.path === currentFilePath ? " active" : ""}`}
+              onClick={() => onOpenSearchHit(hit.path, hit.snippets[0]?.line ?? null)}
+              title={hit.relative_path}
+            >
+              <div className="post-list-title">{hit.title}</div>
+              {hit.snippets.map((snippet) => (
+                <div
+                  key={snippet.line}
+                  className="post-list-meta search-snippet"
+                  onClick={(e) => {
+                    e.stopPropagation();
+                    onOpenSearchHit(hit.path, snippet.line);
+                  }}
+                >
+                  {snippet.line}: {snippet.text}
+                </div>
+              ))}
+            </div>
+          ))}
+        </div>
+      ) : (
+        <>
+          <div className="post-list-filters">
+            <input
+              className="input"
+              type="text"
+              value={query.search ?? ""}
+              onChange={(e) => update({ search: e.target.value })}
+              placeholder="搜索标题、路径或标签"
+            />
+            <select
+              className="select"
+              value={query.tag ?? ""}
+              onChange={(e) => update({ tag: e.target.value || undefined })}
+            >
+              <option value="">全部标签</option>
+              {list.tags.map((tag) => (
+                <option key={tag} value={tag}>
+                  {tag}
+                </option>
+              ))}
+            </select>
+            <select
+              className="select"
+              value={query.draft === undefined ? "" : String(query.draft)}
+              onChange={(e) => update({ draft: parseFilter(e.target.value) })}
+            >
+              <option value="">草稿与正式</option>
+              <option value="false">仅正式</option>
+              <option value="true">仅草稿</option>
+            </select>
+            <select
+              className="select"
+              value={query.published === undefined ? "" : String(query.published)}
+              onChange={(e) => update({ published: parseFilter(e.target.value) })}
+            >
+              <option value="">全部</option>
+              <option value="true">已发到微信</option>
+              <option value="false">未发到微信</option>
+            </select>
+            <select
+              className="select"
+              value={`${query.sortBy ?? "date"}:${query.ascending ? "asc" : "desc"}`}
+              onChange={(e) => {
+                const [sortBy, order] = e.target.value.split(":");
+                update({ sortBy: sortBy as BlogPostQuery["sortBy"], ascending: order === "asc" });
+              }}
+            >
+              <option value="date:desc">日期（新到旧）</option>
+              <option value="date:asc">日期（旧到新）</option>
+              <option value="title:asc">标题</option>
+              <option value="modified:desc">最近修改</option>
+            </select>
+          </div>
+          <div className="post-list-items">
+            {list.posts.map((post) => (
+              <div
+                key={post.path}
+                className={`post-list-item${post.path === currentFilePath ? " active" : ""}`}
+                onClick={() => onOpenPost(post)}
+                title={post.error ?? post.relative_path}
+              >
+                <div className="post-list-title">{post.title}</div>
+                <div className="post-list-meta">
+                  {[
+                    post.date?.slice(0, 10),
+                    post.draft && "草稿",
+                    post.published_to_wechat
+                      ? "已发微信"
+                      : post.wechat_images > 0 && `微信图片 ${post.wechat_images}/${post.images}`,
+                    post.tags.join(", "),
+                  ]
+                    .filter(Boolean)
+                    .join(" · ")}
+                </div>
+              </div>
+            ))}
+          </div>
+        </>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,104 @@
+import { useState } from "react";
+import { DEFAULT_THEME_TOKENS, type ThemeTokens } from "../../hooks/useThemeRegistry";
+
+interface ThemeGeneratorProps {
+  onSave: (name: string, tokens: ThemeTokens) => void;
+}
+
+/** Build a theme from a primary colour, type scale and a few style choices */
+export function ThemeGenerator({ onSave }: ThemeGeneratorProps) {
+  const [name, setName] = useState("我的主题");
+  const [tokens, setTokens] = useState<ThemeTokens>(DEFAULT_THEME_TOKENS);
+
+  const update = <K extends keyof ThemeTokens>(key: K, value: ThemeTokens[K]) => {
+    setTokens((prev) => ({ ...prev, [key]: value }));
+  };
+
+  return (
+    <div className="settings-section">
+      <div className="settings-section-title">主题生成器</div>
+      <div className="settings-field">
+        <label className="settings-label">主题名称</label>
+        <input className="input settings-input" type="text" value={name} onChange={(e) => setName(e.target.value)} />
+      </div>
+      <div className="settings-field">
+        <label className="settings-label">主题色</label>
+        <input
+          className="input settings-input"
+          type="color"
+          value={tokens.primaryColor}
+          onChange={(e) => update("primaryColor", e.target.value)}
+        />
+      </div>
+      <div className="settings-field">
+        <label className="settings-label">正文字号（px）</label>
+        <input
+          className="input settings-input"
+          type="number"
+          min={12}
+          max={24}
+          value={tokens.fontSize}
+          onChange={(e) => update("fontSize", Number(e.target.value))}
+        />
+      </div>
+      <div className="settings-field">
+        <label className="settings-label">行高</label>
+        <input
+          className="input settings-input"
+          type="number"
+          min={1.2}
+          max={2.5}
+          step={0.05}
+          value={tokens.lineHeight}
+          onChange={(e) => update("lineHeight", Number(e.target.value))}
+        />
+      </div>
+      <div className="settings-field">
+        <label className="settings-label">二级标题</label>
+        <select
+          className="input settings-input"
+          value={tokens.headingStyle}
+          onChange={(e) => update("headingStyle", e.target.value as ThemeTokens["headingStyle"])}
+        >
+          <option value="left-bar">左侧竖线</option>
+          <option value="underline">下划线</option>
+          <option value="filled">色块</option>
+          <option value="centered">居中</option>
+          <option value="plain">仅颜色</option>
+        </select>
+      </div>
+      <div className="settings-field">
+        <label className="settings-label">代码块</label>
+        <select
+          className="input settings-input"
+          value={tokens.codeStyle}
+          onChange={(e) => update("codeStyle", e.target.value as ThemeTokens["codeStyle"])}
+        >
+          <option value="light">浅色</option>
+          <option value="dark">深色</option>
+          <option value="bordered">边框</option>
+        </select>
+      </div>
+      <div className="settings-field">
+        <label className="settings-label">引用</label>
+        <select
+          className="input settings-input"
+          value={tokens.quoteStyle}
+          onChange={(e) => update("quoteStyle", e.target.value as ThemeTokens["quoteStyle"])}
+        >
+          <option value="bar">竖线</option>
+          <option value="card">卡片</option>
+          <option value="italic">斜体</option>
+        </select>
+      </div>
+      <div className="settings-field settings-test-row">
+        <button className="btn" onClick={() => onSave(name.trim() || "我的主题", tokens)}>
+          生成并使用
+        </button>
+      </div>
+      <div className="settings-field-hint">
+        单篇文章可在 Front Matter 中用 theme_primary_color、theme_font_size、theme_line_height、theme_heading_style、theme_code_style、theme_quote_style 覆盖这些参数
+      </div>
+    </div>
+  );
+}
@@ -1,10 +1,35 @@
-import { Save } from "lucide-react";
+import { Save, Trash2 } from "lucide-react";
+import type { ThemeInfo, ThemeTokens } from "../../hooks/useThemeRegistry";
+import type { BackupInfo } from "../../hooks/useFileBackups";
+import { describeTextFormat, type TextFormat } from "../../hooks/useFileFormat";
+import type { SiteProfileInfo } from "../../hooks/useSiteProfile";
+import { ThemeGenerator } from "./ThemeGenerator";
 
 interface SettingsPaneProps {
   sitePrefix: string;
+  siteProfile: SiteProfileInfo | null;
   setSitePrefix: (value: string) => void;
   assetsDir: string;
   setAssetsDir: (value: string) => void;
+  footnoteHeading: string;
+  setFootnoteHeading: (value: string) => void;
+  footnoteAllowlist: string;
+  setFootnoteAllowlist: (value: string) => void;
+  tocNumbered: string;
+  setTocNumbered: (value: string) => void;
+  darkModeHints: string;
+  setDarkModeHints: (value: string) => void;
+  backupCount: string;
+  setBackupCount: (value: string) => void;
+  normalizeOnSave: string;
+  setNormalizeOnSave: (value: string) => void;
+  fileFormat: TextFormat | null;
+  currentFilePath: string | null;
+  backups: BackupInfo[];
+  handleRestoreBackup: (backup: BackupInfo) => void;
+  workspaceRoots: string[];
+  handleAddWorkspaceRoot: () => void;
+  handleRemoveWorkspaceRoot: (path: string) => void;
   openaiUrl: string;
   setOpenaiUrl: (value: string) => void;
   openaiToken: string;
@@ -31,14 +56,48 @@ interface SettingsPaneProps {
   wechatTestStatus: string;
   settingsSaveStatus: string;
   handleSettingsSave: () => void;
+  themes: ThemeInfo[];
+  handleInstallTheme: () => void;
+  handleRemoveTheme: (id: string) => void;
+  handleImportCommunityTheme: () => void;
+  handleSaveGeneratedTheme: (name: string, tokens: ThemeTokens) => void;
+  handleCheckContrast: () => void;
+  handleTraceStyles: () => void;
   debugLogs: string[];
 }
 
+const SITE_GENERATOR_NAMES: Record<SiteProfileInfo["generator"], string> = {
+  plain: "普通",
+  hexo: "Hexo",
+  hugo: "Hugo",
+  jekyll: "Jekyll",
+};
+
 export function SettingsPane({
   sitePrefix,
+  siteProfile,
   setSitePrefix,
   assetsDir,
   setAssetsDir,
+  footnoteHeading,
+  setFootnoteHeading,
+  footnoteAllowlist,
+  setFootnoteAllowlist,
+  tocNumbered,
+  setTocNumbered,
+  darkModeHints,
+  setDarkModeHints,
+  backupCount,
+  setBackupCount,
+  normalizeOnSave,
+  setNormalizeOnSave,
+  fileFormat,
+  currentFilePath,
+  backups,
+  handleRestoreBackup,
+  workspaceRoots,
+  handleAddWorkspaceRoot,
+  handleRemoveWorkspaceRoot,
   openaiUrl,
   setOpenaiUrl,
   openaiToken,
@@ -65,6 +124,13 @@ export function SettingsPane({
   wechatTestStatus,
   settingsSaveStatus,
   handleSettingsSave,
+  themes,
+  handleInstallTheme,
+  handleRemoveTheme,
+  handleImportCommunityTheme,
+  handleSaveGeneratedTheme,
+  handleCheckContrast,
+  handleTraceStyles,
   debugLogs,
 }: SettingsPaneProps) {
   return (
@@ -91,6 +157,13 @@ export function SettingsPane({
             onChange={(e) => setSitePrefix(e.target.value)}
             placeholder="例如：https://example.com"
           />
+          <div className="settings-field-hint">
+            {siteProfile && siteProfile.generator !== "plain"
+              ? `当前文章属于 ${SITE_GENERATOR_NAMES[siteProfile.generator]} 站点（${siteProfile.root}），` +
+                `图片按其目录约定查找，发布地址：${siteProfile.baseUrl ?? "未知，请在站点配置中设置网址或填写网站前缀"}` +
+                (siteProfile.postAssetFolder ? "；已启用 post_asset_folder" : "")
+              : "未检测到 Hexo、Hugo 或 Jekyll 配置时，本地找不到的图片会从“网站前缀 + 图片路径”下载"}
+          </div>
         </div>
         <div className="settings-field">
           <label className="settings-label">图片保存目录</label>
@@ -105,6 +178,167 @@ export function SettingsPane({
         </div>
       </div>
 
+      <div className="settings-section">
+        <div className="settings-section-title">排版配置</div>
+        <div className="settings-field">
+          <label className="settings-label">链接脚注标题</label>
+          <input
+            className="input settings-input"
+            type="text"
+            value={footnoteHeading}
+            onChange={(e) => setFootnoteHeading(e.target.value)}
+            placeholder="默认：参考资料"
+          />
+        </div>
+        <div className="settings-field">
+          <label className="settings-label">保留可点击链接的域名</label>
+          <input
+            className="input settings-input"
+            type="text"
+            value={footnoteAllowlist}
+            onChange={(e) => setFootnoteAllowlist(e.target.value)}
+            placeholder="多个域名用逗号分隔，例如：example.com, github.com"
+          />
+          <div className="settings-field-hint">mp.weixin.qq.com 的链接始终保持可点击，其余外链转换为文末脚注</div>
+        </div>
+        <div className="settings-field">
+          <label className="settings-label">目录编号</label>
+          <select
+            className="input settings-input"
+            value={tocNumbered}
+            onChange={(e) => setTocNumbered(e.target.value)}
+          >
+            <option value="false">不编号</option>
+            <option value="true">按层级编号（1.1、1.2…）</option>
+          </select>
+          <div className="settings-field-hint">在正文中写一行 [TOC]，或在 Front Matter 中设置 toc: true 即可生成目录</div>
+        </div>
+        <div className="settings-field">
+          <label className="settings-label">深色模式适配</label>
+          <select
+            className="input settings-input"
+            value={darkModeHints}
+            onChange={(e) => setDarkModeHints(e.target.value)}
+          >
+            <option value="false">交给微信自动反色</option>
+            <option value="true">写入深色模式颜色（data-darkmode-*）</option>
+          </select>
+          <div className="settings-field-hint">为带颜色的元素指定深色模式下的文字和背景色，避免标题、代码块反色后看不清；反色效果不佳的主题颜色会在状态栏中提示</div>
+        </div>
+      </div>
+
+      <div className="settings-section">
+        <div className="settings-section-title">主题管理</div>
+        {themes.map((theme) => (
+          <div className="settings-field settings-theme-row" key={theme.id}>
+            <div>
+              <span className="settings-label">{theme.name}</span>
+              <div className="settings-field-hint">
+                {[
+                  theme.builtin ? "内置" : theme.author,
+                  theme.version && `v${theme.version}`,
+                  theme.base && `基于 ${theme.base}`,
+                  theme.description,
+                ]
+                  .filter(Boolean)
+                  .join(" · ")}
+              </div>
+            </div>
+            {!theme.builtin && (
+              <button className="btn btn-icon" onClick={() => handleRemoveTheme(theme.id)} title="删除主题">
+                <Trash2 size={16} />
+              </button>
+            )}
+          </div>
+        ))}
+        <div className="settings-field settings-test-row">
+          <button className="btn" onClick={handleInstallTheme}>
+            安装主题
+          </button>
+          <button className="btn" onClick={handleImportCommunityTheme}>
+            导入 mdnice / doocs 主题
+          </button>
+          <button className="btn" onClick={handleCheckContrast}>
+            检查当前主题对比度
+          </button>
+        </div>
+        <div className="settings-field-hint">
+          支持 .zip 主题包（含 theme.json 与 CSS）、theme.json 或单个 .css 文件；主题目录中的修改会自动生效
+        </div>
+      </div>
+
+      <ThemeGenerator onSave={handleSaveGeneratedTheme} />
+
+      <div className="settings-section">
+        <div className="settings-section-title">工作区</div>
+        {workspaceRoots.map((root) => (
+          <div className="settings-field settings-theme-row" key={root}>
+            <span className="settings-label">{root}</span>
+            <button className="btn btn-icon" onClick={() => handleRemoveWorkspaceRoot(root)} title="移出工作区">
+              <Trash2 size={16} />
+            </button>
+          </div>
+        ))}
+        <div className="settings-field settings-test-row">
+          <button className="btn" onClick={handleAddWorkspaceRoot}>
+            添加工作区目录
+          </button>
+        </div>
+        <div className="settings-field-hint">
+          应用只读写这些目录中的文件；通过“打开”或“另存为”选择文件时，其所在目录会自动加入
+        </div>
+      </div>
+
+      <div className="settings-section">
+        <div className="settings-section-title">文件备份</div>
+        <div className="settings-field">
+          <label className="settings-label">每个文件保留的备份数</label>
+          <input
+            className="input settings-input"
+            type="number"
+            min={0}
+            value={backupCount}
+            onChange={(e) => setBackupCount(e.target.value)}
+          />
+          <div className="settings-field-hint">
+            保存时先把旧内容复制到文件旁的 .wxtyper-backups 目录，超出数量的旧备份会被删除；设为 0 不保留备份
+          </div>
+        </div>
+        {currentFilePath && backups.length === 0 && (
+          <div className="settings-field-hint">当前文件还没有备份</div>
+        )}
+        {backups.map((backup) => (
+          <div className="settings-field settings-theme-row" key={backup.path}>
+            <div>
+              <span className="settings-label">{new Date(backup.timestamp).toLocaleString()}</span>
+              <div className="settings-field-hint">{(backup.size / 1024).toFixed(1)} KB</div>
+            </div>
+            <button className="btn" onClick={() => handleRestoreBackup(backup)}>
+              恢复
+            </button>
+          </div>
+        ))}
+      </div>
+
+      <div className="settings-section">
+        <div className="settings-section-title">文件编码</div>
+        <div className="settings-field">
+          <label className="settings-label">保存时的编码与换行</label>
+          <select
+            className="input settings-input"
+            value={normalizeOnSave}
+            onChange={(e) => setNormalizeOnSave(e.target.value)}
+          >
+            <option value="false">保持文件原有的编码和换行符</option>
+            <option value="true">统一为 UTF-8（无 BOM）和 LF</option>
+          </select>
+          <div className="settings-field-hint">
+            打开时会自动识别 UTF-8、UTF-16 和 GBK/GB18030 编码
+            {fileFormat && `；当前文件：${describeTextFormat(fileFormat)}`}
+          </div>
+        </div>
+      </div>
+
       <div className="settings-section">
         <div className="settings-section-title">OpenAI 兼容接口配置</div>
         <div className="settings-field">
@@ -241,6 +475,12 @@ export function SettingsPane({
             )}
           </div>
         </div>
+        <div className="settings-field settings-test-row">
+          <button className="btn" onClick={handleTraceStyles}>
+            追踪样式来源
+          </button>
+          <span className="settings-field-hint">将当前文章每个元素的样式属性来自内联、主题还是兜底样式写入日志</span>
+        </div>
       </div>
     </div>
   );
@@ -1,21 +1,26 @@
-import { FileText, Save, Palette, Image as IconImage, Sparkles, ImagePlus, FolderDown, Download, Share, Copy, Settings } from "lucide-react";
+import { FileText, FolderOpen, Save, Palette, Image as IconImage, Sparkles, ImagePlus, FolderDown, Download, Share, Copy, Settings, ShieldCheck, Sigma } from "lucide-react";
 import { builtinThemes } from "../constants/themes";
 import type { CustomTheme } from "../hooks/useMarkdownConverter";
+import type { ThemeInfo } from "../hooks/useThemeRegistry";
 
 interface ToolbarProps {
   currentTheme: string;
   setCurrentTheme: (value: string) => void;
   customTheme: CustomTheme | null;
+  themes: ThemeInfo[];
   setCustomTheme: (theme: CustomTheme) => void;
   imagePrefix: string;
   setImagePrefix: (value: string) => void;
   handleOpenMarkdown: () => void;
+  handleOpenBlog: () => void;
   handleSaveMarkdown: () => void;
   handleGenerateSummary: () => void;
   handleGenerateCoverImage: () => void;
   handleLocalizeImages: () => void;
   handleExportMermaidToPng: () => void;
+  handleExportMathToPng: () => void;
   handleUploadImagesToWechat: () => void;
+  handleCheckCompatibility: () => void;
   copyToClipboard: () => void;
   toggleSettings: () => void;
   isSummarizing: boolean;
@@ -28,16 +33,20 @@ export function Toolbar({
   currentTheme,
   setCurrentTheme,
   customTheme,
+  themes,
   setCustomTheme,
   imagePrefix,
   setImagePrefix,
   handleOpenMarkdown,
+  handleOpenBlog,
   handleSaveMarkdown,
   handleGenerateSummary,
   handleGenerateCoverImage,
   handleLocalizeImages,
   handleExportMermaidToPng,
+  handleExportMathToPng,
   handleUploadImagesToWechat,
+  handleCheckCompatibility,
   copyToClipboard,
   toggleSettings,
   isSummarizing,
@@ -56,6 +65,9 @@ export function Toolbar({
         <button className="btn btn-icon" onClick={handleOpenMarkdown} title="Open Markdown">
           <FileText size={18} />
         </button>
+        <button className="btn btn-icon" onClick={handleOpenBlog} title="Open Blog Folder">
+          <FolderOpen size={18} />
+        </button>
         <button className="btn btn-icon" onClick={handleSaveMarkdown} title="Save Markdown">
           <Save size={18} />
         </button>
@@ -69,11 +81,17 @@ export function Toolbar({
             onChange={(e) => setCurrentTheme(e.target.value)}
             title="Select Theme"
           >
-            {Object.keys(builtinThemes).map((themeName) => (
-              <option key={themeName} value={themeName}>
-                {themeName}
-              </option>
-            ))}
+            {themes.length === 0
+              ? Object.keys(builtinThemes).map((themeName) => (
+                  <option key={themeName} value={themeName}>
+                    {themeName}
+                  </option>
+                ))
+              : themes.map((theme) => (
+                  <option key={theme.id} value={theme.name}>
+                    {theme.name}
+                  </option>
+                ))}
             {customTheme && (
               <option value={customTheme.name}>
                 {customTheme.name}
@@ -160,6 +178,14 @@ export function Toolbar({
           <Download size={18} />
         </button>
 
+        <button
+          className="btn btn-icon"
+          onClick={handleExportMathToPng}
+          title="将文中的公式导出为 PNG 图片"
+        >
+          <Sigma size={18} />
+        </button>
+
         <button
           className="btn btn-icon"
           onClick={handleUploadImagesToWechat}
@@ -169,6 +195,14 @@ export function Toolbar({
           <Share size={18} />
         </button>
 
+        <button
+          className="btn btn-icon"
+          onClick={handleCheckCompatibility}
+          title="检查微信兼容性（脚本、外链、定位样式、SVG 等）"
+        >
+          <ShieldCheck size={18} />
+        </button>
+
         <div className="divider-vertical"></div>
 
         <button className="btn btn-primary btn-icon" onClick={copyToClipboard} title="Copy HTML">
@@ -11,3 +11,10 @@ export const builtinThemes = {
 } as const;
 
 export type BuiltinThemeName = keyof typeof builtinThemes;
+
+export const builtinHighlightPalettes: Record<BuiltinThemeName, string> = {
+  "Default (Green)": "github",
+  "Lapis (Blue)": "ocean-dark",
+  "Sakura (Pink)": "solarized-light",
+  "Tech (Dark)": "ocean-dark",
+};
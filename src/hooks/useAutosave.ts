@@ -0,0 +1,102 @@
+import { useCallback, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface DraftInfo {
+  id: string;
+  /** File the draft belongs to; null for a post never saved */
+  path: string | null;
+  title: string;
+  /** Milliseconds since the Unix epoch of the last autosave */
+  updated: number;
+  size: number;
+  preview: string;
+}
+
+/** How long typing has to pause before the buffer is journalled */
+const AUTOSAVE_DELAY_MS = 3000;
+
+/**
+ * Journals the editor buffer to the backend draft store while it differs
+ * from what was last opened or saved, and lists drafts left by a crash.
+ */
+export function useAutosave(content: string, filePath: string | null, initialContent: string) {
+  const [drafts, setDrafts] = useState<DraftInfo[]>([]);
+  const draftId = useRef<string | null>(null);
+  const cleanContent = useRef(initialContent);
+
+  const refresh = useCallback(async () => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__) {
+      return;
+    }
+    try {
+      setDrafts(await invoke<DraftInfo[]>("list_drafts"));
+    } catch (e) {
+      console.error("Failed to list drafts", e);
+    }
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  useEffect(() => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__ || content === cleanContent.current) {
+      return;
+    }
+    const timer = setTimeout(async () => {
+      // Saved while the timer was pending
+      if (content === cleanContent.current) {
+        return;
+      }
+      try {
+        draftId.current = await invoke<string>("autosave_draft", {
+          id: draftId.current,
+          path: filePath,
+          content,
+        });
+      } catch (e) {
+        console.error("Autosave failed", e);
+      }
+    }, AUTOSAVE_DELAY_MS);
+    return () => clearTimeout(timer);
+  }, [content, filePath]);
+
+  /** The buffer now matches a file on disk, so its draft is no longer needed */
+  const markClean = useCallback(async (saved: string) => {
+    cleanContent.current = saved;
+    const id = draftId.current;
+    draftId.current = null;
+    if (id) {
+      try {
+        await invoke("discard_draft", { id });
+      } catch (e) {
+        console.error("Failed to discard draft", e);
+      }
+    }
+  }, []);
+
+  /**
+   * The editor switched to another buffer whose on-disk version is `clean`.
+   * The previous buffer's draft stays in the store; pass `resumeId` to keep
+   * journalling into a recovered draft.
+   */
+  const startBuffer = useCallback((clean: string, resumeId: string | null = null) => {
+    cleanContent.current = clean;
+    draftId.current = resumeId;
+    if (resumeId) {
+      setDrafts((prev) => prev.filter((d) => d.id !== resumeId));
+    }
+  }, []);
+
+  /** Remove a draft from the recovery list, deleting it unless already gone */
+  const dismissDraft = useCallback(async (id: string, deleted = false) => {
+    if (!deleted) {
+      await invoke("discard_draft", { id });
+    }
+    setDrafts((prev) => prev.filter((d) => d.id !== id));
+  }, []);
+
+  return { drafts, refresh, markClean, startBuffer, dismissDraft };
+}
@@ -0,0 +1,73 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface BlogPost {
+  path: string;
+  relative_path: string;
+  title: string;
+  date: string | null;
+  tags: string[];
+  draft: boolean;
+  published_to_wechat: boolean;
+  images: number;
+  wechat_images: number;
+  modified: number;
+  error: string | null;
+}
+
+export interface BlogPostQuery {
+  search?: string;
+  tag?: string;
+  draft?: boolean;
+  published?: boolean;
+  sortBy?: "date" | "title" | "modified";
+  ascending?: boolean;
+}
+
+export interface BlogPostList {
+  root: string;
+  posts: BlogPost[];
+  total: number;
+  tags: string[];
+}
+
+/** Posts of the opened blog checkout, re-queried when the filters change or the window regains focus */
+export function useBlogIndex() {
+  const [list, setList] = useState<BlogPostList | null>(null);
+  const [query, setQuery] = useState<BlogPostQuery>({ sortBy: "date" });
+  const isOpen = list !== null;
+
+  const refresh = useCallback(async () => {
+    if (!isOpen) {
+      return;
+    }
+    try {
+      setList(await invoke<BlogPostList>("list_blog_posts", { query }));
+    } catch (e) {
+      console.error("Failed to list blog posts", e);
+      setList(null);
+    }
+  }, [query, isOpen]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  useEffect(() => {
+    window.addEventListener("focus", refresh);
+    return () => window.removeEventListener("focus", refresh);
+  }, [refresh]);
+
+  /** Ask for a blog root in a native dialog and index it; `null` when cancelled */
+  const openBlog = async () => {
+    const opened = await invoke<BlogPostList | null>("open_blog_workspace", { query });
+    if (opened) {
+      setList(opened);
+    }
+    return opened;
+  };
+
+  const closeBlog = () => setList(null);
+
+  return { list, query, setQuery, refresh, openBlog, closeBlog };
+}
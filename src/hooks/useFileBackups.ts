@@ -0,0 +1,33 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface BackupInfo {
+  path: string;
+  /** Milliseconds since the Unix epoch */
+  timestamp: number;
+  size: number;
+}
+
+/** Backups of the open file, kept in the hidden folder next to it */
+export function useFileBackups(filePath: string | null) {
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+
+  const refresh = useCallback(async () => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__ || !filePath) {
+      setBackups([]);
+      return;
+    }
+    try {
+      setBackups(await invoke<BackupInfo[]>("list_file_backups", { path: filePath }));
+    } catch (e) {
+      console.error("Failed to list backups", e);
+    }
+  }, [filePath]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  return { backups, refresh };
+}
@@ -0,0 +1,44 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface TextFormat {
+  encoding: "utf8" | "gb18030" | "utf16le" | "utf16be";
+  bom: boolean;
+  lineEnding: "lf" | "crlf";
+}
+
+const ENCODING_LABELS: Record<TextFormat["encoding"], string> = {
+  utf8: "UTF-8",
+  gb18030: "GBK/GB18030",
+  utf16le: "UTF-16 LE",
+  utf16be: "UTF-16 BE",
+};
+
+export function describeTextFormat(format: TextFormat): string {
+  const bom = format.bom && format.encoding === "utf8" ? " BOM" : "";
+  return `${ENCODING_LABELS[format.encoding]}${bom} · ${format.lineEnding.toUpperCase()}`;
+}
+
+/** Encoding and line endings the open file was read in and will be saved with */
+export function useFileFormat(filePath: string | null) {
+  const [format, setFormat] = useState<TextFormat | null>(null);
+
+  const refresh = useCallback(async () => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__ || !filePath) {
+      setFormat(null);
+      return;
+    }
+    try {
+      setFormat(await invoke<TextFormat>("get_file_format", { path: filePath }));
+    } catch (e) {
+      console.error("Failed to read file format", e);
+    }
+  }, [filePath]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  return { format, refresh };
+}
@@ -0,0 +1,48 @@
+import { useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface FileChangeEvent {
+  path: string;
+  kind: "modify" | "rename" | "delete" | "create";
+}
+
+/** Start of the error `save_markdown_file` returns when the file changed on disk */
+export const FILE_CONFLICT_ERROR = "文件已在外部修改";
+
+interface FileWatcherHandlers {
+  onFileChanged: (event: FileChangeEvent) => void;
+  onAssetsChanged: (event: FileChangeEvent) => void;
+}
+
+/** Ask the backend to watch the open file and its assets directory for changes made by other programs */
+export function useFileWatcher(filePath: string | null, assetsDir: string, handlers: FileWatcherHandlers) {
+  const handlersRef = useRef(handlers);
+  handlersRef.current = handlers;
+
+  useEffect(() => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__) {
+      return;
+    }
+    const command = filePath ? invoke("watch_open_file", { path: filePath, assetsDir }) : invoke("unwatch_open_file");
+    command.catch((e) => console.error("Failed to watch file", e));
+  }, [filePath, assetsDir]);
+
+  useEffect(() => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__) {
+      return;
+    }
+    const unlistenFile = listen<FileChangeEvent>("file-changed", (event) => {
+      handlersRef.current.onFileChanged(event.payload);
+    });
+    const unlistenAssets = listen<FileChangeEvent>("assets-changed", (event) => {
+      handlersRef.current.onAssetsChanged(event.payload);
+    });
+    return () => {
+      unlistenFile.then((stop) => stop());
+      unlistenAssets.then((stop) => stop());
+    };
+  }, []);
+}
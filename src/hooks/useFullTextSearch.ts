@@ -0,0 +1,61 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface SearchSnippet {
+  /** 1-based line number in the file */
+  line: number;
+  text: string;
+}
+
+export interface SearchHit {
+  path: string;
+  relative_path: string;
+  title: string;
+  score: number;
+  snippets: SearchSnippet[];
+}
+
+export interface SearchResults {
+  root: string;
+  indexed: number;
+  hits: SearchHit[];
+}
+
+/** Pause in typing before a query is sent */
+const SEARCH_DELAY_MS = 300;
+
+/** Full-text search over the markdown files under `root`, run as the query is typed */
+export function useFullTextSearch(root: string | null) {
+  const [query, setQuery] = useState("");
+  const [results, setResults] = useState<SearchResults | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__ || !root || !query.trim()) {
+      setResults(null);
+      setError(null);
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(async () => {
+      try {
+        const found = await invoke<SearchResults>("search_markdown", { root, query });
+        if (!cancelled) {
+          setResults(found);
+          setError(null);
+        }
+      } catch (e) {
+        if (!cancelled) {
+          setError(String(e));
+        }
+      }
+    }, SEARCH_DELAY_MS);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [root, query]);
+
+  return { query, setQuery, results, error };
+}
@@ -1,18 +1,143 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { builtinThemes, BuiltinThemeName } from "../constants/themes";
+import { builtinHighlightPalettes, builtinThemes, BuiltinThemeName } from "../constants/themes";
+import type { ThemeInfo } from "./useThemeRegistry";
 
 export interface CustomTheme {
   name: string;
   css: string;
 }
 
+export interface ArticleMeta {
+  title?: string | null;
+  author?: string | null;
+  digest?: string | null;
+  cover?: string | null;
+  tags: string[];
+  original_url?: string | null;
+  date?: string | null;
+  extra: Record<string, unknown>;
+}
+
+export interface OutlineItem {
+  level: number;
+  text: string;
+  anchor: string;
+  line: number;
+}
+
+export interface DocumentStats {
+  characters: number;
+  words: number;
+  reading_minutes: number;
+}
+
+export interface ImageInfo {
+  src: string;
+  alt: string;
+  title: string;
+  kind: "remote" | "local" | "data";
+  resolved: string | null;
+  line: number;
+}
+
+export interface LinkInfo {
+  url: string;
+  text: string;
+  title: string;
+  line: number;
+}
+
+export interface ConvertWarning {
+  kind: string;
+  message: string;
+  line: number;
+}
+
+export interface ConvertMarkdownResult {
+  html: string;
+  meta: ArticleMeta | null;
+  outline: OutlineItem[];
+  stats: DocumentStats;
+  images: ImageInfo[];
+  links: LinkInfo[];
+  warnings: ConvertWarning[];
+}
+
+export interface LintDiagnostic {
+  rule: string;
+  severity: "error" | "warning" | "info";
+  message: string;
+  line: number;
+  snippet: string;
+}
+
+export interface ElementStyleTrace {
+  element: string;
+  line: number;
+  properties: { property: string; value: string; layer: "inline" | "theme" | "fallback" }[];
+}
+
+export interface ConvertOptions {
+  footnoteHeading: string;
+  footnoteAllowlist: string;
+  tocNumbered: boolean;
+  darkModeHints: boolean;
+  imagePrefix: string;
+  assetsDir: string;
+  baseDir: string | null;
+  filePath: string | null;
+}
+
+/** Arguments shared by `convert_markdown` and the commands that re-run the conversion */
+export function buildConvertRequest(
+  markdown: string,
+  currentTheme: string,
+  customTheme: CustomTheme | null,
+  themes: ThemeInfo[],
+  options: ConvertOptions,
+) {
+  const installed = themes.find((theme) => theme.name === currentTheme);
+  let css: string = installed?.css ?? builtinThemes[currentTheme as BuiltinThemeName];
+  if (!css && customTheme && currentTheme === customTheme.name) {
+    css = customTheme.css;
+  }
+  if (!css) {
+    css = builtinThemes["Default (Green)"];
+  }
+  const highlightTheme =
+    installed?.highlight ?? builtinHighlightPalettes[currentTheme as BuiltinThemeName];
+  return {
+    content: markdown,
+    css,
+    options: {
+      highlightTheme,
+      footnoteAllowlist: options.footnoteAllowlist
+        .split(/[,，\s]+/)
+        .map((d) => d.trim())
+        .filter((d) => d.length > 0),
+      footnoteHeading: options.footnoteHeading.trim() || undefined,
+      tocNumbered: options.tocNumbered,
+      darkModeHints: options.darkModeHints,
+      // Front matter `theme_*` keys are applied on top of a generated theme's tokens
+      themeTokens: installed?.tokens ?? undefined,
+      imagePrefix: options.imagePrefix.trim() || undefined,
+      assetsDir: options.assetsDir,
+      baseDir: options.baseDir ?? undefined,
+      filePath: options.filePath ?? undefined,
+    },
+  };
+}
+
 export function useMarkdownConverter(
   markdown: string,
   currentTheme: string,
   customTheme: CustomTheme | null,
+  themes: ThemeInfo[],
+  options: ConvertOptions,
 ) {
   const [rawHtml, setRawHtml] = useState<string>("");
+  const [result, setResult] = useState<ConvertMarkdownResult | null>(null);
 
   useEffect(() => {
     let mounted = true;
@@ -20,19 +145,13 @@ export function useMarkdownConverter(
       try {
         // @ts-ignore
         if (window.__TAURI_INTERNALS__) {
-          let css = builtinThemes[currentTheme as BuiltinThemeName];
-          if (!css && customTheme && currentTheme === customTheme.name) {
-            css = customTheme.css;
-          }
-          if (!css) {
-            css = builtinThemes["Default (Green)"];
-          }
-          const result = await invoke<string>("convert_markdown", {
-            content: markdown,
-            css,
-          });
+          const converted = await invoke<ConvertMarkdownResult>(
+            "convert_markdown",
+            buildConvertRequest(markdown, currentTheme, customTheme, themes, options),
+          );
           if (mounted) {
-            setRawHtml(result);
+            setRawHtml(converted.html);
+            setResult(converted);
           }
         } else if (mounted) {
           console.warn("Tauri API not available. Running in browser mode?");
@@ -50,7 +169,20 @@ export function useMarkdownConverter(
     return () => {
       mounted = false;
     };
-  }, [markdown, currentTheme, customTheme]);
+  }, [
+    markdown,
+    currentTheme,
+    customTheme,
+    themes,
+    options.footnoteHeading,
+    options.footnoteAllowlist,
+    options.tocNumbered,
+    options.darkModeHints,
+    options.imagePrefix,
+    options.assetsDir,
+    options.baseDir,
+    options.filePath,
+  ]);
 
-  return rawHtml;
+  return { rawHtml, result };
 }
@@ -1,9 +1,8 @@
 import { useEffect, useState } from "react";
-import { applyImagePrefix, convertLocalImagePaths } from "../utils/images";
+import { convertLocalImagePaths } from "../utils/images";
 
 export function useProcessedHtml(
   rawHtml: string,
-  imagePrefix: string,
   currentFilePath: string | null,
   assetsDir: string,
 ) {
@@ -15,8 +14,8 @@ export function useProcessedHtml(
       const baseDir = currentFilePath.replace(/[^\\/]*$/, "");
       processedHtml = convertLocalImagePaths(rawHtml, baseDir, assetsDir);
     }
-    setHtml(applyImagePrefix(processedHtml, imagePrefix, assetsDir));
-  }, [rawHtml, imagePrefix, currentFilePath, assetsDir]);
+    setHtml(processedHtml);
+  }, [rawHtml, currentFilePath, assetsDir]);
 
   return html;
 }
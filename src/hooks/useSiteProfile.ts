@@ -0,0 +1,38 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+export interface SiteProfileInfo {
+  generator: "plain" | "hexo" | "hugo" | "jekyll";
+  /** Folder holding the generator config */
+  root: string | null;
+  /** Published address of the site root, without a trailing slash */
+  baseUrl: string | null;
+  staticDirs: string[];
+  postAssetFolder: boolean;
+}
+
+/** Hexo, Hugo or Jekyll site detected from the config above the open file */
+export function useSiteProfile(filePath: string | null, sitePrefix: string) {
+  const [profile, setProfile] = useState<SiteProfileInfo | null>(null);
+
+  useEffect(() => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__ || !filePath) {
+      setProfile(null);
+      return;
+    }
+    let cancelled = false;
+    invoke<SiteProfileInfo>("get_site_profile", { filePath, sitePrefix })
+      .then((found) => {
+        if (!cancelled) {
+          setProfile(found);
+        }
+      })
+      .catch((e) => console.error("Failed to detect site profile", e));
+    return () => {
+      cancelled = true;
+    };
+  }, [filePath, sitePrefix]);
+
+  return profile;
+}
@@ -0,0 +1,106 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+export interface ThemeInfo {
+  id: string;
+  name: string;
+  author: string | null;
+  version: string | null;
+  description: string | null;
+  base: string | null;
+  highlight: string | null;
+  builtin: boolean;
+  /** Tokens of a theme made by the generator */
+  tokens: ThemeTokens | null;
+  /** Full CSS with the base themes layered underneath */
+  css: string;
+  path: string | null;
+}
+
+export interface ContrastCheck {
+  selector: string;
+  color: string;
+  background: string;
+  ratio: number;
+  large_text: boolean;
+  required: number;
+  passes_aa: boolean;
+  passes_aaa: boolean;
+  suggested_color: string | null;
+  suggested_background: string | null;
+}
+
+export interface ContrastReport {
+  checks: ContrastCheck[];
+  failures: number;
+}
+
+export interface ThemeImportResult {
+  theme: ThemeInfo;
+  unmapped: { selector: string; reason: string }[];
+}
+
+/** Design tokens a generated theme is built from */
+export interface ThemeTokens {
+  primaryColor: string;
+  fontSize: number;
+  lineHeight: number;
+  headingStyle: "underline" | "left-bar" | "filled" | "centered" | "plain";
+  codeStyle: "light" | "dark" | "bordered";
+  quoteStyle: "bar" | "card" | "italic";
+}
+
+export const DEFAULT_THEME_TOKENS: ThemeTokens = {
+  primaryColor: "#07c160",
+  fontSize: 16,
+  lineHeight: 1.75,
+  headingStyle: "left-bar",
+  codeStyle: "light",
+  quoteStyle: "bar",
+};
+
+/** Built-in and installed themes, refreshed whenever the themes folder changes */
+export function useThemeRegistry() {
+  const [themes, setThemes] = useState<ThemeInfo[]>([]);
+
+  const refresh = useCallback(async () => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__) {
+      return;
+    }
+    try {
+      setThemes(await invoke<ThemeInfo[]>("list_themes"));
+    } catch (e) {
+      console.error("Failed to load themes", e);
+    }
+  }, []);
+
+  useEffect(() => {
+    refresh();
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__) {
+      return;
+    }
+    const unlisten = listen("themes-changed", () => {
+      refresh();
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [refresh]);
+
+  /** Pick a theme file in a native dialog and install it; null when cancelled */
+  const installTheme = async () => {
+    const theme = await invoke<ThemeInfo | null>("install_theme");
+    await refresh();
+    return theme;
+  };
+
+  const removeTheme = async (id: string) => {
+    await invoke("remove_theme", { id });
+    await refresh();
+  };
+
+  return { themes, refresh, installTheme, removeTheme };
+}
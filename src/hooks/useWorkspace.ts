@@ -0,0 +1,37 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+/** Folders the backend lets file commands read and write */
+export function useWorkspace() {
+  const [roots, setRoots] = useState<string[]>([]);
+
+  const refresh = useCallback(async () => {
+    // @ts-ignore
+    if (!window.__TAURI_INTERNALS__) {
+      return;
+    }
+    try {
+      setRoots(await invoke<string[]>("list_workspace_roots"));
+    } catch (e) {
+      console.error("Failed to load workspace roots", e);
+    }
+  }, []);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  /** Ask the user for a folder in a native dialog; `null` when cancelled */
+  const addRoot = async () => {
+    const root = await invoke<string | null>("add_workspace_root");
+    await refresh();
+    return root;
+  };
+
+  const removeRoot = async (path: string) => {
+    await invoke("remove_workspace_root", { path });
+    await refresh();
+  };
+
+  return { roots, refresh, addRoot, removeRoot };
+}